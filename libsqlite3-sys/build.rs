@@ -302,6 +302,9 @@ mod build_bundled {
         if cfg!(feature = "column_metadata") {
             cfg.flag("-DSQLITE_ENABLE_COLUMN_METADATA");
         }
+        if cfg!(feature = "stmt_scanstatus") {
+            cfg.flag("-DSQLITE_ENABLE_STMT_SCANSTATUS");
+        }
         if cfg!(feature = "preupdate_hook") {
             cfg.flag("-DSQLITE_ENABLE_PREUPDATE_HOOK");
         }
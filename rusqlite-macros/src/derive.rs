@@ -0,0 +1,423 @@
+//! `FromRow`, `Params`, `ToSql` and `FromSql` derive macros
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{
+    Attribute, Data, DataEnum, DeriveInput, Error, Field, Fields, Ident, LitStr, Result,
+    WherePredicate, parse_quote,
+};
+
+/// `#[rusqlite(...)]` field attributes
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<LitStr>,
+    default: bool,
+    flatten: bool,
+    skip: bool,
+}
+
+impl FieldAttrs {
+    /// Parse attributes, rejecting those not in `supported` (by `derive`)
+    fn parse(attrs: &[Attribute], supported: &[&str], derive: &str) -> Result<Self> {
+        let mut res = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("rusqlite")) {
+            attr.parse_nested_meta(|meta| {
+                if let Some(ident) = meta.path.get_ident()
+                    && !supported.iter().any(|s| ident == s)
+                {
+                    return Err(meta.error(format!("`{ident}` is not supported by {derive}")));
+                }
+                if meta.path.is_ident("rename") {
+                    res.rename = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    res.default = true;
+                } else if meta.path.is_ident("flatten") {
+                    res.flatten = true;
+                } else if meta.path.is_ident("skip") {
+                    res.skip = true;
+                } else {
+                    return Err(meta.error("unsupported rusqlite attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(res)
+    }
+}
+
+fn struct_fields<'a>(input: &'a DeriveInput, derive: &str) -> Result<&'a Fields> {
+    match input.data {
+        Data::Struct(ref s) => Ok(&s.fields),
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            format!("{derive} can only be derived for structs"),
+        )),
+    }
+}
+
+/// Bounds on field types are only needed (and only added) for generic structs:
+/// they would trigger `private_bounds` warnings for private field types.
+fn where_clause(input: &DeriveInput, bounds: Vec<WherePredicate>) -> TokenStream {
+    let mut where_clause = input.generics.where_clause.clone();
+    if input.generics.type_params().next().is_some() {
+        where_clause
+            .get_or_insert_with(|| parse_quote!(where))
+            .predicates
+            .extend(bounds);
+    }
+    quote!(#where_clause)
+}
+
+pub fn from_row(input: &DeriveInput) -> Result<TokenStream> {
+    let fields = struct_fields(input, "FromRow")?;
+    let mut bounds: Vec<WherePredicate> = Vec::new();
+    let mut values = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = FieldAttrs::parse(
+            &field.attrs,
+            &["rename", "default", "flatten", "skip"],
+            "FromRow",
+        )?;
+        let ty = &field.ty;
+        let value = if attrs.skip {
+            bounds.push(parse_quote!(#ty: ::std::default::Default));
+            quote!(::std::default::Default::default())
+        } else if attrs.flatten {
+            if field.ident.is_none() || attrs.rename.is_some() || attrs.default {
+                return Err(Error::new_spanned(
+                    field,
+                    "`flatten` is only supported on named fields, without `rename` or `default`",
+                ));
+            }
+            bounds.push(parse_quote!(#ty: ::rusqlite::FromRow));
+            quote!(<#ty as ::rusqlite::FromRow>::from_row(row)?)
+        } else {
+            let idx = match (attrs.rename, &field.ident) {
+                (Some(name), _) => quote!(#name),
+                (None, Some(ident)) => {
+                    let name = ident.unraw().to_string();
+                    quote!(#name)
+                }
+                (None, None) => quote!(#i),
+            };
+            bounds.push(parse_quote!(#ty: ::rusqlite::types::FromSql));
+            if attrs.default {
+                bounds.push(parse_quote!(#ty: ::std::default::Default));
+                quote! {
+                    match row.get::<_, #ty>(#idx) {
+                        ::std::result::Result::Err(
+                            ::rusqlite::Error::InvalidColumnName(_)
+                            | ::rusqlite::Error::InvalidColumnIndex(_),
+                        ) => ::std::default::Default::default(),
+                        r => r?,
+                    }
+                }
+            } else {
+                quote!(row.get::<_, #ty>(#idx)?)
+            }
+        };
+        values.push(value);
+    }
+    let body = match fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|f| &f.ident);
+            quote!(Self { #(#names: #values,)* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#values,)*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = where_clause(input, bounds);
+    Ok(quote! {
+        impl #impl_generics ::rusqlite::FromRow for #ident #ty_generics #where_clause {
+            fn from_row(row: &::rusqlite::Row<'_>) -> ::rusqlite::Result<Self> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+pub fn params(input: &DeriveInput) -> Result<TokenStream> {
+    let fields = struct_fields(input, "Params")?;
+    let mut bounds: Vec<WherePredicate> = Vec::new();
+    let mut binds = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = FieldAttrs::parse(&field.attrs, &["rename", "flatten", "skip"], "Params")?;
+        if attrs.skip {
+            continue;
+        }
+        let ty = &field.ty;
+        let member = match field.ident {
+            Some(ref ident) => quote!(#ident),
+            None => {
+                let i = syn::Index::from(i);
+                quote!(#i)
+            }
+        };
+        if attrs.flatten {
+            if attrs.rename.is_some() {
+                return Err(Error::new_spanned(
+                    field,
+                    "`flatten` cannot be combined with `rename`",
+                ));
+            }
+            bounds.push(parse_quote!(for<'__p> &'__p #ty: ::rusqlite::Params));
+            binds.push(quote!(::rusqlite::Params::__bind_in(&self.#member, stmt)?;));
+            continue;
+        }
+        let name = match (attrs.rename, &field.ident) {
+            (Some(name), _) => name.value(),
+            (None, Some(ident)) => ident.unraw().to_string(),
+            (None, None) => {
+                let i = i + 1;
+                bounds.push(parse_quote!(#ty: ::rusqlite::ToSql));
+                binds.push(quote!(stmt.raw_bind_parameter(#i, &self.#member)?;));
+                continue;
+            }
+        };
+        let idx = if name.starts_with([':', '@', '$']) {
+            name
+        } else {
+            format!(":{name}")
+        };
+        bounds.push(parse_quote!(#ty: ::rusqlite::ToSql));
+        binds.push(quote!(stmt.raw_bind_parameter(#idx, &self.#member)?;));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = where_clause(input, bounds);
+    Ok(quote! {
+        impl #impl_generics ::rusqlite::__DeriveParams for #ident #ty_generics #where_clause {
+            fn __bind_in(&self, stmt: &mut ::rusqlite::Statement<'_>) -> ::rusqlite::Result<()> {
+                #(#binds)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+/// How a fieldless enum is stored
+enum Repr {
+    /// Variant name (or `rename`), as TEXT
+    Text,
+    /// Discriminant, as INTEGER
+    Integer,
+}
+
+impl Repr {
+    /// `#[rusqlite(repr = "text" | "integer")]` container attribute
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut res = Self::Text;
+        for attr in attrs.iter().filter(|a| a.path().is_ident("rusqlite")) {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("repr") {
+                    return Err(meta.error("unsupported rusqlite attribute"));
+                }
+                let repr: LitStr = meta.value()?.parse()?;
+                res = match repr.value().as_str() {
+                    "text" => Self::Text,
+                    "integer" => Self::Integer,
+                    _ => return Err(Error::new_spanned(repr, "expected \"text\" or \"integer\"")),
+                };
+                Ok(())
+            })?;
+        }
+        Ok(res)
+    }
+}
+
+/// Variants of a fieldless enum, with their TEXT representation
+fn variants(data: &DataEnum) -> Result<Vec<(&Ident, String)>> {
+    data.variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new_spanned(
+                    variant,
+                    "only fieldless enum variants are supported",
+                ));
+            }
+            let attrs = FieldAttrs::parse(&variant.attrs, &["rename"], "enum variants")?;
+            let name = match attrs.rename {
+                Some(name) => name.value(),
+                None => variant.ident.unraw().to_string(),
+            };
+            Ok((&variant.ident, name))
+        })
+        .collect()
+}
+
+/// The field of a newtype
+fn newtype_field<'a>(input: &'a DeriveInput, derive: &str) -> Result<&'a Field> {
+    let field = match input.data {
+        Data::Struct(ref s) if s.fields.len() == 1 => s.fields.iter().next().unwrap(),
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                format!(
+                    "{derive} can only be derived for fieldless enums and single-field structs"
+                ),
+            ));
+        }
+    };
+    // `repr` and `rename` only apply to enums
+    if let Some(attr) = input
+        .attrs
+        .iter()
+        .chain(&field.attrs)
+        .find(|a| a.path().is_ident("rusqlite"))
+    {
+        return Err(Error::new_spanned(
+            attr,
+            format!("rusqlite attributes are not supported by {derive} on structs"),
+        ));
+    }
+    Ok(field)
+}
+
+pub fn to_sql(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let (body, bounds) = if let Data::Enum(ref data) = input.data {
+        let variants = variants(data)?;
+        let repr = Repr::parse(&input.attrs)?;
+        let arms = variants.iter().map(|(variant, name)| match repr {
+            Repr::Text => quote!(Self::#variant => #name.into()),
+            Repr::Integer => quote!(Self::#variant => (Self::#variant as i64).into()),
+        });
+        let body = quote! {
+            ::std::result::Result::Ok(match *self {
+                #(#arms,)*
+            })
+        };
+        (body, Vec::new())
+    } else {
+        let field = newtype_field(input, "ToSql")?;
+        let member = match field.ident {
+            Some(ref ident) => quote!(#ident),
+            None => quote!(0),
+        };
+        let ty = &field.ty;
+        let body = quote!(::rusqlite::types::ToSql::to_sql(&self.#member));
+        (body, vec![parse_quote!(#ty: ::rusqlite::types::ToSql)])
+    };
+    let where_clause = where_clause(input, bounds);
+    Ok(quote! {
+        impl #impl_generics ::rusqlite::types::ToSql for #ident #ty_generics #where_clause {
+            #[inline]
+            fn to_sql(&self) -> ::rusqlite::Result<::rusqlite::types::ToSqlOutput<'_>> {
+                #body
+            }
+        }
+    })
+}
+
+pub fn from_sql(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let (body, bounds) = if let Data::Enum(ref data) = input.data {
+        let variants = variants(data)?;
+        let unknown = format!("invalid value {{:?}} for enum {ident}");
+        let body = match Repr::parse(&input.attrs)? {
+            Repr::Text => {
+                let arms = variants.iter().map(
+                    |(variant, name)| quote!(#name => ::std::result::Result::Ok(Self::#variant)),
+                );
+                quote! {
+                    match value.as_str()? {
+                        #(#arms,)*
+                        s => ::std::result::Result::Err(::rusqlite::types::FromSqlError::Other(
+                            ::std::format!(#unknown, s).into(),
+                        )),
+                    }
+                }
+            }
+            Repr::Integer => {
+                let arms = variants.iter().map(|(variant, _)| {
+                    quote!(i if i == Self::#variant as i64 => ::std::result::Result::Ok(Self::#variant))
+                });
+                quote! {
+                    match value.as_i64()? {
+                        #(#arms,)*
+                        i => ::std::result::Result::Err(::rusqlite::types::FromSqlError::Other(
+                            ::std::format!(#unknown, i).into(),
+                        )),
+                    }
+                }
+            }
+        };
+        (body, Vec::new())
+    } else {
+        let field = newtype_field(input, "FromSql")?;
+        let ty = &field.ty;
+        let body = match field.ident {
+            Some(ref ident) => quote! {
+                <#ty as ::rusqlite::types::FromSql>::column_result(value).map(|#ident| Self { #ident })
+            },
+            None => quote!(<#ty as ::rusqlite::types::FromSql>::column_result(value).map(Self)),
+        };
+        (body, vec![parse_quote!(#ty: ::rusqlite::types::FromSql)])
+    };
+    let where_clause = where_clause(input, bounds);
+    Ok(quote! {
+        impl #impl_generics ::rusqlite::types::FromSql for #ident #ty_generics #where_clause {
+            #[inline]
+            fn column_result(
+                value: ::rusqlite::types::ValueRef<'_>,
+            ) -> ::rusqlite::types::FromSqlResult<Self> {
+                #body
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use syn::parse_quote;
+
+    #[test]
+    fn test_unsupported_attrs() {
+        let err = super::params(&parse_quote! {
+            struct Person {
+                #[rusqlite(default)]
+                age: u32,
+            }
+        })
+        .unwrap_err();
+        assert_eq!("`default` is not supported by Params", err.to_string());
+        assert!(
+            super::from_row(&parse_quote! {
+                struct Person {
+                    #[rusqlite(default)]
+                    age: u32,
+                }
+            })
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_unsupported_enum_attrs() {
+        let err = super::to_sql(&parse_quote! {
+            enum Status {
+                #[rusqlite(skip)]
+                Active,
+            }
+        })
+        .unwrap_err();
+        assert_eq!("`skip` is not supported by enum variants", err.to_string());
+        let err = super::from_sql(&parse_quote! {
+            #[rusqlite(repr = "integer")]
+            struct UserId(i64);
+        })
+        .unwrap_err();
+        assert_eq!(
+            "rusqlite attributes are not supported by FromSql on structs",
+            err.to_string()
+        );
+    }
+}
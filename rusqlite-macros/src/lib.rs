@@ -9,6 +9,54 @@ use sqlite3_parser::ast::ParameterInfo;
 use sqlite3_parser::ast::fmt::ToTokens;
 use sqlite3_parser::lexer::sql::Parser;
 
+mod derive;
+
+/// Derive `rusqlite::FromRow` for a struct.
+///
+/// See `rusqlite::FromRow` for the supported `#[rusqlite(...)]` attributes.
+#[proc_macro_derive(FromRow, attributes(rusqlite))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive::from_row(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `rusqlite::Params` for references to a struct.
+///
+/// See `rusqlite::Params` for the supported `#[rusqlite(...)]` attributes.
+#[proc_macro_derive(Params, attributes(rusqlite))]
+pub fn derive_params(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive::params(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `rusqlite::types::ToSql` for a fieldless enum or a newtype.
+///
+/// See `rusqlite::types::ToSql` for the supported `#[rusqlite(...)]`
+/// attributes.
+#[proc_macro_derive(ToSql, attributes(rusqlite))]
+pub fn derive_to_sql(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive::to_sql(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `rusqlite::types::FromSql` for a fieldless enum or a newtype.
+///
+/// See `rusqlite::types::FromSql` for the supported `#[rusqlite(...)]`
+/// attributes.
+#[proc_macro_derive(FromSql, attributes(rusqlite))]
+pub fn derive_from_sql(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive::from_sql(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 // https://internals.rust-lang.org/t/custom-error-diagnostics-with-procedural-macros-on-almost-stable-rust/8113
 
 #[doc(hidden)]
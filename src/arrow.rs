@@ -0,0 +1,584 @@
+//! Export query results as [Apache Arrow](https://arrow.apache.org)
+//! [`RecordBatch`]es.
+//!
+//! Values are appended straight from SQLite to Arrow builders: TEXT and BLOB
+//! values are copied once, into the Arrow buffers, without intermediate
+//! [`Value`](crate::types::Value).
+//!
+//! The Arrow type of each column is, in order of precedence:
+//! 1. the one given with [`ArrowBatches::with_data_type`],
+//! 2. derived from the declared type of the column, following SQLite
+//!    [type affinity](https://sqlite.org/datatype3.html#determination_of_column_affinity)
+//!    rules (INTEGER => `Int64`, REAL => `Float64`, TEXT => `Utf8`, BLOB =>
+//!    `Binary`),
+//! 3. inferred from the first non-NULL value of the column in the first
+//!    batch (an `Int64` column becomes `Float64` if a REAL value follows in
+//!    the first batch), or `Null` if there is none.
+//!
+//! All fields are nullable. Values that do not match the type of their column
+//! fail like [`Row::get`](crate::Row::get).
+//!
+//! ```rust,no_run
+//! # use rusqlite::{Connection, Result};
+//! # use arrow_schema::DataType;
+//! fn total_rows(conn: &Connection) -> Result<usize> {
+//!     let mut stmt = conn.prepare("SELECT id, name, price FROM products")?;
+//!     let batches = stmt
+//!         .query_arrow([], 8192)?
+//!         .with_data_type("name", DataType::Utf8View)?;
+//!     let mut n = 0;
+//!     for batch in batches {
+//!         n += batch?.num_rows();
+//!     }
+//!     Ok(n)
+//! }
+//! ```
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    ArrayBuilder, BinaryBuilder, BinaryViewBuilder, BooleanBuilder, Float32Builder, Float64Builder,
+    Int8Builder, Int16Builder, Int32Builder, Int64Builder, LargeBinaryBuilder, LargeStringBuilder,
+    NullBuilder, StringBuilder, StringViewBuilder, UInt8Builder, UInt16Builder, UInt32Builder,
+    UInt64Builder,
+};
+use arrow_array::cast::AsArray as _;
+use arrow_array::types::Int64Type;
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+
+use crate::column::Affinity;
+use crate::types::{FromSqlError, Type};
+use crate::{Error, Params, Result, Row, RowIndex, Rows, Statement, ffi};
+
+impl Statement<'_> {
+    /// Execute the prepared statement, returning an iterator over its result
+    /// as Arrow [`RecordBatch`]es of at most `batch_size` rows.
+    ///
+    /// See the [module documentation](crate::arrow) for the Arrow type of
+    /// each column.
+    ///
+    /// ## Failure
+    ///
+    /// Will return `Err` if binding parameters fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn query_arrow<P: Params>(
+        &mut self,
+        params: P,
+        batch_size: usize,
+    ) -> Result<ArrowBatches<'_>> {
+        assert!(batch_size > 0, "batch_size must be positive");
+        let columns = self
+            .columns()
+            .map(|column| {
+                let data_type = match column.decl_type().map(Affinity::of) {
+                    Some(Affinity::Integer) => Some(DataType::Int64),
+                    Some(Affinity::Real) => Some(DataType::Float64),
+                    Some(Affinity::Text) => Some(DataType::Utf8),
+                    Some(Affinity::Blob) if !column.decl_type().unwrap().is_empty() => {
+                        Some(DataType::Binary)
+                    }
+                    _ => None,
+                };
+                ColumnBuilder::new(column.name().to_owned(), data_type, batch_size)
+            })
+            .collect();
+        let rows = self.query(params)?;
+        Ok(ArrowBatches {
+            rows,
+            batch_size,
+            columns,
+            schema: None,
+            done: false,
+        })
+    }
+}
+
+/// Iterator over the result of a query as Arrow [`RecordBatch`]es, see
+/// [`Statement::query_arrow`].
+#[must_use = "ArrowBatches is lazy and will do nothing unless consumed"]
+pub struct ArrowBatches<'stmt> {
+    rows: Rows<'stmt>,
+    batch_size: usize,
+    columns: Vec<ColumnBuilder>,
+    schema: Option<SchemaRef>,
+    done: bool,
+}
+
+impl ArrowBatches<'_> {
+    /// Override the Arrow type of a column.
+    ///
+    /// Supported types are `Null`, `Boolean`, `Int8` to `Int64`, `UInt8` to
+    /// `UInt64`, `Float32`, `Float64`, `Utf8`, `LargeUtf8`, `Utf8View`,
+    /// `Binary`, `LargeBinary` and `BinaryView`.
+    ///
+    /// ## Failure
+    ///
+    /// Will return `Err` if `idx` is not a valid column, if `data_type` is
+    /// not supported or if a batch has already been returned.
+    pub fn with_data_type<I: RowIndex>(mut self, idx: I, data_type: DataType) -> Result<Self> {
+        let stmt = self.rows.as_ref().ok_or(Error::InvalidQuery)?;
+        let idx = idx.idx(stmt)?;
+        if self.schema.is_some() {
+            return Err(err!(
+                ffi::SQLITE_MISUSE,
+                "Arrow data type overridden after the first batch"
+            ));
+        }
+        let column = &mut self.columns[idx];
+        let Some(builder) = builder(&data_type, self.batch_size) else {
+            return Err(err!(
+                ffi::SQLITE_MISUSE,
+                "Unsupported Arrow data type {data_type} for column {}",
+                column.name,
+            ));
+        };
+        column.inferred = false;
+        column.builder = Builder::Typed(data_type, builder);
+        Ok(self)
+    }
+
+    /// Schema of the batches, known once the first batch has been returned,
+    /// or once the iterator is exhausted for a query without rows (with the
+    /// declared or overridden types, and `Null` for the other columns).
+    #[must_use]
+    pub fn schema(&self) -> Option<SchemaRef> {
+        self.schema.clone()
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let first_batch = self.schema.is_none();
+        let mut len = 0;
+        while len < self.batch_size {
+            let Some(row) = self.rows.next()? else {
+                break;
+            };
+            for (idx, column) in self.columns.iter_mut().enumerate() {
+                column.append(row, idx, first_batch, self.batch_size)?;
+            }
+            len += 1;
+        }
+        let schema = match self.schema {
+            Some(ref schema) => schema.clone(),
+            None => {
+                let fields: Vec<_> = self
+                    .columns
+                    .iter_mut()
+                    .map(|column| {
+                        let data_type = column.data_type().clone();
+                        Field::new(&column.name, data_type, true)
+                    })
+                    .collect();
+                self.schema.insert(Arc::new(Schema::new(fields))).clone()
+            }
+        };
+        if len == 0 {
+            return Ok(None);
+        }
+        let arrays = self.columns.iter_mut().map(ColumnBuilder::finish).collect();
+        let options = RecordBatchOptions::new().with_row_count(Some(len));
+        RecordBatch::try_new_with_options(schema, arrays, &options)
+            .map(Some)
+            .map_err(|err| FromSqlError::other(err).into())
+    }
+}
+
+impl Iterator for ArrowBatches<'_> {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let batch = self.next_batch().transpose();
+        if !matches!(batch, Some(Ok(_))) {
+            self.done = true;
+        }
+        batch
+    }
+}
+
+struct ColumnBuilder {
+    name: String,
+    /// Whether the type is inferred from values
+    inferred: bool,
+    builder: Builder,
+}
+
+enum Builder {
+    /// Type not known yet, with the number of leading NULLs
+    Pending(usize),
+    Typed(DataType, Box<dyn Append>),
+}
+
+impl ColumnBuilder {
+    fn new(name: String, data_type: Option<DataType>, capacity: usize) -> Self {
+        let builder = match data_type {
+            Some(data_type) => {
+                let builder = builder(&data_type, capacity).unwrap();
+                Builder::Typed(data_type, builder)
+            }
+            None => Builder::Pending(0),
+        };
+        Self {
+            name,
+            inferred: matches!(builder, Builder::Pending(_)),
+            builder,
+        }
+    }
+
+    fn append(
+        &mut self,
+        row: &Row<'_>,
+        idx: usize,
+        first_batch: bool,
+        capacity: usize,
+    ) -> Result<()> {
+        match self.builder {
+            Builder::Pending(ref mut nulls) => {
+                let data_type = match row.get_ref(idx)?.data_type() {
+                    Type::Null => {
+                        *nulls += 1;
+                        return Ok(());
+                    }
+                    Type::Integer => DataType::Int64,
+                    Type::Real => DataType::Float64,
+                    Type::Text => DataType::Utf8,
+                    Type::Blob => DataType::Binary,
+                };
+                let mut builder = builder(&data_type, capacity).unwrap();
+                for _ in 0..*nulls {
+                    builder.append_null();
+                }
+                self.builder = Builder::Typed(data_type, builder);
+            }
+            Builder::Typed(DataType::Int64, ref mut builder)
+                if self.inferred && first_batch && row.get_ref(idx)?.data_type() == Type::Real =>
+            {
+                let ints = builder.finish();
+                let mut floats = Float64Builder::with_capacity(capacity);
+                floats.extend(
+                    ints.as_primitive::<Int64Type>()
+                        .iter()
+                        .map(|i| i.map(|i| i as f64)),
+                );
+                self.builder = Builder::Typed(DataType::Float64, Box::new(floats));
+            }
+            Builder::Typed(..) => {}
+        }
+        self.builder_mut().append(row, idx)
+    }
+
+    /// Data type, `Null` if still unknown
+    fn data_type(&mut self) -> &DataType {
+        if let Builder::Pending(nulls) = self.builder {
+            let mut builder = NullBuilder::new();
+            builder.append_nulls(nulls);
+            self.builder = Builder::Typed(DataType::Null, Box::new(builder));
+        }
+        match self.builder {
+            Builder::Typed(ref data_type, _) => data_type,
+            Builder::Pending(_) => unreachable!(),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let array = self.builder_mut().finish();
+        if let Builder::Typed(DataType::Null, ref mut builder) = self.builder {
+            // `NullBuilder::finish` does not reset its length
+            *builder = Box::new(NullBuilder::new());
+        }
+        array
+    }
+
+    fn builder_mut(&mut self) -> &mut dyn Append {
+        match self.builder {
+            Builder::Typed(_, ref mut builder) => builder.as_mut(),
+            Builder::Pending(_) => unreachable!(),
+        }
+    }
+}
+
+/// Arrow builder fed from SQLite values
+trait Append: ArrayBuilder {
+    fn append(&mut self, row: &Row<'_>, idx: usize) -> Result<()>;
+    fn append_null(&mut self);
+}
+
+fn builder(data_type: &DataType, capacity: usize) -> Option<Box<dyn Append>> {
+    Some(match data_type {
+        DataType::Null => Box::new(NullBuilder::new()),
+        DataType::Boolean => Box::new(BooleanBuilder::with_capacity(capacity)),
+        DataType::Int8 => Box::new(Int8Builder::with_capacity(capacity)),
+        DataType::Int16 => Box::new(Int16Builder::with_capacity(capacity)),
+        DataType::Int32 => Box::new(Int32Builder::with_capacity(capacity)),
+        DataType::Int64 => Box::new(Int64Builder::with_capacity(capacity)),
+        DataType::UInt8 => Box::new(UInt8Builder::with_capacity(capacity)),
+        DataType::UInt16 => Box::new(UInt16Builder::with_capacity(capacity)),
+        DataType::UInt32 => Box::new(UInt32Builder::with_capacity(capacity)),
+        DataType::UInt64 => Box::new(UInt64Builder::with_capacity(capacity)),
+        DataType::Float32 => Box::new(Float32Builder::with_capacity(capacity)),
+        DataType::Float64 => Box::new(Float64Builder::with_capacity(capacity)),
+        DataType::Utf8 => Box::new(StringBuilder::new()),
+        DataType::LargeUtf8 => Box::new(LargeStringBuilder::new()),
+        DataType::Utf8View => Box::new(StringViewBuilder::new()),
+        DataType::Binary => Box::new(BinaryBuilder::new()),
+        DataType::LargeBinary => Box::new(LargeBinaryBuilder::new()),
+        DataType::BinaryView => Box::new(BinaryViewBuilder::new()),
+        _ => return None,
+    })
+}
+
+impl Append for NullBuilder {
+    fn append(&mut self, row: &Row<'_>, idx: usize) -> Result<()> {
+        match row.get_ref(idx)?.data_type() {
+            Type::Null => {
+                self.append_null();
+                Ok(())
+            }
+            t => Err(Error::InvalidColumnType(
+                idx,
+                row.as_ref().column_name_unwrap(idx).to_owned(),
+                t,
+            )),
+        }
+    }
+
+    fn append_null(&mut self) {
+        Self::append_null(self);
+    }
+}
+
+macro_rules! append_integer(
+    ($($builder:ty),*) => {$(
+        impl Append for $builder {
+            fn append(&mut self, row: &Row<'_>, idx: usize) -> Result<()> {
+                let value = match row.get::<_, Option<i64>>(idx)? {
+                    Some(i) => Some(i.try_into().map_err(|_| Error::IntegralValueOutOfRange(idx, i))?),
+                    None => None,
+                };
+                self.append_option(value);
+                Ok(())
+            }
+
+            fn append_null(&mut self) {
+                Self::append_null(self);
+            }
+        }
+    )*}
+);
+
+append_integer!(Int8Builder, Int16Builder, Int32Builder);
+append_integer!(UInt8Builder, UInt16Builder, UInt32Builder, UInt64Builder);
+
+macro_rules! append_from_sql(
+    ($($builder:ty => $t:ty),*) => {$(
+        impl Append for $builder {
+            fn append(&mut self, row: &Row<'_>, idx: usize) -> Result<()> {
+                let value = row.get::<_, Option<$t>>(idx)?;
+                self.append_option(value.map(Into::into));
+                Ok(())
+            }
+
+            fn append_null(&mut self) {
+                Self::append_null(self);
+            }
+        }
+    )*}
+);
+
+append_from_sql!(BooleanBuilder => bool, Int64Builder => i64, Float64Builder => f64);
+
+impl Append for Float32Builder {
+    fn append(&mut self, row: &Row<'_>, idx: usize) -> Result<()> {
+        let value = row.get::<_, Option<f64>>(idx)?;
+        self.append_option(value.map(|f| f as f32));
+        Ok(())
+    }
+
+    fn append_null(&mut self) {
+        Self::append_null(self);
+    }
+}
+
+macro_rules! append_borrowed(
+    ($($builder:ty => $t:ty),*) => {$(
+        impl Append for $builder {
+            fn append(&mut self, row: &Row<'_>, idx: usize) -> Result<()> {
+                let value = row.get_borrowed::<_, Option<$t>>(idx)?;
+                self.append_option(value);
+                Ok(())
+            }
+
+            fn append_null(&mut self) {
+                Self::append_null(self);
+            }
+        }
+    )*}
+);
+
+append_borrowed!(
+    StringBuilder => &str,
+    LargeStringBuilder => &str,
+    StringViewBuilder => &str,
+    BinaryBuilder => &[u8],
+    LargeBinaryBuilder => &[u8],
+    BinaryViewBuilder => &[u8]
+);
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use arrow_array::cast::AsArray as _;
+    use arrow_array::types::{Float64Type, Int64Type, UInt8Type};
+    use arrow_array::{Array as _, RecordBatch};
+    use arrow_schema::DataType;
+
+    use crate::types::Type;
+    use crate::{Connection, Error, Result};
+
+    fn db() -> Result<Connection> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(
+            "CREATE TABLE foo (i INTEGER, r REAL, t TEXT, b BLOB, n);
+             INSERT INTO foo VALUES (1, 1.5, 'one', x'01', 2);
+             INSERT INTO foo VALUES (2, NULL, 'two', NULL, 2.5);
+             INSERT INTO foo VALUES (NULL, 3.5, NULL, x'0303', NULL);",
+        )?;
+        Ok(db)
+    }
+
+    #[test]
+    fn test_query_arrow() -> Result<()> {
+        let db = db()?;
+        let mut stmt = db.prepare("SELECT i, r, t, b, n, NULL AS z FROM foo")?;
+        let batches = stmt
+            .query_arrow([], 2)?
+            .collect::<Result<Vec<RecordBatch>>>()?;
+        assert_eq!(2, batches.len());
+        assert_eq!(2, batches[0].num_rows());
+        assert_eq!(1, batches[1].num_rows());
+        assert_eq!(batches[0].schema(), batches[1].schema());
+        let types: Vec<_> = batches[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.data_type().clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("i".to_owned(), DataType::Int64),
+                ("r".to_owned(), DataType::Float64),
+                ("t".to_owned(), DataType::Utf8),
+                ("b".to_owned(), DataType::Binary),
+                // inferred from 2 then widened by 2.5
+                ("n".to_owned(), DataType::Float64),
+                ("z".to_owned(), DataType::Null),
+            ],
+            types
+        );
+
+        let i = batches[0].column(0).as_primitive::<Int64Type>();
+        assert_eq!(vec![Some(1), Some(2)], i.iter().collect::<Vec<_>>());
+        let t = batches[0].column(2).as_string::<i32>();
+        assert_eq!(vec![Some("one"), Some("two")], t.iter().collect::<Vec<_>>());
+        let n = batches[0].column(4).as_primitive::<Float64Type>();
+        assert_eq!(vec![Some(2.0), Some(2.5)], n.iter().collect::<Vec<_>>());
+        assert!(batches[1].column(4).is_null(0));
+        let b = batches[1].column(3).as_binary::<i32>();
+        assert_eq!(b"\x03\x03", b.value(0));
+        assert_eq!(1, batches[1].column(5).len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_schema() -> Result<()> {
+        let db = db()?;
+        let mut stmt = db.prepare("SELECT i, t, n, NULL AS z FROM foo WHERE 0")?;
+        let mut batches = stmt
+            .query_arrow([], 2)?
+            .with_data_type("z", DataType::Boolean)?;
+        assert_eq!(None, batches.schema());
+        assert!(batches.next().is_none());
+        let schema = batches.schema().unwrap();
+        let types: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("i", DataType::Int64),
+                ("t", DataType::Utf8),
+                // no declared type, no value to infer the type from
+                ("n", DataType::Null),
+                ("z", DataType::Boolean),
+            ],
+            types
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_data_type() -> Result<()> {
+        let db = db()?;
+        let mut stmt = db.prepare("SELECT i, t FROM foo")?;
+        let mut batches = stmt
+            .query_arrow([], 10)?
+            .with_data_type(0, DataType::UInt8)?
+            .with_data_type("t", DataType::Utf8View)?;
+        assert!(batches.schema().is_none());
+        let batch = batches.next().unwrap()?;
+        assert!(batches.next().is_none());
+        assert_eq!(Some(batch.schema()), batches.schema());
+        let i = batch.column(0).as_primitive::<UInt8Type>();
+        assert_eq!(vec![Some(1), Some(2), None], i.iter().collect::<Vec<_>>());
+        let t = batch.column(1).as_string_view();
+        assert_eq!(
+            vec![Some("one"), Some("two"), None],
+            t.iter().collect::<Vec<_>>()
+        );
+
+        let mut stmt = db.prepare("SELECT i FROM foo")?;
+        assert!(
+            stmt.query_arrow([], 10)?
+                .with_data_type(1, DataType::Int64)
+                .is_err()
+        );
+        assert!(
+            stmt.query_arrow([], 10)?
+                .with_data_type(0, DataType::Date32)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let db = db()?;
+        let mut stmt = db.prepare("SELECT i FROM foo UNION ALL SELECT 300")?;
+        let err = stmt
+            .query_arrow([], 10)?
+            .with_data_type(0, DataType::Int8)?
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(Error::IntegralValueOutOfRange(0, 300), err);
+
+        // type inferred from the first batch
+        let mut stmt = db.prepare("SELECT n FROM foo UNION ALL SELECT 'x'")?;
+        let mut batches = stmt.query_arrow([], 3)?;
+        batches.next().unwrap()?;
+        let err = batches.next().unwrap().unwrap_err();
+        assert!(matches!(err, Error::InvalidColumnType(0, _, Type::Text)));
+        assert!(batches.next().is_none());
+
+        let mut stmt = db.prepare("SELECT * FROM foo WHERE 0")?;
+        assert!(stmt.query_arrow([], 10)?.next().is_none());
+        Ok(())
+    }
+}
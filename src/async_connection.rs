@@ -0,0 +1,447 @@
+//! Executor-agnostic async connection handle.
+//!
+//! An [`AsyncConnection`] owns a [`Connection`] on a dedicated worker
+//! thread. Closures are sent to the worker and their results are returned
+//! through futures which can be awaited on any runtime. Dropping one of these
+//! futures before completion cancels the closure if it has not started yet,
+//! or interrupts the connection (see [`InterruptHandle`]) if it is running.
+//!
+//! ```rust,no_run
+//! use rusqlite::async_connection::AsyncConnection;
+//! use rusqlite::Result;
+//!
+//! async fn count(db: &AsyncConnection) -> Result<i64> {
+//!     db.execute("INSERT INTO foo VALUES (?1)", (42,)).await?;
+//!     db.call(|conn| conn.query_row("SELECT count(*) FROM foo", [], |r| r.get(0)))
+//!         .await
+//! }
+//! ```
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::{Connection, InterruptHandle, Params, Result, Row, Transaction, ffi};
+
+enum Message {
+    Call(Box<dyn FnOnce(&mut Connection) + Send>),
+    Close(Box<dyn FnOnce(Connection) + Send>),
+}
+
+/// Handle to a [`Connection`] owned by a worker thread.
+///
+/// The connection is closed when the handle is dropped, once the closures
+/// already sent have run.
+pub struct AsyncConnection {
+    sender: Sender<Message>,
+    interrupt: Arc<InterruptHandle>,
+}
+
+impl AsyncConnection {
+    /// Move `conn` to a new worker thread.
+    #[must_use]
+    pub fn new(conn: Connection) -> Self {
+        let interrupt = Arc::new(conn.get_interrupt_handle());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || run(conn, &receiver));
+        Self { sender, interrupt }
+    }
+
+    /// Open a connection with `open` on a new worker thread.
+    ///
+    /// ```rust,no_run
+    /// # use rusqlite::{async_connection::AsyncConnection, Connection, OpenFlags, Result};
+    /// async fn open() -> Result<AsyncConnection> {
+    ///     AsyncConnection::open_with(|| Connection::open_with_flags("app.db", OpenFlags::default())).await
+    /// }
+    /// ```
+    pub fn open_with<F>(open: F) -> Call<Self>
+    where
+        F: FnOnce() -> Result<Connection> + Send + 'static,
+    {
+        let (task, call) = Task::new(open, None);
+        thread::spawn(move || {
+            let (sender, receiver) = mpsc::channel();
+            let mut conn = None;
+            task.run_with(|open| {
+                let c = open()?;
+                let interrupt = Arc::new(c.get_interrupt_handle());
+                conn = Some(c);
+                Ok(Self { sender, interrupt })
+            });
+            if let Some(conn) = conn {
+                run(conn, &receiver);
+            }
+        });
+        call
+    }
+
+    /// Open a connection to the database at `path` on a new worker thread.
+    ///
+    /// See [`Connection::open`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Call<Self> {
+        let path = path.as_ref().to_owned();
+        Self::open_with(move || Connection::open(path))
+    }
+
+    /// Open a connection to a new in-memory database on a new worker thread.
+    ///
+    /// See [`Connection::open_in_memory`].
+    pub fn open_in_memory() -> Call<Self> {
+        Self::open_with(Connection::open_in_memory)
+    }
+
+    /// Run `f` on the worker thread.
+    ///
+    /// If `f` panics, the panic is propagated to the task awaiting the
+    /// returned future.
+    pub fn call<T, F>(&self, f: F) -> Call<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let (task, call) = Task::new(f, Some(self.interrupt.clone()));
+        // If the worker is gone, the task is dropped, which completes the call
+        // with an error.
+        let _ = self
+            .sender
+            .send(Message::Call(Box::new(move |conn| task.run(conn))));
+        call
+    }
+
+    /// Convenience method to prepare and execute a single SQL statement.
+    ///
+    /// See [`Connection::execute`].
+    pub fn execute<P>(&self, sql: &str, params: P) -> Call<usize>
+    where
+        P: Params + Send + 'static,
+    {
+        let sql = sql.to_owned();
+        self.call(move |conn| conn.execute(&sql, params))
+    }
+
+    /// Execute a query and collect the rows mapped by `f`.
+    ///
+    /// See [`Statement::query_map`](crate::Statement::query_map).
+    pub fn query_map<T, P, F>(&self, sql: &str, params: P, f: F) -> Call<Vec<T>>
+    where
+        T: Send + 'static,
+        P: Params + Send + 'static,
+        F: FnMut(&Row<'_>) -> Result<T> + Send + 'static,
+    {
+        let sql = sql.to_owned();
+        self.call(move |conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            stmt.query_map(params, f)?.collect()
+        })
+    }
+
+    /// Run `f` in a transaction with the default behavior.
+    ///
+    /// The transaction is committed if `f` returns `Ok`, and rolled back
+    /// otherwise.
+    ///
+    /// See [`Connection::transaction`].
+    pub fn transaction<T, F>(&self, f: F) -> Call<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Transaction<'_>) -> Result<T> + Send + 'static,
+    {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            let value = f(&tx)?;
+            tx.commit()?;
+            Ok(value)
+        })
+    }
+
+    /// Interrupt the query running on the worker thread, if any.
+    pub fn interrupt(&self) {
+        self.interrupt.interrupt();
+    }
+
+    /// Close the connection once the closures already sent have run.
+    ///
+    /// See [`Connection::close`].
+    pub fn close(self) -> Call<()> {
+        let (task, call) = Task::new(|conn: Connection| conn.close().map_err(|(_, e)| e), None);
+        let _ = self
+            .sender
+            .send(Message::Close(Box::new(move |conn| task.run(conn))));
+        call
+    }
+}
+
+fn run(mut conn: Connection, receiver: &mpsc::Receiver<Message>) {
+    for message in receiver {
+        match message {
+            Message::Call(f) => f(&mut conn),
+            Message::Close(f) => return f(conn),
+        }
+    }
+}
+
+enum Status<T> {
+    Queued,
+    Running,
+    Done(thread::Result<Result<T>>),
+    Cancelled,
+    Taken,
+}
+
+struct State<T> {
+    status: Status<T>,
+    waker: Option<Waker>,
+}
+
+type Shared<T> = Arc<Mutex<State<T>>>;
+
+fn lock<T>(shared: &Shared<T>) -> MutexGuard<'_, State<T>> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Closure sent to the worker thread
+struct Task<T, F> {
+    shared: Shared<T>,
+    f: Option<F>,
+}
+
+impl<T, F> Task<T, F> {
+    fn new(f: F, interrupt: Option<Arc<InterruptHandle>>) -> (Self, Call<T>) {
+        let shared = Arc::new(Mutex::new(State {
+            status: Status::Queued,
+            waker: None,
+        }));
+        let call = Call {
+            shared: shared.clone(),
+            interrupt,
+        };
+        (Self { shared, f: Some(f) }, call)
+    }
+
+    fn run<A>(self, arg: A)
+    where
+        F: FnOnce(A) -> Result<T>,
+    {
+        self.run_with(|f| f(arg));
+    }
+
+    fn run_with(mut self, g: impl FnOnce(F) -> Result<T>) {
+        let f = self.f.take().unwrap();
+        {
+            let mut state = lock(&self.shared);
+            if matches!(state.status, Status::Cancelled) {
+                return;
+            }
+            state.status = Status::Running;
+        }
+        let r = panic::catch_unwind(AssertUnwindSafe(|| g(f)));
+        complete(&self.shared, r);
+    }
+}
+
+impl<T, F> Drop for Task<T, F> {
+    fn drop(&mut self) {
+        if self.f.is_some() {
+            complete(
+                &self.shared,
+                Ok(Err(err!(ffi::SQLITE_MISUSE, "connection closed"))),
+            );
+        }
+    }
+}
+
+fn complete<T>(shared: &Shared<T>, r: thread::Result<Result<T>>) {
+    let mut state = lock(shared);
+    state.status = Status::Done(r);
+    if let Some(waker) = state.waker.take() {
+        waker.wake();
+    }
+}
+
+/// Future resolved with the result of a closure run by the worker thread of
+/// an [`AsyncConnection`].
+///
+/// Dropping it before completion cancels the closure if it has not started
+/// yet, or interrupts the connection if it is running.
+#[must_use = "futures do nothing unless polled, and dropping this one cancels the call"]
+pub struct Call<T> {
+    shared: Shared<T>,
+    interrupt: Option<Arc<InterruptHandle>>,
+}
+
+impl<T> Future for Call<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let mut state = lock(&self.shared);
+        match std::mem::replace(&mut state.status, Status::Taken) {
+            Status::Done(Ok(r)) => Poll::Ready(r),
+            Status::Done(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            Status::Taken => panic!("`Call` polled after completion"),
+            status => {
+                state.status = status;
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for Call<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared);
+        match state.status {
+            Status::Queued => state.status = Status::Cancelled,
+            Status::Running => {
+                if let Some(ref interrupt) = self.interrupt {
+                    interrupt.interrupt();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::sync::mpsc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    use super::AsyncConnection;
+    use crate::{ErrorCode, Result};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut f = pin!(f);
+        loop {
+            match f.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_async_connection() -> Result<()> {
+        block_on(async {
+            let db = AsyncConnection::open_in_memory().await?;
+            db.call(|conn| conn.execute_batch("CREATE TABLE foo(x INTEGER, y TEXT)"))
+                .await?;
+            assert_eq!(
+                1,
+                db.execute("INSERT INTO foo VALUES (?1, ?2)", (1, "one"))
+                    .await?
+            );
+            db.transaction(|tx| {
+                tx.execute("INSERT INTO foo VALUES (2, 'two')", [])?;
+                tx.execute("INSERT INTO foo VALUES (3, 'three')", [])?;
+                Ok(())
+            })
+            .await?;
+            db.transaction(|tx| tx.execute("INSERT INTO bar VALUES (4)", []))
+                .await
+                .unwrap_err();
+            let rows = db
+                .query_map("SELECT y FROM foo WHERE x > ?1 ORDER BY x", (1,), |r| {
+                    r.get::<_, String>(0)
+                })
+                .await?;
+            assert_eq!(rows, ["two", "three"]);
+            db.close().await
+        })
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_open_error() {
+        let err = block_on(AsyncConnection::open("/nonexistent/dir/db.sqlite")).err();
+        assert_eq!(
+            Some(ErrorCode::CannotOpen),
+            err.and_then(|e| e.sqlite_error_code())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_cancel() -> Result<()> {
+        let db = block_on(AsyncConnection::open_in_memory())?;
+        let (started_tx, started_rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
+        let waker = Waker::noop();
+        let mut cx = Context::from_waker(waker);
+        {
+            let mut call = pin!(db.call(move |conn| {
+                started_tx.send(()).unwrap();
+                let r = conn.query_row(
+                    "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c",
+                    [],
+                    |r| r.get::<_, i64>(0),
+                );
+                tx.send(r).unwrap();
+                Ok(())
+            }));
+            assert!(call.as_mut().poll(&mut cx).is_pending());
+            // the worker has dequeued the call
+            started_rx.recv().unwrap();
+        }
+        // the interrupt is lost if the statement was not running yet
+        let r = loop {
+            match rx.recv_timeout(Duration::from_millis(50)) {
+                Ok(r) => break r,
+                Err(mpsc::RecvTimeoutError::Timeout) => db.interrupt(),
+                Err(err) => panic!("{err}"),
+            }
+        };
+        let err = r.unwrap_err();
+        assert_eq!(
+            Some(ErrorCode::OperationInterrupted),
+            err.sqlite_error_code()
+        );
+
+        // cancelled before it starts
+        let (tx, rx) = mpsc::channel();
+        let blocker = db.call(|_| {
+            thread::sleep(Duration::from_millis(50));
+            Ok(())
+        });
+        drop(db.call(move |_| tx.send(()).map_err(|_| unreachable!())));
+        block_on(blocker)?;
+        block_on(db.call(|_| Ok(())))?;
+        assert!(rx.try_recv().is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    #[should_panic(expected = "boom")]
+    fn test_panic() {
+        let db = block_on(AsyncConnection::open_in_memory()).unwrap();
+        let _ = block_on(db.call(|_| -> Result<()> { panic!("boom") }));
+    }
+}
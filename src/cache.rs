@@ -157,6 +157,8 @@ impl StatementCache {
         }
         let mut cache = self.0.borrow_mut();
         stmt.clear_bindings();
+        #[cfg(feature = "unlock_notify")]
+        stmt.set_unlock_wait(crate::unlock_notify::UnlockWait::default());
         if let Some(sql) = stmt.statement_cache_key() {
             cache.insert(sql, stmt);
         } else {
@@ -3,6 +3,8 @@ use std::ptr;
 use std::str;
 
 use crate::ffi;
+#[cfg(feature = "column_decltype")]
+use crate::types::{ToSqlOutput, Type, ValueRef};
 use crate::{Connection, Error, Name, Result, Statement};
 
 /// Information about a column of a SQLite query.
@@ -267,6 +269,247 @@ impl Statement<'_> {
     }
 }
 
+/// State of the checked mode of a statement
+#[cfg(feature = "column_decltype")]
+#[derive(Debug, Default)]
+pub(crate) struct DeclTypeCheck {
+    /// Declared type of the column matching each parameter
+    params: Vec<Option<String>>,
+}
+
+#[cfg(feature = "column_decltype")]
+impl Statement<'_> {
+    /// Enable or disable the checked mode of this statement.
+    ///
+    /// In checked mode, reading a column with [`Row::get`](crate::Row::get)
+    /// (or [`Row::get_borrowed`](crate::Row::get_borrowed)) fails with
+    /// [`Error::DeclTypeMismatch`] when the requested Rust type does not
+    /// accept the storage class matching the
+    /// [type affinity](https://sqlite.org/datatype3.html#determination_of_column_affinity)
+    /// of the declared type of the column, even if the stored value could
+    /// have been converted:
+    ///
+    /// | Affinity | Rust type must accept |
+    /// |----------|-----------------------|
+    /// | INTEGER  | INTEGER               |
+    /// | REAL     | REAL                  |
+    /// | NUMERIC  | INTEGER, REAL or TEXT |
+    /// | TEXT     | TEXT                  |
+    /// | BLOB     | anything              |
+    ///
+    /// Columns declared as DATE, DATETIME, TIMESTAMP or TIME (NUMERIC
+    /// affinity) are date and time columns: the Rust type must accept TEXT, or
+    /// both INTEGER and REAL (unix timestamps and Julian day numbers, like the
+    /// `UnixSeconds` and `JulianDay` wrappers of the date and time features),
+    /// so that plain integers like `i64` are rejected.
+    ///
+    /// Expressions and columns without declared type are not checked. See
+    /// [`check_parameters`](Statement::check_parameters) to also check bound
+    /// parameters.
+    ///
+    /// Whether the Rust type accepts a storage class is a heuristic: on each
+    /// checked read, its [`FromSql`](crate::types::FromSql) (or
+    /// [`FromSqlRef`](crate::types::FromSqlRef)) implementation is called with
+    /// sample values that do not come from the database (`0`, `0.0` and an
+    /// empty TEXT), and the storage class is accepted unless it fails with
+    /// [`FromSqlError::InvalidType`](crate::types::FromSqlError::InvalidType).
+    /// Any other error, like a type parsing TEXT failing to parse the empty
+    /// string, counts as accepting the storage class. So implementations used
+    /// in checked mode should fail with `InvalidType` for the storage classes
+    /// they never accept, and have no side effects.
+    ///
+    /// This is meant to bring some type safety to non-STRICT tables, for
+    /// example only in debug builds:
+    ///
+    /// ```rust,no_run
+    /// # use rusqlite::{Connection, Result};
+    /// fn names(conn: &Connection) -> Result<Vec<String>> {
+    ///     let mut stmt = conn.prepare("SELECT name FROM people")?;
+    ///     stmt.set_checked(cfg!(debug_assertions));
+    ///     stmt.query_map([], |row| row.get(0))?.collect()
+    /// }
+    /// ```
+    pub fn set_checked(&mut self, checked: bool) {
+        if checked {
+            self.decl_type_check.get_or_insert_with(Default::default);
+        } else {
+            self.decl_type_check = None;
+        }
+    }
+
+    /// Enable the checked mode (see [`set_checked`](Statement::set_checked))
+    /// and check bound parameters against the columns of `table_name`.
+    ///
+    /// A named parameter (`:name`, `@name` or `$name`) matching a column
+    /// of the table fails to bind with [`Error::DeclTypeMismatch`] when its
+    /// value is not NULL and its storage class does not match the type
+    /// affinity of the column:
+    ///
+    /// | Affinity | Accepted values     |
+    /// |----------|---------------------|
+    /// | INTEGER  | INTEGER             |
+    /// | REAL     | REAL, INTEGER       |
+    /// | NUMERIC  | INTEGER, REAL, TEXT |
+    /// | TEXT     | TEXT                |
+    /// | BLOB     | anything            |
+    ///
+    /// ```rust,no_run
+    /// # use rusqlite::{Connection, Result, named_params};
+    /// fn insert_person(conn: &Connection, name: &str, age: u8) -> Result<usize> {
+    ///     let mut stmt = conn.prepare("INSERT INTO people (name, age) VALUES (:name, :age)")?;
+    ///     stmt.check_parameters(None, "people")?;
+    ///     stmt.execute(named_params! { ":name": name, ":age": age })
+    /// }
+    /// ```
+    ///
+    /// `db_name` is main, temp, the name in ATTACH, or `None` to search all
+    /// databases.
+    ///
+    /// ## Failure
+    ///
+    /// Will return `Err` if `table_name` does not exist.
+    pub fn check_parameters(&mut self, db_name: Option<&str>, table_name: &str) -> Result<()> {
+        if !self.conn.table_exists(db_name, table_name)? {
+            return Err(err!(ffi::SQLITE_ERROR, "no such table: {table_name}"));
+        }
+        let mut params = Vec::with_capacity(self.parameter_count());
+        for i in 1..=self.parameter_count() {
+            let mut decl_type = None;
+            // strip ':', '@' or '$' prefix
+            if let Some(column_name) = self.parameter_name(i).and_then(|name| name.get(1..))
+                && self.conn.column_exists(db_name, table_name, column_name)?
+            {
+                let (data_type, ..) =
+                    self.conn
+                        .column_metadata(db_name, table_name, column_name)?;
+                decl_type = data_type.map(|t| t.to_string_lossy().into_owned());
+            }
+            params.push(decl_type);
+        }
+        self.decl_type_check = Some(DeclTypeCheck { params });
+        Ok(())
+    }
+
+    /// In checked mode, check that `rust_type`, which accepts the values for
+    /// which `accepts` returns true, can read column `col`.
+    pub(crate) fn check_column_type(
+        &self,
+        col: usize,
+        rust_type: &str,
+        accepts: impl Fn(ValueRef<'static>) -> bool,
+    ) -> Result<()> {
+        if self.decl_type_check.is_none() {
+            return Ok(());
+        }
+        let Some(decl_type) = self.stmt.column_decltype(col) else {
+            return Ok(());
+        };
+        let decl_type = decl_type
+            .to_str()
+            .expect("Invalid UTF-8 sequence in column declaration");
+        let ok = match Affinity::of(decl_type) {
+            Affinity::Integer => accepts(ValueRef::Integer(0)),
+            Affinity::Real => accepts(ValueRef::Real(0.0)),
+            Affinity::Numeric if is_date_time(decl_type) => {
+                accepts(ValueRef::Text(b""))
+                    || (accepts(ValueRef::Integer(0)) && accepts(ValueRef::Real(0.0)))
+            }
+            Affinity::Numeric => {
+                accepts(ValueRef::Integer(0))
+                    || accepts(ValueRef::Real(0.0))
+                    || accepts(ValueRef::Text(b""))
+            }
+            Affinity::Text => accepts(ValueRef::Text(b"")),
+            Affinity::Blob => true,
+        };
+        if ok {
+            return Ok(());
+        }
+        Err(Error::DeclTypeMismatch {
+            name: self.column_name_unwrap(col).to_owned(),
+            decl_type: decl_type.to_owned(),
+            found: rust_type.to_owned(),
+        })
+    }
+
+    /// In checked mode, check `value` bound to parameter `ndx`.
+    pub(crate) fn check_parameter_type(&self, ndx: usize, value: &ToSqlOutput<'_>) -> Result<()> {
+        let Some(Some(decl_type)) = self
+            .decl_type_check
+            .as_ref()
+            .and_then(|check| check.params.get(ndx.wrapping_sub(1)))
+        else {
+            return Ok(());
+        };
+        let data_type = match value {
+            ToSqlOutput::Borrowed(v) => v.data_type(),
+            ToSqlOutput::Owned(v) => v.data_type(),
+            #[cfg(feature = "blob")]
+            ToSqlOutput::ZeroBlob(_) => Type::Blob,
+            #[cfg(any(feature = "functions", feature = "pointer"))]
+            _ => return Ok(()),
+        };
+        if Affinity::of(decl_type).accepts(data_type) {
+            return Ok(());
+        }
+        Err(Error::DeclTypeMismatch {
+            name: self.parameter_name(ndx).unwrap_or_default().to_owned(),
+            decl_type: decl_type.clone(),
+            found: data_type.to_string(),
+        })
+    }
+}
+
+/// Type affinity of a declared column type
+#[cfg(feature = "column_decltype")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+#[cfg(feature = "column_decltype")]
+impl Affinity {
+    /// <https://sqlite.org/datatype3.html#determination_of_column_affinity>
+    pub(crate) fn of(decl_type: &str) -> Self {
+        let decl_type = decl_type.to_ascii_uppercase();
+        let contains = |s: &str| decl_type.contains(s);
+        if contains("INT") {
+            Self::Integer
+        } else if contains("CHAR") || contains("CLOB") || contains("TEXT") {
+            Self::Text
+        } else if contains("BLOB") || decl_type.is_empty() {
+            Self::Blob
+        } else if contains("REAL") || contains("FLOA") || contains("DOUB") {
+            Self::Real
+        } else {
+            Self::Numeric
+        }
+    }
+
+    /// Whether a value of type `t` matches this affinity
+    fn accepts(self, t: Type) -> bool {
+        matches!(
+            (self, t),
+            (_, Type::Null)
+                | (Self::Blob, _)
+                | (Self::Integer, Type::Integer)
+                | (Self::Real | Self::Numeric, Type::Integer | Type::Real)
+                | (Self::Text | Self::Numeric, Type::Text)
+        )
+    }
+}
+
+/// Whether `decl_type` is DATE, DATETIME, TIMESTAMP or TIME
+#[cfg(feature = "column_decltype")]
+fn is_date_time(decl_type: &str) -> bool {
+    let decl_type = decl_type.to_ascii_uppercase();
+    decl_type.contains("DATE") || decl_type.contains("TIME")
+}
+
 impl Connection {
     /// Check if `table_name`.`column_name` exists.
     ///
@@ -601,4 +844,224 @@ mod test {
         assert!(db.column_metadata(None, c"sqlite_master", c"foo").is_err());
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "column_decltype")]
+    fn test_affinity() {
+        use super::Affinity;
+
+        assert_eq!(Affinity::Integer, Affinity::of("BIGINT"));
+        assert_eq!(Affinity::Integer, Affinity::of("point")); // poINT
+        assert_eq!(Affinity::Text, Affinity::of("VARCHAR(255)"));
+        assert_eq!(Affinity::Blob, Affinity::of(""));
+        assert_eq!(Affinity::Real, Affinity::of("double precision"));
+        assert_eq!(Affinity::Numeric, Affinity::of("DATE"));
+        assert_eq!(Affinity::Numeric, Affinity::of("ANY"));
+    }
+
+    #[test]
+    #[cfg(feature = "column_decltype")]
+    fn test_checked_reads() -> Result<()> {
+        use crate::Error;
+
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(
+            "CREATE TABLE foo(i INTEGER, t TEXT, r REAL, d DATE, b, n TEXT, m NUMERIC);
+             INSERT INTO foo VALUES(1, '2', 3.5, '2024-01-01', x'00', NULL, 'x');",
+        )?;
+        let mut stmt = db.prepare("SELECT i, t, r, d, b, i + 1, n, m FROM foo")?;
+        // unchecked
+        assert_eq!(None, stmt.query_row([], |r| r.get::<_, Option<i64>>(6))?);
+
+        stmt.set_checked(true);
+        let row = stmt.query_row([], |r| {
+            Ok((
+                r.get::<_, i64>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, f64>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Vec<u8>>(4)?,
+                r.get::<_, String>(5).is_err(),
+            ))
+        })?;
+        assert_eq!(
+            (
+                1,
+                "2".to_owned(),
+                3.5,
+                "2024-01-01".to_owned(),
+                vec![0],
+                true
+            ),
+            row
+        );
+        // compatible types
+        stmt.query_row([], |r| {
+            r.get::<_, Option<u8>>(0)?;
+            r.get::<_, bool>(0)?;
+            r.get::<_, String>("t")?;
+            r.get_borrowed::<_, &str>(1)?;
+            r.get_borrowed::<_, &str>(3)?;
+            r.get::<_, String>(7)?;
+            r.get::<_, crate::types::Value>(4)
+        })?;
+
+        let err = stmt
+            .query_row([], |r| r.get::<_, Option<i64>>(6))
+            .unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "n"));
+        let err = stmt.query_row([], |r| r.get::<_, i64>(1)).unwrap_err();
+        assert_eq!(
+            Error::DeclTypeMismatch {
+                name: "t".to_owned(),
+                decl_type: "TEXT".to_owned(),
+                found: "i64".to_owned()
+            },
+            err
+        );
+        assert_eq!("t declared as TEXT does not match i64", err.to_string());
+        let err = stmt
+            .query_row([], |r| r.get_borrowed::<_, &str>(0).map(str::len))
+            .unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "i"));
+        let err = stmt.query_row([], |r| r.get::<_, i64>(2)).unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "r"));
+        let err = stmt.query_row([], |r| r.get::<_, i64>(3)).unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "d"));
+
+        stmt.set_checked(false);
+        assert_eq!(None, stmt.query_row([], |r| r.get::<_, Option<i64>>(6))?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "column_decltype")]
+    fn test_checked_reads_parse_failure() -> Result<()> {
+        use crate::Error;
+        use crate::types::{FromSql, FromSqlError, FromSqlResult, ValueRef};
+
+        /// Parsed from TEXT, fails to parse the empty TEXT sample
+        #[derive(Debug, PartialEq)]
+        struct Version(u32);
+        impl FromSql for Version {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                let s = value.as_str()?;
+                s.parse().map(Self).map_err(FromSqlError::other)
+            }
+        }
+
+        /// Fails with `InvalidType` for the empty TEXT sample
+        #[derive(Debug)]
+        struct NonEmpty(String);
+        impl FromSql for NonEmpty {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                match value.as_str()? {
+                    "" => Err(FromSqlError::InvalidType),
+                    s => Ok(Self(s.to_owned())),
+                }
+            }
+        }
+
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(
+            "CREATE TABLE foo(t TEXT, i INTEGER);
+             INSERT INTO foo VALUES('2', 2);",
+        )?;
+        let mut stmt = db.prepare("SELECT t, i FROM foo")?;
+        stmt.set_checked(true);
+        // the parse error for the empty TEXT sample counts as accepting TEXT
+        assert_eq!(Version(2), stmt.query_row([], |r| r.get(0))?);
+        let err = stmt.query_row([], |r| r.get::<_, Version>(1)).unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "i"));
+        // `InvalidType` for the sample rejects TEXT, whatever the stored value
+        let err = stmt
+            .query_row([], |r| r.get::<_, NonEmpty>(0).map(|v| v.0))
+            .unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "t"));
+        stmt.set_checked(false);
+        assert_eq!("2", stmt.query_row([], |r| r.get::<_, NonEmpty>(0))?.0);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "column_decltype",
+        any(feature = "chrono", feature = "jiff", feature = "time")
+    ))]
+    fn test_checked_date_time_reads() -> Result<()> {
+        use crate::Error;
+        use crate::types::{JulianDay, UnixSeconds, UnixTime};
+
+        // Julian day numbers and unix timestamps in a date and time column
+        #[derive(Debug, PartialEq)]
+        struct Nanos(i128);
+        impl UnixTime for Nanos {
+            fn unix_nanos(&self) -> Option<i128> {
+                Some(self.0)
+            }
+
+            fn from_unix_nanos(nanos: i128) -> Option<Self> {
+                Some(Self(nanos))
+            }
+        }
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(
+            "CREATE TABLE foo(d DATE);
+             INSERT INTO foo VALUES(unixepoch('2024-01-01'));",
+        )?;
+        let mut stmt = db.prepare("SELECT d FROM foo")?;
+        stmt.set_checked(true);
+        let UnixSeconds(d) = stmt.query_row([], |r| r.get::<_, UnixSeconds<Nanos>>(0))?;
+        assert_eq!(Nanos(1_704_067_200_000_000_000), d);
+        let err = stmt.query_row([], |r| r.get::<_, i64>(0)).unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "d"));
+        db.execute("UPDATE foo SET d = julianday(d, 'unixepoch')", [])?;
+        let JulianDay(d) = stmt.query_row([], |r| r.get::<_, JulianDay<Nanos>>(0))?;
+        assert_eq!(Nanos(1_704_067_200_000_000_000), d);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "column_decltype")]
+    fn test_checked_parameters() -> Result<()> {
+        use crate::{Error, named_params};
+
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo(i INTEGER, t TEXT, r REAL, b)")?;
+        let mut stmt = db.prepare("INSERT INTO foo VALUES(:i, @t, $r, :b)")?;
+        // unchecked
+        stmt.execute(named_params! { ":i": "one", "@t": 1, "$r": "1.5", ":b": 0 })?;
+
+        stmt.check_parameters(None, "foo")?;
+        stmt.execute(named_params! { ":i": 1, "@t": "one", "$r": 1.5, ":b": 0 })?;
+        stmt.execute(named_params! { ":i": None::<i64>, "@t": "one", "$r": 1, ":b": "x" })?;
+        let err = stmt
+            .execute(named_params! { ":i": "one", "@t": "one", "$r": 1.5, ":b": 0 })
+            .unwrap_err();
+        assert_eq!(
+            Error::DeclTypeMismatch {
+                name: ":i".to_owned(),
+                decl_type: "INTEGER".to_owned(),
+                found: "Text".to_owned()
+            },
+            err
+        );
+        let err = stmt
+            .execute(named_params! { ":i": 1, "@t": 1, "$r": 1.5, ":b": 0 })
+            .unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "@t"));
+        let err = stmt
+            .execute(named_params! { ":i": 1, "@t": "one", "$r": 1.5f64.to_string(), ":b": 0 })
+            .unwrap_err();
+        assert!(matches!(err, Error::DeclTypeMismatch { ref name, .. } if name == "$r"));
+        assert_eq!(3, db.one_column::<i64, _>("SELECT count(*) FROM foo", [])?);
+
+        // unknown columns and positional parameters are not checked
+        let mut stmt = db.prepare("UPDATE foo SET t = ?1 WHERE i = :unknown")?;
+        stmt.check_parameters(Some("main"), "foo")?;
+        stmt.execute((1, "one"))?;
+
+        assert!(stmt.check_parameters(None, "bar").is_err());
+        Ok(())
+    }
 }
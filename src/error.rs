@@ -161,6 +161,24 @@ pub enum Error {
     /// is out of range.
     #[cfg(feature = "modern_sqlite")] // 3.39.0
     InvalidDatabaseIndex(usize),
+    /// Error when waiting for a table locked by another connection sharing the
+    /// same cache would deadlock. The current transaction should be rolled
+    /// back.
+    #[cfg(feature = "unlock_notify")]
+    Deadlock,
+    /// Error in checked mode (see
+    /// [`Statement::set_checked`](crate::Statement::set_checked)) when the
+    /// requested Rust type or the type of a bound value does not match the
+    /// declared type of a column.
+    #[cfg(feature = "column_decltype")]
+    DeclTypeMismatch {
+        /// column or parameter name
+        name: String,
+        /// declared type of the column
+        decl_type: String,
+        /// requested Rust type or SQLite type of the bound value
+        found: String,
+    },
 }
 
 impl PartialEq for Error {
@@ -224,6 +242,21 @@ impl PartialEq for Error {
             (Self::InitError(e1), Self::InitError(e2)) => e1 == e2,
             #[cfg(feature = "modern_sqlite")]
             (Self::InvalidDatabaseIndex(i1), Self::InvalidDatabaseIndex(i2)) => i1 == i2,
+            #[cfg(feature = "unlock_notify")]
+            (Self::Deadlock, Self::Deadlock) => true,
+            #[cfg(feature = "column_decltype")]
+            (
+                Self::DeclTypeMismatch {
+                    name: n1,
+                    decl_type: d1,
+                    found: f1,
+                },
+                Self::DeclTypeMismatch {
+                    name: n2,
+                    decl_type: d2,
+                    found: f2,
+                },
+            ) => n1 == n2 && d1 == d2 && f1 == f2,
             (..) => false,
         }
     }
@@ -243,7 +276,7 @@ impl From<NulError> for Error {
     }
 }
 
-const UNKNOWN_COLUMN: usize = usize::MAX;
+pub(crate) const UNKNOWN_COLUMN: usize = usize::MAX;
 
 /// The conversion isn't precise, but it's convenient to have it
 /// to allow use of `get_raw(…).as_…()?` in callbacks that take `Error`.
@@ -353,6 +386,14 @@ impl fmt::Display for Error {
             Self::InitError(ref err) => err.fmt(f),
             #[cfg(feature = "modern_sqlite")]
             Self::InvalidDatabaseIndex(i) => write!(f, "Invalid database index: {i}"),
+            #[cfg(feature = "unlock_notify")]
+            Self::Deadlock => write!(f, "Waiting for a shared-cache lock would deadlock"),
+            #[cfg(feature = "column_decltype")]
+            Self::DeclTypeMismatch {
+                ref name,
+                ref decl_type,
+                ref found,
+            } => write!(f, "{name} declared as {decl_type} does not match {found}"),
         }
     }
 }
@@ -406,6 +447,10 @@ impl error::Error for Error {
             Self::InitError(ref err) => Some(err),
             #[cfg(feature = "modern_sqlite")]
             Self::InvalidDatabaseIndex(_) => None,
+            #[cfg(feature = "unlock_notify")]
+            Self::Deadlock => None,
+            #[cfg(feature = "column_decltype")]
+            Self::DeclTypeMismatch { .. } => None,
         }
     }
 }
@@ -127,7 +127,9 @@ impl Context<'_> {
             FromSqlError::Other(err) => {
                 Error::FromSqlConversionFailure(idx, value.data_type(), err)
             }
-            FromSqlError::InvalidBlobSize { .. } => {
+            FromSqlError::InvalidBlobSize { .. }
+            | FromSqlError::PrecisionLoss(_)
+            | FromSqlError::Overflow(_) => {
                 Error::FromSqlConversionFailure(idx, value.data_type(), Box::new(err))
             }
         })
@@ -237,9 +237,10 @@ impl InnerConnection {
                         if !unlock_notify::is_locked(self.db, rc) {
                             break;
                         }
-                        rc = unlock_notify::wait_for_unlock_notify(self.db);
-                        if rc != ffi::SQLITE_OK {
-                            break;
+                        match unlock_notify::wait_for_unlock_notify(self.db, None) {
+                            unlock_notify::Unlock::Released => {}
+                            unlock_notify::Unlock::Deadlock => return Err(Error::Deadlock),
+                            unlock_notify::Unlock::TimedOut => break,
                         }
                     }
                     rc
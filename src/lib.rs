@@ -97,8 +97,14 @@ pub use crate::error::{Error, to_sqlite_error};
 pub use crate::ffi::ErrorCode;
 #[cfg(feature = "load_extension")]
 pub use crate::load_extension_guard::LoadExtensionGuard;
+pub use crate::options::ConnectionOptions;
+#[cfg(feature = "rusqlite-macros")]
+#[doc(hidden)]
+pub use crate::params::__DeriveParams;
 pub use crate::params::{Params, ParamsFromIter, params_from_iter};
-pub use crate::row::{AndThenRows, Map, MappedRows, Row, RowIndex, Rows};
+pub use crate::row::{AndThenRows, FromRow, FromRowRef, Map, MappedRows, Row, RowIndex, Rows};
+#[cfg(feature = "stmt_scanstatus")]
+pub use crate::scan_status::ScanStatus;
 pub use crate::statement::{Statement, StatementStatus};
 #[cfg(feature = "modern_sqlite")]
 pub use crate::transaction::TransactionState;
@@ -109,10 +115,16 @@ pub use crate::version::*;
 #[cfg(feature = "rusqlite-macros")]
 #[doc(hidden)]
 pub use rusqlite_macros::__bind;
+#[cfg(feature = "rusqlite-macros")]
+pub use rusqlite_macros::{FromRow, Params};
 
 #[macro_use]
 mod error;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "async_connection")]
+pub mod async_connection;
 #[cfg(not(feature = "loadable_extension"))]
 pub mod auto_extension;
 #[cfg(feature = "backup")]
@@ -138,10 +150,17 @@ mod inner_connection;
 pub mod limits;
 #[cfg(feature = "load_extension")]
 mod load_extension_guard;
+mod options;
 mod params;
+#[cfg(feature = "pool")]
+pub mod pool;
 mod pragma;
 mod raw_statement;
 mod row;
+#[cfg(feature = "stmt_scanstatus")]
+mod scan_status;
+#[cfg(feature = "serde")]
+pub mod serde;
 #[cfg(feature = "serialize")]
 pub mod serialize;
 #[cfg(feature = "session")]
@@ -152,8 +171,10 @@ pub mod trace;
 mod transaction;
 pub mod types;
 #[cfg(feature = "unlock_notify")]
-mod unlock_notify;
+pub mod unlock_notify;
 mod version;
+#[cfg(feature = "vfs")]
+pub mod vfs;
 #[cfg(feature = "vtab")]
 pub mod vtab;
 
@@ -0,0 +1,280 @@
+//! Reusable connection setup
+#[cfg(feature = "collation")]
+use std::cmp::Ordering;
+#[cfg(feature = "functions")]
+use std::ffi::c_int;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::DbConfig;
+#[cfg(feature = "functions")]
+use crate::functions::{Aggregate, Context, FunctionFlags, SqlFnOutput};
+use crate::types::ToSql;
+#[cfg(feature = "vtab")]
+use crate::vtab::{Module, VTab};
+use crate::{Connection, OpenFlags, Result};
+
+type Step = Arc<dyn Fn(&Connection) -> Result<()> + Send + Sync>;
+
+/// Recorded setup of a connection, used to open any number of identically
+/// configured connections.
+///
+/// Each setup step is replayed, in the order it was recorded, on every
+/// connection opened with [`open`](ConnectionOptions::open) or passed to
+/// [`apply`](ConnectionOptions::apply).
+///
+/// ```rust,no_run
+/// # use rusqlite::{ConnectionOptions, Result};
+/// # use std::time::Duration;
+/// fn open_db() -> Result<()> {
+///     let options = ConnectionOptions::new()
+///         .busy_timeout(Duration::from_secs(10))
+///         .pragma("journal_mode", "wal")
+///         .pragma("foreign_keys", true);
+///     let db1 = options.open("app.db")?;
+///     let db2 = options.open("app.db")?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+#[must_use]
+pub struct ConnectionOptions {
+    flags: OpenFlags,
+    vfs: Option<String>,
+    steps: Vec<Step>,
+}
+
+impl ConnectionOptions {
+    /// No setup, and the default [`OpenFlags`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the flags used to open connections.
+    pub fn flags(mut self, flags: OpenFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Set the VFS used to open connections.
+    pub fn vfs(mut self, vfs: &str) -> Self {
+        self.vfs = Some(vfs.to_owned());
+        self
+    }
+
+    /// Record a custom setup step.
+    pub fn with<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Connection) -> Result<()> + Send + Sync + 'static,
+    {
+        self.steps.push(Arc::new(f));
+        self
+    }
+
+    /// Record a call to [`Connection::busy_timeout`].
+    pub fn busy_timeout(self, timeout: Duration) -> Self {
+        self.with(move |conn| conn.busy_timeout(timeout))
+    }
+
+    /// Record a call to [`Connection::set_db_config`].
+    pub fn db_config(self, config: DbConfig, on: bool) -> Self {
+        self.with(move |conn| conn.set_db_config(config, on).map(|_| ()))
+    }
+
+    /// Record a call to [`Connection::pragma_update`] on the main database.
+    pub fn pragma<V>(self, pragma_name: &str, pragma_value: V) -> Self
+    where
+        V: ToSql + Send + Sync + 'static,
+    {
+        let pragma_name = pragma_name.to_owned();
+        self.with(move |conn| conn.pragma_update(None, &pragma_name, &pragma_value))
+    }
+
+    /// Record a call to [`Connection::create_collation`].
+    #[cfg(feature = "collation")]
+    pub fn collation<C>(self, collation_name: &str, x_compare: C) -> Self
+    where
+        C: Fn(&str, &str) -> Ordering + Send + Sync + 'static,
+    {
+        let collation_name = collation_name.to_owned();
+        let x_compare = Arc::new(x_compare);
+        self.with(move |conn| {
+            let x_compare = x_compare.clone();
+            conn.create_collation(collation_name.as_str(), move |a, b| x_compare(a, b))
+        })
+    }
+
+    /// Record a call to [`Connection::create_scalar_function`].
+    #[cfg(feature = "functions")]
+    pub fn scalar_function<F, T>(
+        self,
+        fn_name: &str,
+        n_arg: c_int,
+        flags: FunctionFlags,
+        x_func: F,
+    ) -> Self
+    where
+        F: Fn(&Context<'_>) -> Result<T> + Send + Sync + 'static,
+        T: SqlFnOutput,
+    {
+        let fn_name = fn_name.to_owned();
+        let x_func = Arc::new(x_func);
+        self.with(move |conn| {
+            let x_func = x_func.clone();
+            conn.create_scalar_function(fn_name.as_str(), n_arg, flags, move |ctx| x_func(ctx))
+        })
+    }
+
+    /// Record a call to [`Connection::create_aggregate_function`].
+    #[cfg(feature = "functions")]
+    pub fn aggregate_function<A, D, T>(
+        self,
+        fn_name: &str,
+        n_arg: c_int,
+        flags: FunctionFlags,
+        aggr: D,
+    ) -> Self
+    where
+        A: std::panic::RefUnwindSafe + std::panic::UnwindSafe,
+        D: Aggregate<A, T> + Clone + Send + Sync + 'static,
+        T: SqlFnOutput,
+    {
+        let fn_name = fn_name.to_owned();
+        self.with(move |conn| {
+            conn.create_aggregate_function(fn_name.as_str(), n_arg, flags, aggr.clone())
+        })
+    }
+
+    /// Record a call to [`Connection::create_module`].
+    #[cfg(feature = "vtab")]
+    pub fn module<T>(
+        self,
+        module_name: &str,
+        module: &'static Module<'static, T>,
+        aux: Option<T::Aux>,
+    ) -> Self
+    where
+        T: VTab<'static> + 'static,
+        T::Aux: Clone,
+    {
+        // `Module` only holds function pointers
+        struct StaticModule<T: VTab<'static> + 'static>(&'static Module<'static, T>);
+        unsafe impl<T: VTab<'static>> Send for StaticModule<T> {}
+        unsafe impl<T: VTab<'static>> Sync for StaticModule<T> {}
+        impl<T: VTab<'static>> StaticModule<T> {
+            fn get(&self) -> &'static Module<'static, T> {
+                self.0
+            }
+        }
+
+        let module_name = module_name.to_owned();
+        let module = StaticModule(module);
+        self.with(move |conn| conn.create_module(module_name.as_str(), module.get(), aux.clone()))
+    }
+
+    /// Replay the recorded setup on `conn`.
+    ///
+    /// [`flags`](ConnectionOptions::flags) and
+    /// [`vfs`](ConnectionOptions::vfs) are ignored: this can be used to set
+    /// up connections opened by other means.
+    pub fn apply(&self, conn: &Connection) -> Result<()> {
+        self.steps.iter().try_for_each(|step| step(conn))
+    }
+
+    /// Open a connection to the database at `path` and set it up.
+    ///
+    /// # Failure
+    ///
+    /// Will return `Err` if the connection cannot be opened or if a setup
+    /// step fails.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<Connection> {
+        let conn = match self.vfs {
+            Some(ref vfs) => Connection::open_with_flags_and_vfs(path, self.flags, vfs.as_str())?,
+            None => Connection::open_with_flags(path, self.flags)?,
+        };
+        self.apply(&conn)?;
+        Ok(conn)
+    }
+
+    /// Open a connection to a new in-memory database and set it up.
+    ///
+    /// # Failure
+    ///
+    /// Will return `Err` if the connection cannot be opened or if a setup
+    /// step fails.
+    pub fn open_in_memory(&self) -> Result<Connection> {
+        self.open(":memory:")
+    }
+}
+
+impl fmt::Debug for ConnectionOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionOptions")
+            .field("flags", &self.flags)
+            .field("vfs", &self.vfs)
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::time::Duration;
+
+    use super::ConnectionOptions;
+    use crate::config::DbConfig;
+    use crate::{OpenFlags, Result};
+
+    #[test]
+    fn test_options() -> Result<()> {
+        let options = ConnectionOptions::new()
+            .busy_timeout(Duration::from_millis(1234))
+            .db_config(DbConfig::SQLITE_DBCONFIG_ENABLE_TRIGGER, false)
+            .pragma("cache_size", -4321)
+            .pragma("application_id", 42);
+        for _ in 0..2 {
+            let db = options.open_in_memory()?;
+            let timeout: i64 = db.pragma_query_value(None, "busy_timeout", |r| r.get(0))?;
+            assert_eq!(1234, timeout);
+            assert!(!db.db_config(DbConfig::SQLITE_DBCONFIG_ENABLE_TRIGGER)?);
+            let cache_size: i64 = db.pragma_query_value(None, "cache_size", |r| r.get(0))?;
+            assert_eq!(-4321, cache_size);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_flags() {
+        let options = ConnectionOptions::new()
+            .flags(OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .pragma("application_id", 42);
+        options.open_in_memory().unwrap_err();
+    }
+
+    #[test]
+    #[cfg(all(feature = "functions", feature = "collation"))]
+    fn test_functions() -> Result<()> {
+        use crate::functions::FunctionFlags;
+
+        let options = ConnectionOptions::new()
+            .scalar_function("answer", 0, FunctionFlags::SQLITE_DETERMINISTIC, |_| Ok(42))
+            .collation("rev", |a, b| b.cmp(a));
+        let db1 = options.open_in_memory()?;
+        let db2 = options.open_in_memory()?;
+        for db in [db1, db2] {
+            let answer: i64 = db.one_column("SELECT answer()", [])?;
+            assert_eq!(42, answer);
+            let first: String = db.one_column(
+                "SELECT column1 FROM (VALUES ('a'), ('b')) ORDER BY column1 COLLATE rev LIMIT 1",
+                [],
+            )?;
+            assert_eq!("b", first);
+        }
+        Ok(())
+    }
+}
@@ -5,14 +5,24 @@ mod sealed {
     /// that are allowed are ones in this crate.
     pub trait Sealed {}
 }
-use sealed::Sealed;
+pub(crate) use sealed::Sealed;
+
+/// Implemented by `#[derive(Params)]`: [`Params`] is implemented for
+/// references to the derived type.
+#[cfg(feature = "rusqlite-macros")]
+#[doc(hidden)]
+pub trait __DeriveParams {
+    #[doc(hidden)]
+    fn __bind_in(&self, stmt: &mut Statement<'_>) -> Result<()>;
+}
 
 /// Trait used for [sets of parameter][params] passed into SQL
 /// statements/queries.
 ///
 /// [params]: https://www.sqlite.org/c3ref/bind_blob.html
 ///
-/// Note: Currently, this trait can only be implemented inside this crate.
+/// Note: Currently, this trait can only be implemented inside this crate, or
+/// derived (see [below](#derived-parameters)).
 /// Additionally, it's methods (which are `doc(hidden)`) should currently not be
 /// considered part of the stable API, although it's possible they will
 /// stabilize in the future.
@@ -133,6 +143,36 @@ use sealed::Sealed;
 /// }
 /// ```
 ///
+/// ## Derived parameters
+///
+/// With the `rusqlite-macros` feature, `Params` can be derived for references
+/// to a struct: fields of structs with named fields are bound to the
+/// `:field_name` parameter, and fields of tuple structs to the parameter at
+/// the same (one-based) position.
+///
+/// Fields accept the following `#[rusqlite(...)]` attributes:
+/// * `rename = "name"` binds the field to `:name` (or to `name` if it starts
+///   with `:`, `@` or `$`) instead,
+/// * `skip` does not bind the field,
+/// * `flatten` binds the fields of the field, a reference to which itself
+///   implements `Params`.
+///
+#[cfg_attr(feature = "rusqlite-macros", doc = "```rust,no_run")]
+#[cfg_attr(not(feature = "rusqlite-macros"), doc = "```rust,ignore")]
+/// # use rusqlite::{Connection, Params, Result};
+/// #[derive(Params)]
+/// struct Person {
+///     name: String,
+///     #[rusqlite(rename = "years")]
+///     age: u32,
+/// }
+///
+/// fn insert(conn: &Connection, person: &Person) -> Result<()> {
+///     conn.execute("INSERT INTO person (name, age) VALUES (:name, :years)", person)?;
+///     Ok(())
+/// }
+/// ```
+///
 /// ## No parameters
 ///
 /// You can just use an empty tuple or the empty array literal to run a query
@@ -201,6 +241,16 @@ pub trait Params: Sealed {
 // empty array literal as a parameter, since the `T` there would fail to be
 // inferred. The error message here would probably be quite bad, and so on
 // further thought, probably would end up causing *more* surprises, not less.
+#[cfg(feature = "rusqlite-macros")]
+impl<T: __DeriveParams + ?Sized> Sealed for &T {}
+#[cfg(feature = "rusqlite-macros")]
+impl<T: __DeriveParams + ?Sized> Params for &T {
+    #[inline]
+    fn __bind_in(self, stmt: &mut Statement<'_>) -> Result<()> {
+        __DeriveParams::__bind_in(self, stmt)
+    }
+}
+
 impl Sealed for [&(dyn ToSql + Send + Sync); 0] {}
 impl Params for [&(dyn ToSql + Send + Sync); 0] {
     #[inline]
@@ -0,0 +1,333 @@
+//! Connection pool for WAL databases, with a single writer and multiple
+//! readers.
+//!
+//! [`Connection`] is `Send` but not `Sync`: a [`Pool`] shares a fixed set of
+//! connections to the same database file between threads. Writes are
+//! serialized through the writer connection while readers run concurrently,
+//! which is what the [WAL](https://sqlite.org/wal.html) journal mode allows.
+//!
+//! ```rust,no_run
+//! use rusqlite::pool::Pool;
+//! use rusqlite::Result;
+//!
+//! fn main() -> Result<()> {
+//!     let pool = Pool::open("app.db", 4, |conn| {
+//!         conn.pragma_update(None, "foreign_keys", true)
+//!     })?;
+//!     pool.transaction(|tx| {
+//!         tx.execute_batch("CREATE TABLE IF NOT EXISTS foo(x)")?;
+//!         tx.execute("INSERT INTO foo VALUES (?1)", [42])?;
+//!         Ok(())
+//!     })?;
+//!     std::thread::scope(|s| {
+//!         for _ in 0..4 {
+//!             s.spawn(|| -> Result<i64> {
+//!                 pool.reader().query_row("SELECT count(*) FROM foo", [], |r| r.get(0))
+//!             });
+//!         }
+//!     });
+//!     Ok(())
+//! }
+//! ```
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::{
+    Connection, InterruptHandle, OpenFlags, Result, Transaction, TransactionBehavior, ffi,
+};
+
+/// Connections waiting to be used
+struct Slot {
+    connections: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl Slot {
+    fn new(connections: Vec<Connection>) -> Self {
+        Self {
+            connections: Mutex::new(connections),
+            available: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Connection>> {
+        self.connections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take(&self) -> Connection {
+        let mut connections = self.lock();
+        loop {
+            if let Some(conn) = connections.pop() {
+                return conn;
+            }
+            connections = self
+                .available
+                .wait(connections)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn try_take(&self) -> Option<Connection> {
+        self.lock().pop()
+    }
+
+    fn give_back(&self, conn: Connection) {
+        self.lock().push(conn);
+        self.available.notify_one();
+    }
+}
+
+/// Pool of one read-write connection and `n` read-only connections to the
+/// same database file, in WAL mode.
+///
+/// `Pool` is `Sync`: share it between threads with a reference or an `Arc`.
+pub struct Pool {
+    writer: Slot,
+    readers: Slot,
+    interrupts: Vec<InterruptHandle>,
+}
+
+impl Pool {
+    /// Open the database at `path` (created if it does not exist), switch it
+    /// to WAL mode and open one writer and `readers` read-only connections.
+    ///
+    /// `init` is called on every connection right after it is opened, to set
+    /// pragmas, or register functions and collations.
+    ///
+    /// # Failure
+    ///
+    /// Will return `Err` if `readers` is zero, if a connection cannot be
+    /// opened, if `init` fails or if the database cannot be switched to WAL
+    /// mode (for example, if `path` is `:memory:`).
+    pub fn open<P, F>(path: P, readers: usize, init: F) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: Fn(&Connection) -> Result<()>,
+    {
+        if readers == 0 {
+            return Err(err!(ffi::SQLITE_MISUSE, "a pool needs at least one reader"));
+        }
+        let path = path.as_ref();
+        let writer = Connection::open_with_flags(path, OpenFlags::default())?;
+        init(&writer)?;
+        let mode: String =
+            writer.pragma_update_and_check(None, "journal_mode", "wal", |r| r.get(0))?;
+        if !mode.eq_ignore_ascii_case("wal") {
+            return Err(err!(ffi::SQLITE_MISUSE, "journal mode is {mode}, not wal"));
+        }
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX
+            | OpenFlags::SQLITE_OPEN_URI;
+        let readers = (0..readers)
+            .map(|_| {
+                let conn = Connection::open_with_flags(path, flags)?;
+                init(&conn)?;
+                Ok(conn)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let interrupts = std::iter::once(&writer)
+            .chain(&readers)
+            .map(Connection::get_interrupt_handle)
+            .collect();
+        Ok(Self {
+            writer: Slot::new(vec![writer]),
+            readers: Slot::new(readers),
+            interrupts,
+        })
+    }
+
+    /// Get a read-only connection, blocking until one is available.
+    pub fn reader(&self) -> PooledConnection<'_> {
+        PooledConnection::new(&self.readers, self.readers.take())
+    }
+
+    /// Get a read-only connection if one is available.
+    pub fn try_reader(&self) -> Option<PooledConnection<'_>> {
+        let conn = self.readers.try_take()?;
+        Some(PooledConnection::new(&self.readers, conn))
+    }
+
+    /// Get the read-write connection, blocking until it is available.
+    pub fn writer(&self) -> PooledConnection<'_> {
+        PooledConnection::new(&self.writer, self.writer.take())
+    }
+
+    /// Get the read-write connection if it is available.
+    pub fn try_writer(&self) -> Option<PooledConnection<'_>> {
+        let conn = self.writer.try_take()?;
+        Some(PooledConnection::new(&self.writer, conn))
+    }
+
+    /// Run `f` in an `IMMEDIATE` transaction on the writer, blocking until
+    /// it is available.
+    ///
+    /// The transaction is committed if `f` returns `Ok`, and rolled back
+    /// otherwise.
+    pub fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Transaction<'_>) -> Result<T>,
+    {
+        let mut writer = self.writer();
+        let tx = writer.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    /// Interrupt the queries running on all connections of this pool.
+    ///
+    /// See [`InterruptHandle::interrupt`].
+    pub fn interrupt_all(&self) {
+        for interrupt in &self.interrupts {
+            interrupt.interrupt();
+        }
+    }
+}
+
+/// Connection borrowed from a [`Pool`], given back when dropped.
+pub struct PooledConnection<'p> {
+    slot: &'p Slot,
+    conn: Option<Connection>,
+}
+
+impl<'p> PooledConnection<'p> {
+    fn new(slot: &'p Slot, conn: Connection) -> Self {
+        Self {
+            slot,
+            conn: Some(conn),
+        }
+    }
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            // Do not leak a transaction started with a `BEGIN` statement to
+            // the next user.
+            if !conn.is_autocommit() {
+                let _ = conn.execute_batch("ROLLBACK");
+            }
+            self.slot.give_back(conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::sync::Barrier;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::Pool;
+    use crate::{ErrorCode, Result};
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_pool() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("pool.db3");
+        let pool = Pool::open(&path, 3, |conn| conn.pragma_update(None, "cache_size", 42))?;
+        pool.transaction(|tx| {
+            tx.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (42);")
+        })?;
+
+        // readers are read-only
+        let err = pool
+            .reader()
+            .execute("INSERT INTO foo VALUES (1)", [])
+            .unwrap_err();
+        assert_eq!(Some(ErrorCode::ReadOnly), err.sqlite_error_code());
+
+        // readers run concurrently, while the writer is busy
+        let barrier = Barrier::new(3);
+        let writer = pool.writer();
+        writer.execute_batch("BEGIN IMMEDIATE; INSERT INTO foo VALUES (1);")?;
+        std::thread::scope(|s| {
+            let handles: Vec<_> = (0..3)
+                .map(|_| {
+                    s.spawn(|| -> Result<i64> {
+                        let reader = pool.reader();
+                        barrier.wait();
+                        let cache_size: i64 =
+                            reader.pragma_query_value(None, "cache_size", |r| r.get(0))?;
+                        reader.one_column("SELECT sum(x) FROM foo WHERE x = ?1", [cache_size])
+                    })
+                })
+                .collect();
+            for h in handles {
+                assert_eq!(42, h.join().unwrap().unwrap());
+            }
+        });
+        assert!(pool.try_writer().is_none());
+        // the pending transaction is rolled back
+        drop(writer);
+
+        let n: i64 = pool.reader().one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(1, n);
+
+        // rolled back on error
+        pool.transaction(|tx| {
+            tx.execute("INSERT INTO foo VALUES (2)", [])?;
+            tx.execute("INSERT INTO bar VALUES (2)", [])
+        })
+        .unwrap_err();
+        let n: i64 = pool.reader().one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(1, n);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Pool::open(":memory:", 2, |_| Ok(())).is_err());
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(Pool::open(temp_dir.path().join("pool.db3"), 0, |_| Ok(())).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_interrupt_all() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pool = Pool::open(temp_dir.path().join("pool.db3"), 1, |_| Ok(()))?;
+        let reader = pool.reader();
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                // the interrupt is lost if the statement is not running yet
+                while !done.load(Ordering::Acquire) {
+                    pool.interrupt_all();
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+            });
+            let r = reader.one_column::<i64, _>(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c",
+                [],
+            );
+            done.store(true, Ordering::Release);
+            let err = r.unwrap_err();
+            assert_eq!(
+                Some(ErrorCode::OperationInterrupted),
+                err.sqlite_error_code()
+            );
+        });
+        Ok(())
+    }
+}
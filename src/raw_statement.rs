@@ -1,5 +1,6 @@
 use super::StatementStatus;
 use super::ffi;
+use crate::Result;
 use crate::util::ParamIndexCache;
 use crate::util::SqliteMallocString;
 use std::ffi::{CStr, c_int};
@@ -25,6 +26,10 @@ pub struct RawStatement {
     // `statement_cache_key` might differ is if the statement has a `tail`.
     #[cfg(feature = "cache")]
     statement_cache_key: Option<Arc<str>>,
+    // What `step` does when a table is locked by another connection sharing
+    // the same cache.
+    #[cfg(feature = "unlock_notify")]
+    unlock_wait: crate::unlock_notify::UnlockWait,
 }
 
 impl RawStatement {
@@ -35,6 +40,8 @@ impl RawStatement {
             cache: ParamIndexCache::default(),
             #[cfg(feature = "cache")]
             statement_cache_key: None,
+            #[cfg(feature = "unlock_notify")]
+            unlock_wait: crate::unlock_notify::UnlockWait::default(),
         }
     }
 
@@ -55,6 +62,12 @@ impl RawStatement {
         self.statement_cache_key.clone()
     }
 
+    #[inline]
+    #[cfg(feature = "unlock_notify")]
+    pub(crate) fn set_unlock_wait(&mut self, unlock_wait: crate::unlock_notify::UnlockWait) {
+        self.unlock_wait = unlock_wait;
+    }
+
     #[inline]
     pub unsafe fn ptr(&self) -> *mut ffi::sqlite3_stmt {
         self.ptr
@@ -142,30 +155,36 @@ impl RawStatement {
     }
 
     #[inline]
-    pub fn step(&self) -> c_int {
+    pub fn step(&self) -> Result<c_int> {
         cfg_select! {
             feature = "unlock_notify" => {
                 use crate::unlock_notify;
                 let mut db = ptr::null_mut::<ffi::sqlite3>();
+                let mut deadline = None;
                 loop {
                     unsafe {
-                        let mut rc = ffi::sqlite3_step(self.ptr);
+                        let rc = ffi::sqlite3_step(self.ptr);
                         // Bail out early for success and errors unrelated to locking. We
                         // still need check `is_locked` after this, but checking now lets us
                         // avoid one or two (admittedly cheap) calls into SQLite that we
                         // don't need to make.
                         if (rc & 0xff) != ffi::SQLITE_LOCKED {
-                            break rc;
+                            break Ok(rc);
                         }
                         if db.is_null() {
                             db = ffi::sqlite3_db_handle(self.ptr);
                         }
                         if !unlock_notify::is_locked(db, rc) {
-                            break rc;
+                            break Ok(rc);
                         }
-                        rc = unlock_notify::wait_for_unlock_notify(db);
-                        if rc != ffi::SQLITE_OK {
-                            break rc;
+                        // The deadline is computed once, on the first wait.
+                        let Some(d) = *deadline.get_or_insert_with(|| self.unlock_wait.deadline()) else {
+                            break Ok(rc);
+                        };
+                        match unlock_notify::wait_for_unlock_notify(db, d) {
+                            unlock_notify::Unlock::Released => {}
+                            unlock_notify::Unlock::Deadlock => break Err(crate::Error::Deadlock),
+                            unlock_notify::Unlock::TimedOut => break Ok(rc),
                         }
                         self.reset();
                     }
@@ -173,7 +192,7 @@ impl RawStatement {
             }
             _ => {
                 // no_fmt
-                unsafe { ffi::sqlite3_step(self.ptr) }
+                Ok(unsafe { ffi::sqlite3_step(self.ptr) })
             }
         }
     }
@@ -3,7 +3,7 @@ use fallible_streaming_iterator::FallibleStreamingIterator;
 use std::convert;
 
 use super::{Error, Result, Statement};
-use crate::types::{FromSql, FromSqlError, ValueRef};
+use crate::types::{Coerce, FromSql, FromSqlError, FromSqlRef, Type, ValueRef};
 
 /// A handle (lazy fallible streaming iterator) for the resulting rows of a query.
 #[must_use = "Rows is lazy and will do nothing unless consumed"]
@@ -281,25 +281,88 @@ impl Row<'_> {
     /// If the result type is i128 (which requires the `i128_blob` feature to be
     /// enabled), and the underlying SQLite column is a blob whose size is not
     /// 16 bytes, `Error::InvalidColumnType` will also be returned.
+    ///
+    /// In checked mode (see [`Statement::set_checked`]), returns an
+    /// `Error::DeclTypeMismatch` if `T` does not match the declared type of
+    /// the column.
     #[track_caller]
     pub fn get<I: RowIndex, T: FromSql>(&self, idx: I) -> Result<T> {
         let idx = idx.idx(self.stmt)?;
+        #[cfg(feature = "column_decltype")]
+        self.stmt
+            .check_column_type(idx, std::any::type_name::<T>(), |v| {
+                !matches!(T::column_result(v), Err(FromSqlError::InvalidType))
+            })?;
         let value = self.stmt.value_ref(idx);
-        FromSql::column_result(value).map_err(|err| match err {
-            FromSqlError::InvalidType => Error::InvalidColumnType(
-                idx,
-                self.stmt.column_name_unwrap(idx).into(),
-                value.data_type(),
-            ),
+        FromSql::column_result(value)
+            .map_err(|err| self.map_from_sql_error(idx, value.data_type(), err))
+    }
+
+    /// Get the value of a particular column of the result row, borrowing TEXT
+    /// and BLOB content instead of copying it.
+    ///
+    /// Like [`get_ref`](Row::get_ref), the value is valid only as long as this
+    /// Row, which is enforced by its lifetime.
+    ///
+    /// ```rust,no_run
+    /// # use rusqlite::{Connection, Result};
+    /// fn total_len(conn: &Connection) -> Result<usize> {
+    ///     let mut stmt = conn.prepare("SELECT name FROM people")?;
+    ///     let mut rows = stmt.query([])?;
+    ///     let mut len = 0;
+    ///     while let Some(row) = rows.next()? {
+    ///         let name: &str = row.get_borrowed(0)?;
+    ///         len += name.len();
+    ///     }
+    ///     Ok(len)
+    /// }
+    /// ```
+    ///
+    /// ## Failure
+    ///
+    /// Same as [`get`](Row::get).
+    #[track_caller]
+    pub fn get_borrowed<'a, I: RowIndex, T: FromSqlRef<'a>>(&'a self, idx: I) -> Result<T> {
+        let idx = idx.idx(self.stmt)?;
+        #[cfg(feature = "column_decltype")]
+        self.stmt
+            .check_column_type(idx, std::any::type_name::<T>(), |v| {
+                !matches!(T::column_result_ref(v), Err(FromSqlError::InvalidType))
+            })?;
+        let value = self.stmt.value_ref(idx);
+        FromSqlRef::column_result_ref(value)
+            .map_err(|err| self.map_from_sql_error(idx, value.data_type(), err))
+    }
+
+    /// Get the value of a particular column of the result row, converting it
+    /// like SQLite type affinity does when `T` does not accept the stored
+    /// type.
+    ///
+    /// Shortcut for `row.get::<_, Coerce<T>>(idx)`, see
+    /// [`Coerce`](crate::types::Coerce).
+    ///
+    /// ## Failure
+    ///
+    /// Same as [`get`](Row::get).
+    #[track_caller]
+    pub fn get_coerced<I: RowIndex, T: FromSql>(&self, idx: I) -> Result<T> {
+        self.get::<_, Coerce<T>>(idx).map(|Coerce(v)| v)
+    }
+
+    fn map_from_sql_error(&self, idx: usize, data_type: Type, err: FromSqlError) -> Error {
+        match err {
+            FromSqlError::InvalidType => {
+                Error::InvalidColumnType(idx, self.stmt.column_name_unwrap(idx).into(), data_type)
+            }
             FromSqlError::OutOfRange(i) => Error::IntegralValueOutOfRange(idx, i),
             FromSqlError::Utf8Error(err) => Error::Utf8Error(idx, err),
-            FromSqlError::Other(err) => {
-                Error::FromSqlConversionFailure(idx, value.data_type(), err)
-            }
-            FromSqlError::InvalidBlobSize { .. } => {
-                Error::FromSqlConversionFailure(idx, value.data_type(), Box::new(err))
+            FromSqlError::Other(err) => Error::FromSqlConversionFailure(idx, data_type, err),
+            FromSqlError::InvalidBlobSize { .. }
+            | FromSqlError::PrecisionLoss(_)
+            | FromSqlError::Overflow(_) => {
+                Error::FromSqlConversionFailure(idx, data_type, Box::new(err))
             }
-        })
+        }
     }
 
     /// Get the value of a particular column of the result row as a `ValueRef`,
@@ -451,6 +514,41 @@ impl RowIndex for &'_ str {
     }
 }
 
+/// A trait implemented by types that can be built from a result row.
+///
+/// With the `rusqlite-macros` feature, it can be derived for structs: fields of
+/// structs with named fields are read from the column with the same name, and
+/// fields of tuple structs from the column at the same index.
+///
+/// Fields accept the following `#[rusqlite(...)]` attributes:
+/// * `rename = "column"` reads the field from `column` instead,
+/// * `default` uses `Default::default()` if the column does not exist,
+/// * `skip` does not read the field, and uses `Default::default()`,
+/// * `flatten` reads the field, itself implementing `FromRow`, from the same
+///   row.
+///
+#[cfg_attr(feature = "rusqlite-macros", doc = "```rust,no_run")]
+#[cfg_attr(not(feature = "rusqlite-macros"), doc = "```rust,ignore")]
+/// # use rusqlite::{Connection, FromRow, Result};
+/// #[derive(FromRow)]
+/// struct Person {
+///     id: i64,
+///     #[rusqlite(rename = "full_name")]
+///     name: String,
+///     #[rusqlite(default)]
+///     email: Option<String>,
+/// }
+///
+/// fn people(conn: &Connection) -> Result<Vec<Person>> {
+///     let mut stmt = conn.prepare("SELECT id, full_name FROM person")?;
+///     stmt.query_map([], Person::from_row)?.collect()
+/// }
+/// ```
+pub trait FromRow: Sized {
+    /// Build a value from `row`.
+    fn from_row(row: &Row<'_>) -> Result<Self>;
+}
+
 macro_rules! tuple_try_from_row {
     ($($field:ident),*) => {
         impl<'a, $($field,)*> convert::TryFrom<&'a Row<'a>> for ($($field,)*) where $($field: FromSql,)* {
@@ -472,13 +570,62 @@ macro_rules! tuple_try_from_row {
     }
 }
 
+/// A trait for types that can be built from a row, borrowing TEXT and BLOB
+/// content from it.
+///
+/// Implemented for tuples (up to 16 elements) of [`FromSqlRef`] values, read
+/// by column index:
+///
+/// ```rust,no_run
+/// # use rusqlite::{Connection, FromRowRef, Result};
+/// fn longest_name(conn: &Connection) -> Result<Option<i64>> {
+///     let mut stmt = conn.prepare("SELECT id, name FROM people")?;
+///     let mut rows = stmt.query([])?;
+///     let mut longest = None;
+///     let mut max = 0;
+///     while let Some(row) = rows.next()? {
+///         let (id, name) = <(i64, &str)>::from_row_ref(row)?;
+///         if name.len() > max {
+///             longest = Some(id);
+///             max = name.len();
+///         }
+///     }
+///     Ok(longest)
+/// }
+/// ```
+pub trait FromRowRef<'a>: Sized {
+    /// Build a value from `row`.
+    fn from_row_ref(row: &'a Row<'_>) -> Result<Self>;
+}
+
+macro_rules! tuple_from_row_ref {
+    ($($field:ident),*) => {
+        impl<'a, $($field,)*> FromRowRef<'a> for ($($field,)*) where $($field: FromSqlRef<'a>,)* {
+            // we end with index += 1, which rustc warns about
+            // unused_variables and unused_mut are allowed for ()
+            #[allow(unused_assignments, unused_variables, unused_mut)]
+            fn from_row_ref(row: &'a Row<'_>) -> Result<Self> {
+                let mut index = 0;
+                $(
+                    #[expect(non_snake_case)]
+                    let $field = row.get_borrowed::<_, $field>(index)?;
+                    index += 1;
+                )*
+                Ok(($($field,)*))
+            }
+        }
+    }
+}
+
 macro_rules! tuples_try_from_row {
     () => {
         // not very useful, but maybe some other macro users will find this helpful
         tuple_try_from_row!();
+        tuple_from_row_ref!();
     };
     ($first:ident $(, $remaining:ident)*) => {
         tuple_try_from_row!($first $(, $remaining)*);
+        tuple_from_row_ref!($first $(, $remaining)*);
         tuples_try_from_row!($($remaining),*);
     };
 }
@@ -679,4 +826,56 @@ mod tests {
         assert!(!p_ret.is_null());
         Ok(())
     }
+
+    #[test]
+    fn test_get_borrowed() -> Result<()> {
+        use crate::Error;
+        use crate::types::{Type, ValueRef};
+        use std::borrow::Cow;
+
+        let conn = Connection::open_in_memory()?;
+        let mut stmt = conn.prepare("SELECT 'text', x'0102', NULL, 42")?;
+        let mut rows = stmt.query([])?;
+        let row = rows.next()?.unwrap();
+        assert_eq!("text", row.get_borrowed::<_, &str>(0)?);
+        assert_eq!(&[1, 2], row.get_borrowed::<_, &[u8]>(1)?);
+        assert!(matches!(
+            row.get_borrowed::<_, Cow<'_, str>>(0)?,
+            Cow::Borrowed("text")
+        ));
+        assert!(matches!(
+            row.get_borrowed::<_, Cow<'_, [u8]>>(1)?,
+            Cow::Borrowed([1, 2])
+        ));
+        assert_eq!(None, row.get_borrowed::<_, Option<&str>>(2)?);
+        assert_eq!(Some("text"), row.get_borrowed::<_, Option<&str>>(0)?);
+        assert_eq!(
+            ValueRef::Integer(42),
+            row.get_borrowed::<_, ValueRef<'_>>(3)?
+        );
+        assert_eq!(42, row.get_borrowed::<_, i64>(3)?);
+        assert_eq!(
+            Error::InvalidColumnType(3, "42".to_owned(), Type::Integer),
+            row.get_borrowed::<_, &str>(3).unwrap_err()
+        );
+        assert_eq!(
+            Error::InvalidColumnIndex(4),
+            row.get_borrowed::<_, &str>(4).unwrap_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_row_ref_for_tuple() -> Result<()> {
+        use crate::FromRowRef as _;
+
+        let conn = Connection::open_in_memory()?;
+        let mut stmt = conn.prepare("SELECT 1, 'one', x'01', NULL")?;
+        let mut rows = stmt.query([])?;
+        let row = rows.next()?.unwrap();
+        let (i, s, b, n) = <(i64, &str, &[u8], Option<&str>)>::from_row_ref(row)?;
+        assert_eq!((1, "one", &[1u8][..], None), (i, s, b, n));
+        assert!(<(i64, i64)>::from_row_ref(row).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,239 @@
+use std::ffi::{CStr, c_char, c_int, c_void};
+use std::ptr;
+
+use crate::{Statement, ffi};
+
+/// Measured and estimated performance of a query plan element, like a
+/// "SCAN" or "SEARCH" loop, a subquery or a temporary b-tree.
+///
+/// See [`Statement::scan_status`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ScanStatus {
+    /// Id of the element, unique within the statement, as in the first column
+    /// of `EXPLAIN QUERY PLAN`
+    pub id: i32,
+    /// Id of the parent element, or 0, as in the second column of
+    /// `EXPLAIN QUERY PLAN`
+    pub parent_id: i32,
+    /// `EXPLAIN QUERY PLAN` description, like "SCAN t"
+    pub explain: String,
+    /// Name of the table or index used by a loop
+    pub name: Option<String>,
+    /// Number of times the loop has run, if available (not for subqueries)
+    pub loops: Option<i64>,
+    /// Number of rows visited by all the runs of the loop
+    pub rows_visited: Option<i64>,
+    /// Query planner estimate of the number of rows output by each run of
+    /// the loop
+    pub estimated_rows: Option<f64>,
+    /// Number of processor time-stamp counter cycles spent on the element,
+    /// if available
+    pub cycles: Option<i64>,
+    /// Nested elements, in `EXPLAIN QUERY PLAN` order
+    pub children: Vec<ScanStatus>,
+}
+
+impl ScanStatus {
+    /// Average number of rows visited by each run of the loop, to compare
+    /// with [`estimated_rows`](Self::estimated_rows)
+    #[must_use]
+    pub fn actual_rows(&self) -> Option<f64> {
+        match (self.rows_visited, self.loops) {
+            (Some(rows), Some(loops)) if loops > 0 => Some(rows as f64 / loops as f64),
+            _ => None,
+        }
+    }
+}
+
+impl Statement<'_> {
+    /// Measured and estimated performance of the query plan elements of this
+    /// statement, as a tree like `EXPLAIN QUERY PLAN` output.
+    ///
+    /// Counters accumulate over all the runs of the statement since it was
+    /// prepared or since [`reset_scan_status`](Self::reset_scan_status).
+    /// They are only collected while
+    /// [`SQLITE_DBCONFIG_STMT_SCANSTATUS`](crate::config::DbConfig::SQLITE_DBCONFIG_STMT_SCANSTATUS)
+    /// is enabled (the default).
+    ///
+    /// Requires SQLite >= 3.42.0 compiled with `SQLITE_ENABLE_STMT_SCANSTATUS`
+    /// (see the `stmt_scanstatus` feature).
+    ///
+    /// ```rust,no_run
+    /// # use rusqlite::{Connection, Result, ScanStatus};
+    /// fn profile(conn: &Connection, sql: &str) -> Result<()> {
+    ///     let mut stmt = conn.prepare(sql)?;
+    ///     let mut rows = stmt.query([])?;
+    ///     while rows.next()?.is_some() {}
+    ///     drop(rows);
+    ///     fn print(elements: &[ScanStatus], depth: usize) {
+    ///         for e in elements {
+    ///             println!(
+    ///                 "{:depth$}{}: estimated {:?}, actual {:?} rows",
+    ///                 "",
+    ///                 e.explain,
+    ///                 e.estimated_rows,
+    ///                 e.actual_rows()
+    ///             );
+    ///             print(&e.children, depth + 2);
+    ///         }
+    ///     }
+    ///     print(&stmt.scan_status(), 0);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn scan_status(&self) -> Vec<ScanStatus> {
+        let mut elements = Vec::new();
+        while let Some(element) = self.scan_status_element(elements.len() as c_int) {
+            elements.push(element);
+        }
+        // children follow their parent
+        for i in (0..elements.len()).rev() {
+            let parent_id = elements[i].parent_id;
+            if let Some(parent) = elements[..i].iter().rposition(|e| e.id == parent_id) {
+                let element = elements.remove(i);
+                elements[parent].children.insert(0, element);
+            }
+        }
+        elements
+    }
+
+    /// Reset the counters reported by [`scan_status`](Self::scan_status).
+    #[inline]
+    pub fn reset_scan_status(&self) {
+        unsafe { ffi::sqlite3_stmt_scanstatus_reset(self.stmt.ptr()) }
+    }
+
+    fn scan_status_element(&self, idx: c_int) -> Option<ScanStatus> {
+        let explain = self.scan_status_str(idx, ffi::SQLITE_SCANSTAT_EXPLAIN)?;
+        let loops = self.scan_status_i64(idx, ffi::SQLITE_SCANSTAT_NLOOP);
+        Some(ScanStatus {
+            id: self.scan_status_int(idx, ffi::SQLITE_SCANSTAT_SELECTID),
+            parent_id: self.scan_status_int(idx, ffi::SQLITE_SCANSTAT_PARENTID),
+            explain,
+            name: self.scan_status_str(idx, ffi::SQLITE_SCANSTAT_NAME),
+            loops,
+            rows_visited: self.scan_status_i64(idx, ffi::SQLITE_SCANSTAT_NVISIT),
+            // only meaningful for loops
+            estimated_rows: loops.and_then(|_| self.scan_status_f64(idx, ffi::SQLITE_SCANSTAT_EST)),
+            cycles: self.scan_status_i64(idx, ffi::SQLITE_SCANSTAT_NCYCLE),
+            children: Vec::new(),
+        })
+    }
+
+    /// `int` value (`SQLITE_SCANSTAT_SELECTID` and `SQLITE_SCANSTAT_PARENTID`),
+    /// 0 if not available
+    fn scan_status_int(&self, idx: c_int, op: c_int) -> c_int {
+        let mut value: c_int = 0;
+        unsafe { self.scan_status_raw(idx, op, ptr::from_mut(&mut value).cast()) };
+        value
+    }
+
+    /// `sqlite3_int64` value (`SQLITE_SCANSTAT_NLOOP`, `SQLITE_SCANSTAT_NVISIT`
+    /// and `SQLITE_SCANSTAT_NCYCLE`), `None` if not available
+    fn scan_status_i64(&self, idx: c_int, op: c_int) -> Option<i64> {
+        let mut value: ffi::sqlite3_int64 = -1;
+        unsafe { self.scan_status_raw(idx, op, ptr::from_mut(&mut value).cast()) };
+        (value >= 0).then_some(value)
+    }
+
+    /// `double` value (`SQLITE_SCANSTAT_EST`), `None` if not available
+    fn scan_status_f64(&self, idx: c_int, op: c_int) -> Option<f64> {
+        let mut value: f64 = -1.0;
+        unsafe { self.scan_status_raw(idx, op, ptr::from_mut(&mut value).cast()) };
+        (value >= 0.0).then_some(value)
+    }
+
+    /// String value (`SQLITE_SCANSTAT_NAME` and `SQLITE_SCANSTAT_EXPLAIN`),
+    /// `None` if not available or if `idx` is out of range
+    fn scan_status_str(&self, idx: c_int, op: c_int) -> Option<String> {
+        let mut s: *const c_char = ptr::null();
+        let rc = unsafe { self.scan_status_raw(idx, op, ptr::from_mut(&mut s).cast()) };
+        if rc != 0 || s.is_null() {
+            return None;
+        }
+        Some(unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned())
+    }
+
+    /// # Safety
+    ///
+    /// `out` must point to a value of the type documented for `op`.
+    unsafe fn scan_status_raw(&self, idx: c_int, op: c_int, out: *mut c_void) -> c_int {
+        unsafe {
+            ffi::sqlite3_stmt_scanstatus_v2(
+                self.stmt.ptr(),
+                idx,
+                op,
+                ffi::SQLITE_SCANSTAT_COMPLEX,
+                out,
+            )
+        }
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use crate::{Connection, Result};
+
+    #[test]
+    fn test_scan_status() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(
+            "CREATE TABLE a (id INTEGER PRIMARY KEY, x);
+             CREATE TABLE b (id INTEGER PRIMARY KEY, a_id);
+             WITH RECURSIVE s(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM s WHERE n < 20)
+             INSERT INTO a (x) SELECT n FROM s WHERE n <= 10;
+             WITH RECURSIVE s(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM s WHERE n < 20)
+             INSERT INTO b (a_id) SELECT n % 5 + 1 FROM s;",
+        )?;
+        let mut stmt = db.prepare(
+            "SELECT a.x, count(*) FROM b JOIN a ON a.id = b.a_id \
+             WHERE b.id IN (SELECT id FROM b WHERE a_id < 3) GROUP BY a.x",
+        )?;
+        assert_eq!(2, stmt.query_map([], |_| Ok(()))?.count());
+
+        let plan = stmt.scan_status();
+        let find = |prefix: &str| {
+            plan.iter()
+                .find(|e| e.explain.starts_with(prefix))
+                .unwrap_or_else(|| panic!("{prefix} not in {plan:#?}"))
+        };
+        let search_b = find("SEARCH b");
+        assert_eq!(Some("b"), search_b.name.as_deref());
+        assert_eq!(0, search_b.parent_id);
+        assert_eq!(Some(1), search_b.loops);
+        // 8 rows of b with a_id < 3
+        assert_eq!(Some(8), search_b.rows_visited);
+        assert_eq!(Some(8.0), search_b.actual_rows());
+        assert!(search_b.estimated_rows.is_some());
+        assert!(search_b.children.is_empty());
+        let search_a = find("SEARCH a");
+        assert_eq!(Some(8), search_a.loops);
+        assert_eq!(Some(1.0), search_a.actual_rows());
+
+        // subquery with its own loop
+        let list = find("LIST SUBQUERY");
+        assert_eq!(None, list.loops);
+        assert_eq!(None, list.estimated_rows);
+        let [child] = &list.children[..] else {
+            panic!("{list:#?}");
+        };
+        assert_eq!(list.id, child.parent_id);
+        assert_eq!("SCAN b", child.explain);
+        assert_eq!(Some(20), child.rows_visited);
+
+        stmt.reset_scan_status();
+        let plan = stmt.scan_status();
+        let search_b = plan
+            .iter()
+            .find(|e| e.explain.starts_with("SEARCH b"))
+            .unwrap();
+        assert_eq!(Some(0), search_b.loops);
+        assert_eq!(None, search_b.actual_rows());
+        Ok(())
+    }
+}
@@ -0,0 +1,1061 @@
+//! [serde](https://serde.rs) support: deserialize result rows, and serialize
+//! named parameters.
+//!
+//! Rows are deserialized like maps from column names to column values:
+//!
+//! | SQLite  | Rust                                              |
+//! |---------|---------------------------------------------------|
+//! | NULL    | `None`, `()`                                      |
+//! | INTEGER | integers, floats, `bool`                          |
+//! | REAL    | floats                                            |
+//! | TEXT    | strings, unit enum variants, or nested JSON for sequences, maps, structs and other enum variants |
+//! | BLOB    | bytes, `Vec<u8>`, `[u8; N]`                       |
+//!
+//! ```rust,no_run
+//! # use rusqlite::{Connection, Result};
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Person {
+//!     name: String,
+//!     tags: Vec<String>, // stored as JSON text
+//! }
+//!
+//! fn copy(conn: &Connection) -> Result<()> {
+//!     let mut stmt = conn.prepare("SELECT name, tags FROM person")?;
+//!     let people: Vec<Person> = stmt.query([])?.deserialize().collect::<Result<_>>()?;
+//!     for person in &people {
+//!         let params = rusqlite::serde::to_params(person)?;
+//!         conn.execute("INSERT INTO archive (name, tags) VALUES (:name, :tags)", &params)?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::fmt;
+use std::str;
+
+use ::serde::de::value::{SeqDeserializer, StrDeserializer};
+use ::serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor,
+};
+use ::serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use crate::error::UNKNOWN_COLUMN;
+use crate::params::Sealed;
+use crate::types::{Type, Value, ValueRef};
+use crate::{Error, MappedRows, Params, Result, Row, Rows, Statement};
+
+/// Deserialize a result row, with columns mapped to fields by name.
+///
+/// # Failure
+///
+/// Will return `Err` if a column cannot be converted to the type of its field,
+/// or if a field is missing (unless it has a default).
+pub fn from_row<T: DeserializeOwned>(row: &Row<'_>) -> Result<T> {
+    T::deserialize(RowDeserializer { row })
+}
+
+impl<'stmt> Rows<'stmt> {
+    /// Deserialize each row with [`from_row`].
+    #[inline]
+    pub fn deserialize<T: DeserializeOwned>(self) -> MappedRows<'stmt, fn(&Row<'_>) -> Result<T>> {
+        self.mapped(from_row::<T> as fn(&Row<'_>) -> Result<T>)
+    }
+}
+
+impl de::Error for Error {
+    #[cold]
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        // The column index and type are set by `RowDeserializer`, if known.
+        Self::FromSqlConversionFailure(UNKNOWN_COLUMN, Type::Null, msg.to_string().into())
+    }
+}
+
+impl ser::Error for Error {
+    #[cold]
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::ToSqlConversionFailure(msg.to_string().into())
+    }
+}
+
+/// Add the column index and type to a deserialization error
+fn with_column(err: Error, idx: usize, value: ValueRef<'_>) -> Error {
+    match err {
+        Error::FromSqlConversionFailure(UNKNOWN_COLUMN, _, err) => {
+            Error::FromSqlConversionFailure(idx, value.data_type(), err)
+        }
+        Error::Utf8Error(UNKNOWN_COLUMN, err) => Error::Utf8Error(idx, err),
+        err => err,
+    }
+}
+
+struct RowDeserializer<'a, 'stmt> {
+    row: &'a Row<'stmt>,
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer<'_, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(Columns {
+            stmt: self.row.as_ref(),
+            idx: 0,
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let stmt = self.row.as_ref();
+        let values = (0..stmt.column_count()).map(|idx| ColumnDeserializer { stmt, idx });
+        let mut seq = SeqDeserializer::<_, Error>::new(values);
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+/// Columns of a row, as a map
+struct Columns<'a, 'stmt> {
+    stmt: &'a Statement<'stmt>,
+    idx: usize,
+}
+
+impl<'de> MapAccess<'de> for Columns<'_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.idx >= self.stmt.column_count() {
+            return Ok(None);
+        }
+        let name = self.stmt.column_name_unwrap(self.idx);
+        seed.deserialize(StrDeserializer::new(name)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let idx = self.idx;
+        self.idx += 1;
+        seed.deserialize(ColumnDeserializer {
+            stmt: self.stmt,
+            idx,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.stmt.column_count() - self.idx)
+    }
+}
+
+struct ColumnDeserializer<'a, 'stmt> {
+    stmt: &'a Statement<'stmt>,
+    idx: usize,
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let value = self.stmt.value_ref(self.idx);
+                ValueDeserializer { value }
+                    .$method(visitor)
+                    .map_err(|err| with_column(err, self.idx, value))
+            }
+        )*
+    };
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ColumnDeserializer<'_, '_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for ColumnDeserializer<'_, '_> {
+    type Error = Error;
+
+    forward_to_value! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_option deserialize_unit deserialize_seq deserialize_map
+        deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        let value = self.stmt.value_ref(self.idx);
+        ValueDeserializer { value }
+            .deserialize_unit_struct(name, visitor)
+            .map_err(|err| with_column(err, self.idx, value))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let value = self.stmt.value_ref(self.idx);
+        ValueDeserializer { value }
+            .deserialize_enum(name, variants, visitor)
+            .map_err(|err| with_column(err, self.idx, value))
+    }
+}
+
+/// Deserializer of a single value
+struct ValueDeserializer<'a> {
+    value: ValueRef<'a>,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn text(&self) -> Result<Option<&'a str>> {
+        match self.value {
+            ValueRef::Text(s) => Ok(Some(str::from_utf8(s)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn invalid_type<T>(&self, exp: &dyn de::Expected) -> Result<T> {
+        let unexp = match self.value {
+            ValueRef::Null => de::Unexpected::Unit,
+            ValueRef::Integer(i) => de::Unexpected::Signed(i),
+            ValueRef::Real(f) => de::Unexpected::Float(f),
+            ValueRef::Text(s) => de::Unexpected::Bytes(s),
+            ValueRef::Blob(b) => de::Unexpected::Bytes(b),
+        };
+        Err(de::Error::invalid_type(unexp, exp))
+    }
+
+    /// Deserialize nested JSON text (`from_reader` because visitors may not
+    /// borrow from the row)
+    fn json<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let Some(text) = self.text()? else {
+            return self.invalid_type(&visitor);
+        };
+        let mut de = serde_json::Deserializer::from_reader(text.as_bytes());
+        de.deserialize_any(visitor)
+            .and_then(|value| de.end().map(|()| value))
+            .map_err(|err| Error::FromSqlConversionFailure(UNKNOWN_COLUMN, Type::Text, err.into()))
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueRef::Null => visitor.visit_unit(),
+            ValueRef::Integer(i) => visitor.visit_i64(i),
+            ValueRef::Real(f) => visitor.visit_f64(f),
+            ValueRef::Text(_) => visitor.visit_str(self.text()?.unwrap()),
+            ValueRef::Blob(b) => visitor.visit_bytes(b),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueRef::Integer(i) => visitor.visit_bool(i != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueRef::Integer(i) => visitor.visit_f64(i as f64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueRef::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueRef::Blob(b) => {
+                let mut seq = SeqDeserializer::<_, Error>::new(b.iter().copied());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            _ => self.json(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.json(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.json(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let Some(text) = self.text()? else {
+            return self.invalid_type(&visitor);
+        };
+        if text.trim_start().starts_with(['{', '"']) {
+            let mut de = serde_json::Deserializer::from_reader(text.as_bytes());
+            de.deserialize_enum(name, variants, visitor)
+                .and_then(|value| de.end().map(|()| value))
+                .map_err(|err| {
+                    Error::FromSqlConversionFailure(UNKNOWN_COLUMN, Type::Text, err.into())
+                })
+        } else {
+            visitor.visit_enum(text.into_deserializer())
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string bytes byte_buf
+        unit identifier ignored_any
+    }
+}
+
+/// Named parameters serialized by [`to_params`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NamedParams(Vec<(String, Value)>);
+
+impl NamedParams {
+    /// Parameter names (prefixed with `:`) and values
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[(String, Value)] {
+        &self.0
+    }
+}
+
+impl Sealed for &NamedParams {}
+impl Params for &NamedParams {
+    fn __bind_in(self, stmt: &mut Statement<'_>) -> Result<()> {
+        for (name, value) in &self.0 {
+            stmt.raw_bind_parameter(name.as_str(), value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Sealed for NamedParams {}
+impl Params for NamedParams {
+    #[inline]
+    fn __bind_in(self, stmt: &mut Statement<'_>) -> Result<()> {
+        (&self).__bind_in(stmt)
+    }
+}
+
+/// Serialize a struct (or a map with string keys) to named parameters: each
+/// field is bound to the `:field_name` parameter.
+///
+/// | Rust                                      | SQLite  |
+/// |-------------------------------------------|---------|
+/// | `None`, `()`                              | NULL    |
+/// | integers, `bool`                          | INTEGER |
+/// | floats                                    | REAL    |
+/// | strings, unit enum variants               | TEXT    |
+/// | bytes (see `serde_bytes`)                 | BLOB    |
+/// | sequences, maps, structs, other variants  | JSON TEXT |
+///
+/// # Failure
+///
+/// Will return `Err` if `value` is not a struct or a map, or if a field
+/// cannot be serialized.
+pub fn to_params<T: Serialize + ?Sized>(value: &T) -> Result<NamedParams> {
+    value.serialize(ParamsSerializer).map(NamedParams)
+}
+
+/// Serialize a field value, or fail with `Nested` for values to be serialized
+/// as JSON
+enum ValueError {
+    Nested,
+    Error(Error),
+}
+
+impl fmt::Debug for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nested => f.write_str("Nested"),
+            Self::Error(err) => err.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nested => f.write_str("nested value"),
+            Self::Error(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+impl ser::Error for ValueError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Error(ser::Error::custom(msg))
+    }
+}
+
+fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    match value.serialize(ValueSerializer) {
+        Ok(value) => Ok(value),
+        Err(ValueError::Nested) => serde_json::to_string(value)
+            .map(Value::Text)
+            .map_err(|err| Error::ToSqlConversionFailure(err.into())),
+        Err(ValueError::Error(err)) => Err(err),
+    }
+}
+
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+    type SerializeSeq = Impossible<Value, ValueError>;
+    type SerializeTuple = Impossible<Value, ValueError>;
+    type SerializeTupleStruct = Impossible<Value, ValueError>;
+    type SerializeTupleVariant = Impossible<Value, ValueError>;
+    type SerializeMap = Impossible<Value, ValueError>;
+    type SerializeStruct = Impossible<Value, ValueError>;
+    type SerializeStructVariant = Impossible<Value, ValueError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, ValueError> {
+        i64::try_from(v)
+            .map(Value::Integer)
+            .map_err(|err| ValueError::Error(Error::ToSqlConversionFailure(err.into())))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, ValueError> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, ValueError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, ValueError> {
+        i64::try_from(v)
+            .map(Value::Integer)
+            .map_err(|err| ValueError::Error(Error::ToSqlConversionFailure(err.into())))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, ValueError> {
+        Ok(Value::Real(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, ValueError> {
+        Ok(Value::Real(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, ValueError> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, ValueError> {
+        Ok(Value::Text(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, ValueError> {
+        Ok(Value::Blob(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, ValueError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, ValueError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, ValueError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, ValueError> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, ValueError> {
+        Ok(Value::Text(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, ValueError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, ValueError> {
+        Err(ValueError::Nested)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, ValueError> {
+        Err(ValueError::Nested)
+    }
+}
+
+type Fields = Vec<(String, Value)>;
+
+/// Serializer of a struct or map to named parameters
+struct ParamsSerializer;
+
+#[cold]
+fn not_a_struct<T>() -> Result<T> {
+    Err(ser::Error::custom("parameters must be a struct or a map"))
+}
+
+impl Serializer for ParamsSerializer {
+    type Ok = Fields;
+    type Error = Error;
+    type SerializeSeq = Impossible<Fields, Error>;
+    type SerializeTuple = Impossible<Fields, Error>;
+    type SerializeTupleStruct = Impossible<Fields, Error>;
+    type SerializeTupleVariant = Impossible<Fields, Error>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = Impossible<Fields, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_none(self) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Fields> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Fields> {
+        Ok(Fields::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Fields> {
+        Ok(Fields::new())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Fields> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Fields> {
+        not_a_struct()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        not_a_struct()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        not_a_struct()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        not_a_struct()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        not_a_struct()
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer> {
+        Ok(MapSerializer {
+            fields: Fields::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        not_a_struct()
+    }
+}
+
+struct MapSerializer {
+    fields: Fields,
+    key: Option<String>,
+}
+
+impl MapSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<()> {
+        let value = to_value(value)?;
+        self.fields.push((format!(":{name}"), value));
+        Ok(())
+    }
+}
+
+impl SerializeMap for MapSerializer {
+    type Ok = Fields;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match to_value(key)? {
+            Value::Text(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("parameter names must be strings")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.push(&key, value)
+    }
+
+    fn end(self) -> Result<Fields> {
+        Ok(self.fields)
+    }
+}
+
+impl SerializeStruct for MapSerializer {
+    type Ok = Fields;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Fields> {
+        Ok(self.fields)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{from_row, to_params};
+    use crate::types::{Type, Value};
+    use crate::{Connection, Error, Result};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Kind {
+        Admin,
+        Guest { until: i64 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Address {
+        city: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Person {
+        id: i64,
+        name: String,
+        score: f64,
+        active: bool,
+        nickname: Option<String>,
+        avatar: Vec<u8>,
+        tags: Vec<String>,
+        address: Address,
+        kind: Kind,
+        #[serde(default, skip_serializing)]
+        missing: u8,
+    }
+
+    fn db() -> Result<Connection> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(
+            "CREATE TABLE person (id, name, score, active, nickname, avatar, tags, address, kind)",
+        )?;
+        Ok(db)
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let db = db()?;
+        let people = [
+            Person {
+                id: 1,
+                name: "Lisa".to_owned(),
+                score: 1.0,
+                active: true,
+                nickname: None,
+                avatar: vec![1, 2, 3],
+                tags: vec!["smart".to_owned()],
+                address: Address {
+                    city: "Springfield".to_owned(),
+                },
+                kind: Kind::Admin,
+                missing: 0,
+            },
+            Person {
+                id: 2,
+                name: "Bart".to_owned(),
+                score: 0.5,
+                active: false,
+                nickname: Some("El Barto".to_owned()),
+                avatar: vec![],
+                tags: vec![],
+                address: Address {
+                    city: "Shelbyville".to_owned(),
+                },
+                kind: Kind::Guest { until: 42 },
+                missing: 0,
+            },
+        ];
+        let mut stmt = db.prepare(
+            "INSERT INTO person VALUES (:id, :name, :score, :active, :nickname, :avatar, :tags, :address, :kind)",
+        )?;
+        for person in &people {
+            stmt.execute(to_params(person)?)?;
+        }
+
+        let (tags, avatar): (String, String) =
+            db.query_row("SELECT tags, avatar FROM person WHERE id = 1", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })?;
+        assert_eq!(r#"["smart"]"#, tags);
+        // `Vec<u8>` is a sequence for serde
+        assert_eq!("[1,2,3]", avatar);
+
+        let mut stmt = db.prepare("SELECT * FROM person ORDER BY id")?;
+        let rows = stmt
+            .query([])?
+            .deserialize::<Person>()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(people.as_slice(), rows.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_row() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let map: BTreeMap<String, Option<i64>> =
+            db.query_row("SELECT 1 AS a, NULL AS b", [], from_row)?;
+        assert_eq!(Some(&Some(1)), map.get("a"));
+        assert_eq!(Some(&None), map.get("b"));
+
+        let tuple: (i64, String) = db.query_row("SELECT 1, 'x'", [], from_row)?;
+        assert_eq!((1, "x".to_owned()), tuple);
+
+        let (blob, array): (Vec<u8>, [u8; 2]) =
+            db.query_row("SELECT x'010203', x'0405'", [], from_row)?;
+        assert_eq!(vec![1, 2, 3], blob);
+        assert_eq!([4, 5], array);
+
+        let err = db
+            .query_row("SELECT 'x' AS id", [], from_row::<Address>)
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::FromSqlConversionFailure(_, Type::Null, _)),
+            "{err:?}"
+        );
+
+        let err = db
+            .query_row("SELECT 1 AS city", [], from_row::<Address>)
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::FromSqlConversionFailure(0, Type::Integer, _)),
+            "{err:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_params() -> Result<()> {
+        let mut map = BTreeMap::new();
+        map.insert("a", 1);
+        let params = to_params(&map)?;
+        assert_eq!(&[(":a".to_owned(), Value::Integer(1))], params.as_slice());
+        to_params(&42).unwrap_err();
+        to_params(&u64::MAX).unwrap_err();
+        to_params(&[("a", u64::MAX)].into_iter().collect::<BTreeMap<_, _>>()).unwrap_err();
+        Ok(())
+    }
+}
@@ -14,6 +14,8 @@ use crate::types::{ToSql, ToSqlOutput, Value};
 pub struct Statement<'conn> {
     pub(crate) conn: &'conn Connection,
     pub(crate) stmt: RawStatement,
+    #[cfg(feature = "column_decltype")]
+    pub(crate) decl_type_check: Option<crate::column::DeclTypeCheck>,
 }
 
 impl Statement<'_> {
@@ -613,6 +615,8 @@ impl Statement<'_> {
     // generic because many of these branches can constant fold away.
     fn bind_parameter<P: ?Sized + ToSql>(&self, param: &P, ndx: usize) -> Result<()> {
         let value = param.to_sql()?;
+        #[cfg(feature = "column_decltype")]
+        self.check_parameter_type(ndx, &value)?;
 
         let ptr = unsafe { self.stmt.ptr() };
         self.conn.decode_result(match value {
@@ -692,6 +696,7 @@ impl Statement<'_> {
         self.check_update()?;
         let r = self.stmt.step();
         let rr = self.stmt.reset();
+        let r = r?;
         match r {
             ffi::SQLITE_DONE => match rr {
                 ffi::SQLITE_OK => Ok(self.conn.changes() as usize),
@@ -776,6 +781,16 @@ impl Statement<'_> {
         self.stmt.clear_bindings();
     }
 
+    /// Set what stepping this statement does when a table is locked by
+    /// another connection sharing the same cache.
+    ///
+    /// The default is [`UnlockWait::Forever`](crate::unlock_notify::UnlockWait::Forever).
+    /// Statements returned to the cache are reset to the default.
+    #[cfg(feature = "unlock_notify")]
+    pub fn set_unlock_wait(&mut self, unlock_wait: crate::unlock_notify::UnlockWait) {
+        self.stmt.set_unlock_wait(unlock_wait);
+    }
+
     pub(crate) unsafe fn ptr(&self) -> *mut ffi::sqlite3_stmt {
         unsafe { self.stmt.ptr() }
     }
@@ -807,7 +822,12 @@ impl Drop for Statement<'_> {
 impl Statement<'_> {
     #[inline]
     pub(super) fn new(conn: &Connection, stmt: RawStatement) -> Statement<'_> {
-        Statement { conn, stmt }
+        Statement {
+            conn,
+            stmt,
+            #[cfg(feature = "column_decltype")]
+            decl_type_check: None,
+        }
     }
 
     pub(super) fn value_ref(&self, col: usize) -> ValueRef<'_> {
@@ -868,7 +888,7 @@ impl Statement<'_> {
 
     #[inline]
     pub(super) fn step(&self) -> Result<bool> {
-        match self.stmt.step() {
+        match self.stmt.step()? {
             ffi::SQLITE_ROW => Ok(true),
             ffi::SQLITE_DONE => Ok(false),
             code => Err(self.conn.decode_result(code).unwrap_err()),
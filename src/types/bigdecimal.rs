@@ -0,0 +1,87 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`BigDecimal`].
+use std::str::FromStr as _;
+
+use bigdecimal::BigDecimal;
+
+use crate::Result;
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+/// Serialize `BigDecimal` to canonical text: plain notation, without trailing
+/// zeros in the fractional part.
+impl ToSql for BigDecimal {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.normalized().to_plain_string()))
+    }
+}
+
+/// Deserialize INTEGER, REAL (through its shortest decimal representation)
+/// and TEXT (in plain or scientific notation) to `BigDecimal`.
+impl FromSql for BigDecimal {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(i) => Ok(Self::from(i)),
+            ValueRef::Real(f) => Self::from_str(&f.to_string()).map_err(FromSqlError::other),
+            ValueRef::Text(_) => Self::from_str(value.as_str()?).map_err(FromSqlError::other),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use bigdecimal::BigDecimal;
+
+    use crate::types::Type;
+    use crate::{Connection, Error, Result};
+
+    fn checked_memory_handle() -> Result<Connection> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (d)")?;
+        Ok(db)
+    }
+
+    #[test]
+    fn test_to_sql() -> Result<()> {
+        let db = checked_memory_handle()?;
+        for (d, s) in [
+            ("1.50", "1.5"),
+            ("-0.000", "0"),
+            ("1e3", "1000"),
+            (
+                "123456789012345678901234567890.123456789012345678901234567890",
+                "123456789012345678901234567890.12345678901234567890123456789",
+            ),
+        ] {
+            let d: BigDecimal = d.parse().unwrap();
+            let v: String = db.one_column("SELECT ?1", [&d])?;
+            assert_eq!(s, v);
+            let v: BigDecimal = db.one_column("SELECT ?1", [&d])?;
+            assert_eq!(d, v);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_sql() -> Result<()> {
+        let db = checked_memory_handle()?;
+        db.execute_batch("INSERT INTO foo VALUES (42), (0.1), ('19.99'), ('-1e3'), (x'00')")?;
+        let mut stmt = db.prepare("SELECT d FROM foo")?;
+        let values: Vec<Result<BigDecimal>> = stmt.query_map([], |r| r.get(0))?.collect();
+        assert_eq!(BigDecimal::from(42), *values[0].as_ref().unwrap());
+        assert_eq!(BigDecimal::new(1.into(), 1), *values[1].as_ref().unwrap());
+        assert_eq!(
+            BigDecimal::new(1999.into(), 2),
+            *values[2].as_ref().unwrap()
+        );
+        assert_eq!(BigDecimal::from(-1000), *values[3].as_ref().unwrap());
+        assert!(matches!(
+            values[4],
+            Err(Error::InvalidColumnType(_, _, Type::Blob))
+        ));
+        Ok(())
+    }
+}
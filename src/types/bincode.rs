@@ -0,0 +1,112 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`Bincode`].
+
+use bincode::config::standard;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use crate::{Error, Result};
+
+/// Wrapper to persist any serializable value as a
+/// [bincode](https://crates.io/crates/bincode) BLOB, with the standard
+/// configuration.
+///
+/// The encoding is compact but not self-describing: changing the shape of `T`
+/// makes previously stored values unreadable.
+///
+/// ```rust,no_run
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::Bincode;
+/// fn insert_point(conn: &Connection, point: (f64, f64)) -> Result<usize> {
+///     conn.execute("INSERT INTO points (p) VALUES (?1)", [Bincode(point)])
+/// }
+///
+/// fn point(conn: &Connection, id: i64) -> Result<(f64, f64)> {
+///     conn.query_row("SELECT p FROM points WHERE id = ?1", [id], |row| {
+///         let Bincode(point) = row.get(0)?;
+///         Ok(point)
+///     })
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bincode<T>(pub T);
+
+/// Serialize to BLOB.
+impl<T: Serialize> ToSql for Bincode<T> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        bincode::serde::encode_to_vec(&self.0, standard())
+            .map(ToSqlOutput::from)
+            .map_err(|err| Error::ToSqlConversionFailure(err.into()))
+    }
+}
+
+/// Deserialize from BLOB. Fails if the BLOB has trailing bytes.
+impl<T: DeserializeOwned> FromSql for Bincode<T> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let blob = value.as_blob()?;
+        let (v, len) =
+            bincode::serde::decode_from_slice(blob, standard()).map_err(FromSqlError::other)?;
+        if len != blob.len() {
+            return Err(FromSqlError::Other(
+                format!("{} trailing bytes", blob.len() - len).into(),
+            ));
+        }
+        Ok(Self(v))
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::types::{Bincode, Type};
+    use crate::{Connection, Error, Result};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x: i64,
+        y: Option<String>,
+    }
+
+    #[test]
+    fn test_bincode() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (b BLOB)")?;
+        let p = Point {
+            x: 1,
+            y: Some("one".to_owned()),
+        };
+        db.execute("INSERT INTO foo (b) VALUES (?1)", [Bincode(&p)])?;
+        let b: Vec<u8> = db.one_column("SELECT b FROM foo", [])?;
+        assert_eq!(b"\x02\x01\x03one", &b[..]);
+        let Bincode(v): Bincode<Point> = db.one_column("SELECT b FROM foo", [])?;
+        assert_eq!(p, v);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let err = db
+            .one_column::<Bincode<Point>, _>("SELECT 'text'", [])
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidColumnType(0, _, Type::Text)));
+        for blob in ["x'02'", "x'020000'"] {
+            let err = db
+                .query_row(&format!("SELECT 1, {blob}"), [], |r| {
+                    r.get::<_, Bincode<Point>>(1)
+                })
+                .unwrap_err();
+            assert!(
+                matches!(err, Error::FromSqlConversionFailure(1, Type::Blob, _)),
+                "{blob}: {err}"
+            );
+        }
+        Ok(())
+    }
+}
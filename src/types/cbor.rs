@@ -0,0 +1,116 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`Cbor`].
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use crate::{Error, Result};
+
+/// Wrapper to persist any serializable value as a
+/// [CBOR](https://cbor.io) BLOB.
+///
+/// ```rust,no_run
+/// # use std::collections::HashMap;
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::Cbor;
+/// fn insert_settings(conn: &Connection, settings: &HashMap<String, i64>) -> Result<usize> {
+///     conn.execute("INSERT INTO users (settings) VALUES (?1)", [Cbor(settings)])
+/// }
+///
+/// fn settings(conn: &Connection, id: i64) -> Result<HashMap<String, i64>> {
+///     conn.query_row("SELECT settings FROM users WHERE id = ?1", [id], |row| {
+///         let Cbor(settings) = row.get(0)?;
+///         Ok(settings)
+///     })
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cbor<T>(pub T);
+
+/// Serialize to BLOB.
+impl<T: Serialize> ToSql for Cbor<T> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        let mut blob = Vec::new();
+        ciborium::into_writer(&self.0, &mut blob)
+            .map_err(|err| Error::ToSqlConversionFailure(err.into()))?;
+        Ok(ToSqlOutput::from(blob))
+    }
+}
+
+/// Deserialize from BLOB. Fails if the BLOB has trailing bytes.
+impl<T: DeserializeOwned> FromSql for Cbor<T> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let mut blob = value.as_blob()?;
+        let v = ciborium::from_reader(&mut blob).map_err(FromSqlError::other)?;
+        if !blob.is_empty() {
+            return Err(FromSqlError::Other(
+                format!("{} trailing bytes", blob.len()).into(),
+            ));
+        }
+        Ok(Self(v))
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::types::{Cbor, Type};
+    use crate::{Connection, Error, Result};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x: i64,
+        y: Option<String>,
+    }
+
+    #[test]
+    fn test_cbor() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (b BLOB)")?;
+        let p = Point {
+            x: 1,
+            y: Some("one".to_owned()),
+        };
+        db.execute("INSERT INTO foo (b) VALUES (?1)", [Cbor(&p)])?;
+        let t: String = db.one_column("SELECT typeof(b) FROM foo", [])?;
+        assert_eq!("blob", t);
+        let Cbor(v): Cbor<Point> = db.one_column("SELECT b FROM foo", [])?;
+        assert_eq!(p, v);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let err = db
+            .one_column::<Cbor<Point>, _>("SELECT 1, x'ff'", [])
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidColumnType(0, _, Type::Integer)));
+        let err = db
+            .query_row("SELECT 1, x'a0'", [], |r| r.get::<_, Cbor<Point>>(1))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FromSqlConversionFailure(1, Type::Blob, _)
+        ));
+        let mut blob = Vec::new();
+        ciborium::into_writer(&Point { x: 1, y: None }, &mut blob).unwrap();
+        blob.push(0);
+        let err = db
+            .one_column::<Cbor<Point>, _>("SELECT ?1", [blob])
+            .unwrap_err();
+        match err {
+            Error::FromSqlConversionFailure(0, Type::Blob, err) => {
+                assert_eq!("1 trailing bytes", err.to_string());
+            }
+            err => panic!("unexpected error {err}"),
+        }
+        Ok(())
+    }
+}
@@ -5,7 +5,9 @@ use chrono::{
 };
 
 use crate::Result;
-use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, ValueRef};
+use crate::types::{
+    FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, UnixTime, ValueRef,
+};
 
 /// ISO 8601 calendar date without timezone => "YYYY-MM-DD"
 impl ToSql for NaiveDate {
@@ -154,6 +156,70 @@ impl FromSql for DateTime<FixedOffset> {
     }
 }
 
+impl UnixTime for DateTime<Utc> {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        let secs = i128::from(self.timestamp());
+        Some(secs * 1_000_000_000 + i128::from(self.timestamp_subsec_nanos()))
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        let secs = i64::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+        Self::from_timestamp(secs, nanos.rem_euclid(1_000_000_000) as u32)
+    }
+}
+
+impl UnixTime for DateTime<Local> {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        self.to_utc().unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        DateTime::<Utc>::from_unix_nanos(nanos).map(|dt| dt.with_timezone(&Local))
+    }
+}
+
+impl UnixTime for DateTime<FixedOffset> {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        self.to_utc().unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        DateTime::<Utc>::from_unix_nanos(nanos).map(|dt| dt.fixed_offset())
+    }
+}
+
+/// Interpreted as UTC.
+impl UnixTime for NaiveDateTime {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        self.and_utc().unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        DateTime::<Utc>::from_unix_nanos(nanos).map(|dt| dt.naive_utc())
+    }
+}
+
+/// Midnight UTC, the time of day is truncated when read.
+impl UnixTime for NaiveDate {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        self.and_time(NaiveTime::MIN).unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        NaiveDateTime::from_unix_nanos(nanos).map(|dt| dt.date())
+    }
+}
+
 #[cfg(all(test, not(miri)))]
 mod test {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
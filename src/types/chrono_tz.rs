@@ -0,0 +1,121 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`DateTime<Tz>`].
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+
+use crate::Result;
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+/// Date and time with IANA time zone => RFC 9557 timestamp, with the time zone
+/// annotation ("YYYY-MM-DDTHH:MM:SS.SSS[+-]HH:MM[Time/Zone]").
+impl ToSql for DateTime<Tz> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        let date_str = format!("{}[{}]", self.format("%FT%T%.f%:z"), self.timezone().name());
+        Ok(ToSqlOutput::from(date_str))
+    }
+}
+
+/// RFC 9557 ("YYYY-MM-DDTHH:MM:SS.SSS[+-]HH:MM[Time/Zone]") into
+/// `DateTime<Tz>`.
+///
+/// Values with an offset but without an IANA time zone annotation (RFC3339
+/// or RFC 9557 with an offset annotation) get the `Etc/GMT∓N` time zone
+/// matching a whole-hour offset, so that the offset is kept like with the
+/// fixed offset time zone of jiff `Zoned`. Other offsets (like `+05:30`),
+/// values without offset and unix timestamps are converted to UTC: the offset
+/// is lost.
+impl FromSql for DateTime<Tz> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        if let ValueRef::Text(_) = value {
+            let s = value.as_str()?;
+            if let Some((dt, zone)) = s.strip_suffix(']').and_then(|s| s.split_once('[')) {
+                // critical flag
+                let zone = zone.strip_prefix('!').unwrap_or(zone);
+                let dt = DateTime::<FixedOffset>::column_result(ValueRef::Text(dt.as_bytes()))?;
+                if zone.starts_with(['+', '-']) {
+                    return Ok(dt.with_timezone(&offset_zone(*dt.offset())));
+                }
+                let tz: Tz = zone.parse().map_err(FromSqlError::other)?;
+                return Ok(dt.with_timezone(&tz));
+            }
+            if let Ok(dt) = DateTime::<FixedOffset>::column_result(value) {
+                return Ok(dt.with_timezone(&offset_zone(*dt.offset())));
+            }
+        }
+        DateTime::<Utc>::column_result(value).map(|dt| dt.with_timezone(&Tz::UTC))
+    }
+}
+
+/// `Etc/GMT∓N` time zone for a whole-hour `offset` (with the POSIX inverted
+/// sign), UTC otherwise
+fn offset_zone(offset: FixedOffset) -> Tz {
+    let secs = offset.local_minus_utc();
+    match secs / 3600 {
+        0 => Tz::UTC,
+        hours if secs % 3600 == 0 => format!("Etc/GMT{:+}", -hours).parse().unwrap_or(Tz::UTC),
+        _ => Tz::UTC,
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use chrono::{DateTime, Months, TimeZone as _};
+    use chrono_tz::Tz;
+
+    use crate::{Connection, Result};
+
+    #[test]
+    fn test_date_time_tz() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (t TEXT)")?;
+        let dt = chrono_tz::Europe::Paris
+            .with_ymd_and_hms(2024, 7, 1, 9, 30, 0)
+            .unwrap();
+        db.execute("INSERT INTO foo (t) VALUES (?1)", [dt])?;
+
+        let s: String = db.one_column("SELECT t FROM foo", [])?;
+        assert_eq!("2024-07-01T09:30:00+02:00[Europe/Paris]", s);
+        let v: DateTime<Tz> = db.one_column("SELECT t FROM foo", [])?;
+        assert_eq!(dt, v);
+        assert_eq!(chrono_tz::Europe::Paris, v.timezone());
+        // the zone is kept across DST changes
+        let winter = v + Months::new(6);
+        assert_eq!("2025-01-01T09:30:00+01:00", winter.to_rfc3339());
+        // SQLite date and time functions ignore the annotation
+        let s: String = db.one_column("SELECT datetime(substr(t, 1, 25)) FROM foo", [])?;
+        assert_eq!("2024-07-01 07:30:00", s);
+
+        // offset only
+        for s in [
+            "2024-07-01 09:30:00+02:00",
+            "2024-07-01T09:30:00+02:00[+02:00]",
+        ] {
+            let v: DateTime<Tz> = db.one_column("SELECT ?1", [s])?;
+            assert_eq!(dt, v, "{s}");
+            assert_eq!(Tz::Etc__GMTMinus2, v.timezone());
+            assert_eq!("2024-07-01T09:30:00+02:00", v.to_rfc3339());
+        }
+        let v: DateTime<Tz> = db.one_column("SELECT '2024-07-01T07:30:00Z'", [])?;
+        assert_eq!(dt, v);
+        assert_eq!(Tz::UTC, v.timezone());
+        let v: DateTime<Tz> = db.one_column("SELECT '2024-07-01 02:30:00-05:00'", [])?;
+        assert_eq!(dt, v);
+        assert_eq!(Tz::Etc__GMTPlus5, v.timezone());
+        // the offset is lost if it is not a whole number of hours
+        let v: DateTime<Tz> = db.one_column("SELECT '2024-07-01T13:00:00+05:30[+05:30]'", [])?;
+        assert_eq!(dt, v);
+        assert_eq!(Tz::UTC, v.timezone());
+        assert_eq!("2024-07-01T07:30:00+00:00", v.to_rfc3339());
+        let v: DateTime<Tz> = db.one_column("SELECT unixepoch('2024-07-01T07:30:00Z')", [])?;
+        assert_eq!(dt, v);
+
+        let r: Result<DateTime<Tz>> =
+            db.one_column("SELECT '2024-07-01T09:30:00+02:00[Nowhere/Unknown]'", []);
+        assert!(r.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+//! [`FromSql`] implementation for [`Coerce`].
+
+use crate::types::{FromSql, FromSqlError, FromSqlResult, Value, ValueRef};
+
+/// Wrapper to read loosely typed values, converting them like SQLite
+/// [type affinity](https://sqlite.org/datatype3.html#type_affinity) does, when
+/// `T` does not accept the stored type:
+///
+/// | SQLite                          | Converted to               |
+/// |---------------------------------|----------------------------|
+/// | INTEGER                         | TEXT                       |
+/// | REAL without fractional part    | INTEGER                    |
+/// | REAL                            | TEXT (shortest round-trip) |
+/// | well-formed integer TEXT        | INTEGER                    |
+/// | well-formed real TEXT           | REAL (or INTEGER as above) |
+///
+/// NULL and BLOB values are never converted. Range checks still apply to the
+/// converted value, and a REAL with a fractional part read as an integer fails
+/// with [`FromSqlError::PrecisionLoss`].
+///
+/// See also [`Row::get_coerced`](crate::Row::get_coerced).
+///
+/// ```rust,no_run
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::Coerce;
+/// fn ages(conn: &Connection) -> Result<Vec<i64>> {
+///     // `age` may contain 42, 42.0 or '42'
+///     let mut stmt = conn.prepare("SELECT age FROM legacy_people")?;
+///     let rows = stmt.query_map([], |row| row.get::<_, Coerce<i64>>(0))?;
+///     rows.map(|r| r.map(|Coerce(age)| age)).collect()
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coerce<T>(pub T);
+
+impl<T: FromSql> FromSql for Coerce<T> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match T::column_result(value) {
+            Err(FromSqlError::InvalidType) => {}
+            r => return r.map(Self),
+        }
+        match value {
+            ValueRef::Integer(i) => from_text(&i.to_string()),
+            ValueRef::Real(f) => match from_number(value) {
+                Err(FromSqlError::InvalidType) => from_text(&real_to_text(f)),
+                r => r,
+            },
+            ValueRef::Text(_) => match parse_number(value.as_str()?) {
+                Some(number) => from_number(number),
+                None => Err(FromSqlError::InvalidType),
+            },
+            ValueRef::Null | ValueRef::Blob(_) => Err(FromSqlError::InvalidType),
+        }
+        .map(Self)
+    }
+}
+
+fn from_text<T: FromSql>(s: &str) -> FromSqlResult<T> {
+    T::column_result(ValueRef::Text(s.as_bytes()))
+}
+
+/// Like `T::column_result` but with integral REAL converted to INTEGER
+fn from_number<T: FromSql>(value: ValueRef<'_>) -> FromSqlResult<T> {
+    match T::column_result(value) {
+        Err(FromSqlError::InvalidType) => {}
+        r => return r,
+    }
+    if let ValueRef::Real(f) = value {
+        // saturating cast
+        let i = f as i64;
+        match T::column_result(ValueRef::Integer(i)) {
+            Err(FromSqlError::InvalidType) => {}
+            // 2^63 is not representable as i64 but equals `i64::MAX as f64`
+            r if f.fract() == 0.0 && (i64::MIN as f64..-(i64::MIN as f64)).contains(&f) => {
+                return r;
+            }
+            _ => return Err(FromSqlError::PrecisionLoss(Value::Real(f))),
+        }
+    }
+    Err(FromSqlError::InvalidType)
+}
+
+/// Well-formed integer or real number, with leading and trailing spaces
+fn parse_number(s: &str) -> Option<ValueRef<'static>> {
+    let s = s.trim_ascii();
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        if let Ok(i) = s.parse() {
+            return Some(ValueRef::Integer(i));
+        }
+    } else {
+        // reject "inf", "NaN", ... accepted by Rust but not by SQLite
+        let (mantissa, exponent) = match digits.find(['e', 'E']) {
+            Some(i) => (&digits[..i], Some(&digits[i + 1..])),
+            None => (digits, None),
+        };
+        let (int, fract) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if int.len() + fract.len() == 0 || !is_digits(int) || !is_digits(fract) {
+            return None;
+        }
+        if let Some(exponent) = exponent {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if exponent.is_empty() || !is_digits(exponent) {
+                return None;
+            }
+        }
+    }
+    s.parse().ok().map(ValueRef::Real)
+}
+
+/// Shortest text that round-trips to `f`, laid out like SQLite `%!.17g`
+/// format (older SQLite versions use 15 significant digits instead)
+fn real_to_text(f: f64) -> String {
+    if f.is_infinite() {
+        return if f > 0.0 { "Inf" } else { "-Inf" }.to_owned();
+    }
+    if f == 0.0 {
+        return "0.0".to_owned();
+    }
+    let s = format!("{f:e}");
+    let (mantissa, exponent) = s.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if !(-4..17).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{sign}{:02}", with_fraction(mantissa), exponent.abs())
+    } else {
+        with_fraction(&f.to_string())
+    }
+}
+
+/// Keep at least one fractional digit
+fn with_fraction(s: &str) -> String {
+    if s.contains('.') {
+        s.to_owned()
+    } else {
+        format!("{s}.0")
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::{parse_number, real_to_text};
+    use crate::types::{Coerce, FromSqlError, Type, Value, ValueRef};
+    use crate::{Connection, Error, Result};
+
+    #[test]
+    fn test_real_to_text() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        for (f, expected) in [
+            (1e20, "1.0e+20"),
+            (1.0, "1.0"),
+            (-2.5, "-2.5"),
+            (1e-5, "1.0e-05"),
+            (0.0001, "0.0001"),
+            (-0.0, "0.0"),
+            (1e14, "100000000000000.0"),
+            (2.5e-300, "2.5e-300"),
+            (f64::INFINITY, "Inf"),
+            (f64::NEG_INFINITY, "-Inf"),
+        ] {
+            assert_eq!(expected, real_to_text(f), "{f}");
+            // same as SQLite
+            let s: String = db.one_column("SELECT CAST(?1 AS TEXT)", [f])?;
+            assert_eq!(expected, s, "{f}");
+        }
+        // round-trip, regardless of SQLite precision
+        assert_eq!("0.30000000000000004", real_to_text(0.1 + 0.2));
+        assert_eq!("-0.3333333333333333", real_to_text(-1.0 / 3.0));
+        assert_eq!("10000000000000000.0", real_to_text(1e16));
+        assert_eq!("1.7976931348623157e+308", real_to_text(f64::MAX));
+        assert_eq!("2.2250738585072014e-308", real_to_text(f64::MIN_POSITIVE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(Some(ValueRef::Integer(42)), parse_number(" 42 "));
+        assert_eq!(Some(ValueRef::Integer(-7)), parse_number("-7"));
+        assert_eq!(
+            Some(ValueRef::Real(1e19)),
+            parse_number("9999999999999999999")
+        );
+        assert_eq!(Some(ValueRef::Real(1000.0)), parse_number("1e3"));
+        assert_eq!(Some(ValueRef::Real(0.5)), parse_number("+.5"));
+        assert_eq!(Some(ValueRef::Real(5.0)), parse_number(".5e1"));
+        assert_eq!(Some(ValueRef::Real(1.0)), parse_number("1."));
+        for s in [
+            "", " ", "12abc", "0x10", "inf", "NaN", ".", "1e", "e1", "1.2.3", "--1",
+        ] {
+            assert_eq!(None, parse_number(s), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_coerce() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let i = |sql: &str| db.one_column::<Coerce<i64>, _>(sql, []).map(|c| c.0);
+        assert_eq!(42, i("SELECT 42")?);
+        assert_eq!(42, i("SELECT 42.0")?);
+        assert_eq!(42, i("SELECT ' 42 '")?);
+        assert_eq!(1000, i("SELECT '1e3'")?);
+        match i("SELECT 1.5").unwrap_err() {
+            Error::FromSqlConversionFailure(0, Type::Real, err) => {
+                let err = err.downcast::<FromSqlError>().unwrap();
+                assert_eq!(FromSqlError::PrecisionLoss(Value::Real(1.5)), *err);
+            }
+            err => panic!("unexpected error {err}"),
+        }
+        assert!(matches!(
+            i("SELECT '1.5'").unwrap_err(),
+            Error::FromSqlConversionFailure(0, Type::Text, _)
+        ));
+        assert!(matches!(
+            i("SELECT 1e19").unwrap_err(),
+            Error::FromSqlConversionFailure(0, Type::Real, _)
+        ));
+        assert!(matches!(
+            i("SELECT '12abc'").unwrap_err(),
+            Error::InvalidColumnType(0, _, Type::Text)
+        ));
+        assert!(matches!(
+            i("SELECT x'01'").unwrap_err(),
+            Error::InvalidColumnType(0, _, Type::Blob)
+        ));
+        assert_eq!(
+            Error::IntegralValueOutOfRange(0, 300),
+            db.one_column::<Coerce<i8>, _>("SELECT 300.0", [])
+                .unwrap_err()
+        );
+
+        let f = |sql: &str| db.one_column::<Coerce<f64>, _>(sql, []).map(|c| c.0);
+        assert_eq!(1.5, f("SELECT '1.5'")?);
+        assert_eq!(2.0, f("SELECT '2'")?);
+
+        let s = |sql: &str| db.one_column::<Coerce<String>, _>(sql, []).map(|c| c.0);
+        assert_eq!("42", s("SELECT 42")?);
+        assert_eq!("0.1", s("SELECT 0.1")?);
+        assert_eq!("1.0e+20", s("SELECT 1e20")?);
+
+        let b = |sql: &str| db.one_column::<Coerce<bool>, _>(sql, []).map(|c| c.0);
+        assert!(b("SELECT '1'")?);
+
+        let o = |sql: &str| {
+            db.one_column::<Coerce<Option<i64>>, _>(sql, [])
+                .map(|c| c.0)
+        };
+        assert_eq!(None, o("SELECT NULL")?);
+        assert_eq!(Some(3), o("SELECT '3'")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_coerced() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (x); INSERT INTO foo VALUES ('7'), (8.0), (9);")?;
+        let mut stmt = db.prepare("SELECT x FROM foo")?;
+        let v = stmt
+            .query_map([], |row| row.get_coerced::<_, u8>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(vec![7, 8, 9], v);
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`IsoDuration`].
+
+use std::fmt::{self, Write as _};
+use std::time::Duration;
+
+use crate::Result;
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+const SECS_PER_MINUTE: u64 = 60;
+const SECS_PER_HOUR: u64 = 60 * SECS_PER_MINUTE;
+const SECS_PER_DAY: u64 = 24 * SECS_PER_HOUR;
+const SECS_PER_WEEK: u64 = 7 * SECS_PER_DAY;
+
+/// Wrapper to persist a [`Duration`] as an
+/// [ISO 8601 duration](https://en.wikipedia.org/wiki/ISO_8601#Durations)
+/// TEXT, like "PT1H30M" or "PT0.25S".
+///
+/// Hours, minutes and seconds (with up to 9 fractional digits) are written.
+/// Weeks ("P2W") and days ("P1DT12H") are also accepted when read, as 7 days
+/// and 24 hours. Years and months are rejected as their length varies.
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::IsoDuration;
+/// fn set_timeout(conn: &Connection, job: i64, timeout: Duration) -> Result<usize> {
+///     conn.execute(
+///         "UPDATE jobs SET timeout = ?2 WHERE id = ?1",
+///         (job, IsoDuration(timeout)),
+///     )
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IsoDuration(pub Duration);
+
+impl fmt::Display for IsoDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let nanos = self.0.subsec_nanos();
+        let (hours, minutes, secs) = (
+            secs / SECS_PER_HOUR,
+            secs % SECS_PER_HOUR / SECS_PER_MINUTE,
+            secs % SECS_PER_MINUTE,
+        );
+        f.write_str("PT")?;
+        if hours > 0 {
+            write!(f, "{hours}H")?;
+        }
+        if minutes > 0 {
+            write!(f, "{minutes}M")?;
+        }
+        if secs > 0 || nanos > 0 || (hours == 0 && minutes == 0) {
+            write!(f, "{secs}")?;
+            if nanos > 0 {
+                let fraction = format!("{nanos:09}");
+                write!(f, ".{}", fraction.trim_end_matches('0'))?;
+            }
+            f.write_char('S')?;
+        }
+        Ok(())
+    }
+}
+
+/// ISO 8601 duration TEXT.
+impl ToSql for IsoDuration {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+/// ISO 8601 duration TEXT ("PnWnDTnHnMnS").
+impl FromSql for IsoDuration {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?;
+        parse(s)
+            .map(Self)
+            .ok_or_else(|| FromSqlError::Other(format!("invalid ISO 8601 duration: {s:?}").into()))
+    }
+}
+
+fn parse(s: &str) -> Option<Duration> {
+    let s = s.strip_prefix('P')?;
+    let (date, time) = match s.split_once('T') {
+        Some((_, "")) => return None,
+        Some((date, time)) => (date, time),
+        None if s.is_empty() => return None,
+        None => (s, ""),
+    };
+    let mut duration = Duration::ZERO;
+    parse_components(
+        date,
+        &[('W', SECS_PER_WEEK), ('D', SECS_PER_DAY)],
+        &mut duration,
+    )?;
+    parse_components(
+        time,
+        &[('H', SECS_PER_HOUR), ('M', SECS_PER_MINUTE), ('S', 1)],
+        &mut duration,
+    )?;
+    Some(duration)
+}
+
+/// Add components like "12H", in `units` order, to `duration`. Only seconds
+/// can have a fraction.
+fn parse_components(mut s: &str, units: &[(char, u64)], duration: &mut Duration) -> Option<()> {
+    let mut units = units.iter();
+    while !s.is_empty() {
+        let end = s.find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')?;
+        let (number, rest) = s.split_at(end);
+        let designator = rest.chars().next()?;
+        s = &rest[designator.len_utf8()..];
+        let &(_, secs) = units.find(|(unit, _)| *unit == designator)?;
+        let (int, fraction) = number.split_once(['.', ',']).unwrap_or((number, ""));
+        if int.is_empty() || !int.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let n: u64 = int.parse().ok()?;
+        *duration = duration.checked_add(Duration::from_secs(n.checked_mul(secs)?))?;
+        if int.len() < number.len() {
+            // fraction of a second
+            if secs != 1
+                || fraction.is_empty()
+                || fraction.len() > 9
+                || !fraction.bytes().all(|b| b.is_ascii_digit())
+            {
+                return None;
+            }
+            let nanos: u32 = format!("{fraction:0<9}").parse().ok()?;
+            *duration = duration.checked_add(Duration::from_nanos(nanos.into()))?;
+        }
+    }
+    Some(())
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::time::Duration;
+
+    use super::{IsoDuration, parse};
+    use crate::types::Type;
+    use crate::{Connection, Error, Result};
+
+    #[test]
+    fn test_display() {
+        for (d, s) in [
+            (Duration::ZERO, "PT0S"),
+            (Duration::from_millis(250), "PT0.25S"),
+            (Duration::from_nanos(1), "PT0.000000001S"),
+            (Duration::from_secs(90 * 60), "PT1H30M"),
+            (Duration::from_secs(36 * 3600 + 1), "PT36H1S"),
+            (Duration::new(61, 500_000_000), "PT1M1.5S"),
+        ] {
+            assert_eq!(s, IsoDuration(d).to_string());
+            assert_eq!(Some(d), parse(s), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Some(Duration::from_secs(14 * 86_400)), parse("P2W"));
+        assert_eq!(Some(Duration::from_secs(36 * 3_600)), parse("P1DT12H"));
+        assert_eq!(Some(Duration::from_secs(120)), parse("PT2M"));
+        assert_eq!(Some(Duration::from_millis(1_500)), parse("PT1,5S"));
+        assert_eq!(Some(Duration::from_secs(100)), parse("PT100S"));
+        for s in [
+            "",
+            "P",
+            "PT",
+            "P1DT",
+            "1H",
+            "PT1",
+            "P1Y",
+            "P1M",
+            "PT1.5H",
+            "PT1M1H",
+            "PT1HH",
+            "PT.5S",
+            "PT1.S",
+            "PT1.0000000001S",
+            "PT-1S",
+            "pt1s",
+            "P1D2W",
+        ] {
+            assert_eq!(None, parse(s), "{s}");
+        }
+        assert_eq!(None, parse("PT18446744073709551615H"));
+    }
+
+    #[test]
+    fn test_iso_duration() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let d = Duration::new(5_400, 1_000_000);
+        let s: String = db.one_column("SELECT ?1", [IsoDuration(d)])?;
+        assert_eq!("PT1H30M0.001S", s);
+        let IsoDuration(v) = db.one_column("SELECT ?1", [IsoDuration(d)])?;
+        assert_eq!(d, v);
+
+        let err = db
+            .one_column::<IsoDuration, _>("SELECT 'P1M'", [])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FromSqlConversionFailure(0, Type::Text, _)
+        ));
+        assert_eq!(
+            r#"Conversion error from type Text at index: 0, invalid ISO 8601 duration: "P1M""#,
+            err.to_string()
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 use super::{Value, ValueRef};
 use std::borrow::Cow;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
 use std::str::Utf8Error;
+use std::time::Duration;
 
 /// Enum listing possible errors from [`FromSql`] trait.
 #[derive(Debug)]
@@ -28,6 +32,15 @@ pub enum FromSqlError {
         blob_size: usize,
     },
 
+    /// Error when the value returned by SQLite cannot be represented by the
+    /// requested type without rounding.
+    PrecisionLoss(Value),
+
+    /// Error when the value returned by SQLite is too large (in magnitude) for
+    /// the requested type, like a REAL or TEXT number whose integer part does
+    /// not fit.
+    Overflow(Value),
+
     /// An error case available for implementors of the [`FromSql`] trait.
     Other(Box<dyn Error + Send + Sync + 'static>),
 }
@@ -60,6 +73,8 @@ impl PartialEq for FromSqlError {
                     blob_size: bs2,
                 },
             ) => es1 == es2 && bs1 == bs2,
+            (Self::PrecisionLoss(v1), Self::PrecisionLoss(v2)) => v1 == v2,
+            (Self::Overflow(v1), Self::Overflow(v2)) => v1 == v2,
             (..) => false,
         }
     }
@@ -80,6 +95,10 @@ impl fmt::Display for FromSqlError {
                     "Cannot read {expected_size} byte value out of {blob_size} byte blob"
                 )
             }
+            Self::PrecisionLoss(ref value) => {
+                write!(f, "Value {value:?} cannot be represented without rounding")
+            }
+            Self::Overflow(ref value) => write!(f, "Value {value:?} out of range"),
             Self::Other(ref err) => err.fmt(f),
         }
     }
@@ -106,6 +125,35 @@ impl From<Utf8Error> for FromSqlError {
 pub type FromSqlResult<T> = Result<T, FromSqlError>;
 
 /// A trait for types that can be created from a SQLite value.
+///
+/// With the `rusqlite-macros` feature, `FromSql` and
+/// [`ToSql`](crate::types::ToSql) can be derived for:
+/// * fieldless enums, stored as TEXT variant names (or as the name given by
+///   `#[rusqlite(rename = "name")]` on a variant), or as INTEGER discriminants
+///   with `#[rusqlite(repr = "integer")]` on the enum. Unknown values fail
+///   with [`FromSqlError::Other`].
+/// * structs with a single field, stored as the field.
+///
+#[cfg_attr(feature = "rusqlite-macros", doc = "```rust,no_run")]
+#[cfg_attr(not(feature = "rusqlite-macros"), doc = "```rust,ignore")]
+/// # use rusqlite::types::{FromSql, ToSql};
+/// #[derive(FromSql, ToSql)]
+/// enum Status {
+///     Active,
+///     #[rusqlite(rename = "on_hold")]
+///     OnHold,
+/// }
+///
+/// #[derive(FromSql, ToSql)]
+/// #[rusqlite(repr = "integer")]
+/// enum Priority {
+///     Low = 1,
+///     High = 10,
+/// }
+///
+/// #[derive(FromSql, ToSql)]
+/// struct UserId(i64);
+/// ```
 pub trait FromSql: Sized {
     /// Converts SQLite value into Rust value.
     fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self>;
@@ -283,6 +331,60 @@ impl FromSql for uuid::Uuid {
     }
 }
 
+macro_rules! from_sql_from_str(
+    ($t:ty) => {
+        impl FromSql for $t {
+            #[inline]
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                value.as_str()?.parse().map_err(FromSqlError::other)
+            }
+        }
+    }
+);
+
+from_sql_from_str!(IpAddr);
+from_sql_from_str!(Ipv4Addr);
+from_sql_from_str!(Ipv6Addr);
+from_sql_from_str!(SocketAddr);
+
+/// INTEGER number of nanoseconds => Duration.
+impl FromSql for Duration {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let i = value.as_i64()?;
+        u64::try_from(i)
+            .map(Self::from_nanos)
+            .map_err(|_| FromSqlError::OutOfRange(i))
+    }
+}
+
+/// TEXT or BLOB => `OsString`. On Unix, any bytes are accepted, elsewhere
+/// they must be valid UTF-8.
+impl FromSql for OsString {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let bytes = match value {
+            ValueRef::Text(bytes) | ValueRef::Blob(bytes) => bytes,
+            _ => return Err(FromSqlError::InvalidType),
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt as _;
+            Ok(std::ffi::OsStr::from_bytes(bytes).to_owned())
+        }
+        #[cfg(not(unix))]
+        Ok(std::str::from_utf8(bytes)?.into())
+    }
+}
+
+/// Same as `OsString`.
+impl FromSql for PathBuf {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        OsString::column_result(value).map(Self::from)
+    }
+}
+
 impl<T: FromSql> FromSql for Option<T> {
     #[inline]
     fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
@@ -311,6 +413,88 @@ impl FromSql for Value {
     }
 }
 
+/// A trait for types that can be borrowed from a SQLite value, without
+/// copying TEXT or BLOB content.
+///
+/// Implemented for `&str`, `&[u8]`, `Cow<str>` and `Cow<[u8]>` (which are
+/// always borrowed), [`ValueRef`], `Option<T>` and the owned types of this
+/// module implementing [`FromSql`]. Other [`FromSql`] types can implement it
+/// by delegating to [`FromSql::column_result`].
+///
+/// See [`Row::get_borrowed`](crate::Row::get_borrowed).
+pub trait FromSqlRef<'a>: Sized {
+    /// Converts SQLite value into Rust value, which may borrow from it.
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self>;
+}
+
+impl<'a> FromSqlRef<'a> for &'a str {
+    #[inline]
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        value.as_str()
+    }
+}
+
+impl<'a> FromSqlRef<'a> for &'a [u8] {
+    #[inline]
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        value.as_blob()
+    }
+}
+
+impl<'a> FromSqlRef<'a> for Cow<'a, str> {
+    #[inline]
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        value.as_str().map(Cow::Borrowed)
+    }
+}
+
+impl<'a> FromSqlRef<'a> for Cow<'a, [u8]> {
+    #[inline]
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        value.as_blob().map(Cow::Borrowed)
+    }
+}
+
+impl<'a> FromSqlRef<'a> for ValueRef<'a> {
+    #[inline]
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        Ok(value)
+    }
+}
+
+impl<'a, T: FromSqlRef<'a>> FromSqlRef<'a> for Option<T> {
+    #[inline]
+    fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Null => Ok(None),
+            _ => FromSqlRef::column_result_ref(value).map(Some),
+        }
+    }
+}
+
+macro_rules! from_sql_ref_owned(
+    ($($t:ty),*) => ($(
+        impl<'a> FromSqlRef<'a> for $t {
+            #[inline]
+            fn column_result_ref(value: ValueRef<'a>) -> FromSqlResult<Self> {
+                FromSql::column_result(value)
+            }
+        }
+    )*)
+);
+
+from_sql_ref_owned!(i8, i16, i32, i64, isize, u8, u16, u32, f32, f64, bool);
+from_sql_ref_owned!(String, Box<str>, Vec<u8>, Box<[u8]>, Value);
+from_sql_ref_owned!(
+    IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, Duration, OsString, PathBuf
+);
+#[cfg(feature = "fallible_uint")]
+from_sql_ref_owned!(u64, usize);
+#[cfg(feature = "i128_blob")]
+from_sql_ref_owned!(i128);
+#[cfg(feature = "uuid")]
+from_sql_ref_owned!(uuid::Uuid);
+
 #[cfg(test)]
 mod test {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
@@ -515,4 +699,84 @@ mod test {
         let err = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
         assert!(FromSqlError::Other(Box::new(err)).source().is_some());
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_net() -> Result<()> {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+        let db = Connection::open_in_memory()?;
+        let v4 = Ipv4Addr::new(192, 168, 0, 1);
+        let s: String = db.one_column("SELECT ?1", [v4])?;
+        assert_eq!("192.168.0.1", s);
+        assert_eq!(v4, db.one_column::<Ipv4Addr, _>("SELECT ?1", [v4])?);
+        let v6 = Ipv6Addr::LOCALHOST;
+        assert_eq!(v6, db.one_column::<Ipv6Addr, _>("SELECT '::1'", [])?);
+        let ip = IpAddr::V6(v6);
+        assert_eq!(ip, db.one_column::<IpAddr, _>("SELECT ?1", [ip])?);
+        let addr = SocketAddr::new(ip, 8080);
+        let s: String = db.one_column("SELECT ?1", [addr])?;
+        assert_eq!("[::1]:8080", s);
+        assert_eq!(addr, db.one_column::<SocketAddr, _>("SELECT ?1", [addr])?);
+
+        assert!(matches!(
+            db.one_column::<IpAddr, _>("SELECT '256.0.0.1'", []),
+            Err(Error::FromSqlConversionFailure(0, _, _))
+        ));
+        assert!(matches!(
+            db.one_column::<IpAddr, _>("SELECT 1", []),
+            Err(Error::InvalidColumnType(..))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_duration() -> Result<()> {
+        use std::time::Duration;
+
+        let db = Connection::open_in_memory()?;
+        let d = Duration::new(3, 500);
+        let i: i64 = db.one_column("SELECT ?1", [d])?;
+        assert_eq!(3_000_000_500, i);
+        assert_eq!(d, db.one_column::<Duration, _>("SELECT ?1", [d])?);
+        assert_eq!(
+            Err(Error::IntegralValueOutOfRange(0, -1)),
+            db.one_column::<Duration, _>("SELECT -1", [])
+        );
+        assert!(matches!(
+            db.execute("SELECT ?1", [Duration::MAX]),
+            Err(Error::ToSqlConversionFailure(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_path() -> Result<()> {
+        use std::ffi::OsString;
+        use std::path::{Path, PathBuf};
+
+        let db = Connection::open_in_memory()?;
+        let path = Path::new("/tmp/caf\u{e9}.db");
+        assert_eq!(path, db.one_column::<PathBuf, _>("SELECT ?1", [path])?);
+        let t: String = db.one_column("SELECT typeof(?1)", [path])?;
+        assert_eq!(if cfg!(unix) { "blob" } else { "text" }, t);
+        assert_eq!(
+            OsString::from("a.txt"),
+            db.one_column::<OsString, _>("SELECT 'a.txt'", [])?
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt as _;
+            // not valid UTF-8
+            let name = std::ffi::OsStr::from_bytes(b"caf\xe9");
+            assert_eq!(name, db.one_column::<OsString, _>("SELECT ?1", [name])?);
+            assert_eq!(
+                name,
+                db.one_column::<OsString, _>("SELECT x'636166e9'", [])?
+            );
+        }
+        Ok(())
+    }
 }
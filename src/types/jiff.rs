@@ -1,12 +1,16 @@
 //! Convert some `jiff` types.
 
 use jiff::{
-    Timestamp,
+    Timestamp, Zoned,
     civil::{Date, DateTime, Time},
+    fmt::temporal::{Pieces, PiecesOffset},
+    tz::{Offset, TimeZone},
 };
 
 use crate::Result;
-use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, ValueRef};
+use crate::types::{
+    FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, UnixTime, ValueRef,
+};
 
 /// Gregorian calendar date => "YYYY-MM-DD"
 impl ToSql for Date {
@@ -86,6 +90,83 @@ impl FromSql for Timestamp {
     }
 }
 
+/// Zoned datetime => RFC 9557 timestamp, with the time zone annotation
+/// ("YYYY-MM-DDTHH:MM:SS.SSS[+-]HH:MM[Time/Zone]").
+impl ToSql for Zoned {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+/// RFC 9557 ("YYYY-MM-DDTHH:MM:SS.SSS[+-]HH:MM[Time/Zone]") into `Zoned`.
+///
+/// RFC3339 values without time zone annotation
+/// ("YYYY-MM-DD HH:MM:SS.SSS[+-]HH:MM") get a fixed offset time zone, and unix
+/// timestamps (in seconds) the UTC time zone.
+///
+/// IANA time zone names (like `[Europe/Paris]`) are resolved with the time
+/// zone database of `jiff`, which requires the `jiff-tz` feature (or `jiff`'s
+/// own `tzdb-*` features): without it, these values fail to parse.
+impl FromSql for Zoned {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        if value.data_type() == Type::Integer {
+            return Timestamp::column_result(value).map(|ts| ts.to_zoned(TimeZone::UTC));
+        }
+        let s = value.as_str()?;
+        let pieces = Pieces::parse(s).map_err(FromSqlError::other)?;
+        if pieces.time_zone_annotation().is_some() {
+            return s.parse().map_err(FromSqlError::other);
+        }
+        let tz = match pieces.offset() {
+            Some(PiecesOffset::Numeric(offset)) => TimeZone::fixed(offset.offset()),
+            _ => TimeZone::UTC,
+        };
+        s.parse::<Timestamp>()
+            .map(|ts| ts.to_zoned(tz))
+            .map_err(FromSqlError::other)
+    }
+}
+
+impl UnixTime for Timestamp {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        Some(self.as_nanosecond())
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        let secs = i64::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+        Self::new(secs, nanos.rem_euclid(1_000_000_000) as i32).ok()
+    }
+}
+
+/// Interpreted as UTC.
+impl UnixTime for DateTime {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        Offset::UTC.to_timestamp(*self).ok()?.unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        Timestamp::from_unix_nanos(nanos).map(|ts| Offset::UTC.to_datetime(ts))
+    }
+}
+
+/// Midnight UTC, the time of day is truncated when read.
+impl UnixTime for Date {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        self.to_datetime(Time::midnight()).unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        DateTime::from_unix_nanos(nanos).map(|dt| dt.date())
+    }
+}
+
 #[cfg(all(test, not(miri)))]
 mod test {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
@@ -93,8 +174,9 @@ mod test {
 
     use crate::{Connection, Result};
     use jiff::{
-        Timestamp,
+        Timestamp, Zoned,
         civil::{Date, DateTime, Time},
+        tz::{Offset, TimeZone},
     };
 
     fn checked_memory_handle() -> Result<Connection> {
@@ -220,4 +302,46 @@ mod test {
         }
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "jiff-tz")]
+    fn test_zoned() -> Result<()> {
+        let db = checked_memory_handle()?;
+        let zoned: Zoned = "2024-07-01T09:30:00+02:00[Europe/Paris]".parse().unwrap();
+        db.execute("INSERT INTO foo (t) VALUES (?1)", [&zoned])?;
+
+        let s: String = db.one_column("SELECT t FROM foo", [])?;
+        assert_eq!("2024-07-01T09:30:00+02:00[Europe/Paris]", s);
+        let v: Zoned = db.one_column("SELECT t FROM foo", [])?;
+        assert_eq!(zoned, v);
+        assert_eq!(Some("Europe/Paris"), v.time_zone().iana_name());
+        // the zone is kept across DST changes
+        let winter = v.checked_add(jiff::Span::new().months(6)).unwrap();
+        assert_eq!(Offset::constant(1), winter.offset());
+
+        let r: Result<Zoned> =
+            db.one_column("SELECT '2024-07-01T09:30:00+02:00[Nowhere/Unknown]'", []);
+        assert!(r.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zoned_offset() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let ts: Timestamp = "2024-07-01T07:30:00Z".parse().unwrap();
+        let v: Zoned = db.one_column("SELECT '2024-07-01 09:30:00+02:00'", [])?;
+        assert_eq!(ts, v.timestamp());
+        assert_eq!(TimeZone::fixed(Offset::constant(2)), *v.time_zone());
+        let v: Zoned = db.one_column("SELECT '2024-07-01T09:30:00+02:00[+02:00]'", [])?;
+        assert_eq!(ts, v.timestamp());
+        let v: Zoned = db.one_column("SELECT '2024-07-01T07:30:00Z'", [])?;
+        assert_eq!(ts, v.timestamp());
+        assert_eq!(TimeZone::UTC, *v.time_zone());
+        let v: Zoned = db.one_column("SELECT unixepoch('2024-07-01T07:30:00Z')", [])?;
+        assert_eq!(ts, v.timestamp());
+
+        let r: Result<Zoned> = db.one_column("SELECT '2024-07-01 09:30:00'", []);
+        assert!(r.is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,473 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`Jsonb`].
+//!
+//! See [The SQLite JSONB Format](https://sqlite.org/jsonb.html).
+
+use std::str::Chars;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use crate::{Error, Result};
+
+/// Wrapper to persist a value in the SQLite JSONB binary format
+/// (SQLite >= 3.45.0).
+///
+/// [`ToSql`] serializes the wrapped value to a JSONB BLOB, encoded without
+/// calling SQLite JSON functions.
+///
+/// [`FromSql`] decodes JSONB BLOBs but also JSON TEXT (and INTEGER, REAL or
+/// NULL values like JSON `Value`), so a column can be migrated from JSON text
+/// to JSONB without changing the application code.
+///
+/// ```rust,no_run
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::Jsonb;
+/// # use serde_json::Value;
+/// fn insert_doc(conn: &Connection, doc: &Value) -> Result<usize> {
+///     conn.execute("INSERT INTO docs (body) VALUES (?1)", [Jsonb(doc)])
+/// }
+///
+/// fn doc(conn: &Connection, id: i64) -> Result<Value> {
+///     conn.query_row("SELECT body FROM docs WHERE id = ?1", [id], |row| {
+///         let Jsonb(doc) = row.get(0)?;
+///         Ok(doc)
+///     })
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Jsonb<T>(pub T);
+
+impl<T: Serialize> ToSql for Jsonb<T> {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        let value = serde_json::to_value(&self.0)
+            .map_err(|err| Error::ToSqlConversionFailure(err.into()))?;
+        let mut blob = Vec::new();
+        encode(&value, &mut blob);
+        Ok(ToSqlOutput::from(blob))
+    }
+}
+
+impl<T: DeserializeOwned> FromSql for Jsonb<T> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let value = match value {
+            ValueRef::Blob(b) => decode(b)?,
+            _ => Value::column_result(value)?,
+        };
+        serde_json::from_value(value)
+            .map(Jsonb)
+            .map_err(FromSqlError::other)
+    }
+}
+
+// element types
+const NULL: u8 = 0;
+const TRUE: u8 = 1;
+const FALSE: u8 = 2;
+const INT: u8 = 3;
+const INT5: u8 = 4;
+const FLOAT: u8 = 5;
+const FLOAT5: u8 = 6;
+const TEXT: u8 = 7;
+const TEXTJ: u8 = 8;
+const TEXT5: u8 = 9;
+const TEXTRAW: u8 = 10;
+const ARRAY: u8 = 11;
+const OBJECT: u8 = 12;
+
+/// Same as `serde_json` recursion limit when parsing JSON text
+const MAX_DEPTH: usize = 128;
+
+fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => out.push(NULL),
+        Value::Bool(true) => out.push(TRUE),
+        Value::Bool(false) => out.push(FALSE),
+        Value::Number(n) => {
+            let kind = if n.is_f64() { FLOAT } else { INT };
+            push_element(kind, n.to_string().as_bytes(), out);
+        }
+        Value::String(s) => encode_str(s, out),
+        Value::Array(a) => {
+            let start = out.len();
+            for v in a {
+                encode(v, out);
+            }
+            insert_header(ARRAY, start, out);
+        }
+        Value::Object(o) => {
+            let start = out.len();
+            for (k, v) in o {
+                encode_str(k, out);
+                encode(v, out);
+            }
+            insert_header(OBJECT, start, out);
+        }
+    }
+}
+
+fn encode_str(s: &str, out: &mut Vec<u8>) {
+    // TEXT must not contain anything that would need escaping in JSON text
+    let kind = if s.bytes().any(|b| b < 0x20 || b == b'"' || b == b'\\') {
+        TEXTRAW
+    } else {
+        TEXT
+    };
+    push_element(kind, s.as_bytes(), out);
+}
+
+fn push_element(kind: u8, payload: &[u8], out: &mut Vec<u8>) {
+    let (header, len) = header(kind, payload.len());
+    out.extend_from_slice(&header[..len]);
+    out.extend_from_slice(payload);
+}
+
+/// Insert the header of the container whose payload starts at `start`
+fn insert_header(kind: u8, start: usize, out: &mut Vec<u8>) {
+    let (header, len) = header(kind, out.len() - start);
+    out.splice(start..start, header[..len].iter().copied());
+}
+
+/// Header with the smallest payload size encoding
+fn header(kind: u8, size: usize) -> ([u8; 9], usize) {
+    let mut header = [0; 9];
+    let len = if size <= 11 {
+        header[0] = kind | (size as u8) << 4;
+        return (header, 1);
+    } else if size <= 0xff {
+        header[0] = kind | 0xc0;
+        1
+    } else if size <= 0xffff {
+        header[0] = kind | 0xd0;
+        2
+    } else if size <= 0xffff_ffff {
+        header[0] = kind | 0xe0;
+        4
+    } else {
+        header[0] = kind | 0xf0;
+        8
+    };
+    header[1..=len].copy_from_slice(&(size as u64).to_be_bytes()[8 - len..]);
+    (header, len + 1)
+}
+
+fn decode(blob: &[u8]) -> FromSqlResult<Value> {
+    let (value, rest) = decode_element(blob, 0)?;
+    if !rest.is_empty() {
+        return Err(malformed());
+    }
+    Ok(value)
+}
+
+/// Decode the first element of `data` and return the remaining bytes
+fn decode_element(data: &[u8], depth: usize) -> FromSqlResult<(Value, &[u8])> {
+    let (kind, payload, rest) = split_element(data)?;
+    let value = match kind {
+        NULL => Value::Null,
+        TRUE => Value::Bool(true),
+        FALSE => Value::Bool(false),
+        INT | FLOAT => serde_json::from_slice(payload)
+            .map(Value::Number)
+            .map_err(|_| malformed())?,
+        INT5 => int5(text(payload)?)?,
+        FLOAT5 => float5(text(payload)?)?,
+        TEXT | TEXTRAW => Value::String(text(payload)?.to_owned()),
+        TEXTJ | TEXT5 => Value::String(unescape(text(payload)?)?),
+        ARRAY => {
+            check_depth(depth)?;
+            let mut array = Vec::new();
+            let mut data = payload;
+            while !data.is_empty() {
+                let (value, rest) = decode_element(data, depth + 1)?;
+                array.push(value);
+                data = rest;
+            }
+            Value::Array(array)
+        }
+        OBJECT => {
+            check_depth(depth)?;
+            let mut object = Map::new();
+            let mut data = payload;
+            while !data.is_empty() {
+                let (Value::String(key), rest) = decode_element(data, depth + 1)? else {
+                    return Err(malformed());
+                };
+                if rest.is_empty() {
+                    return Err(malformed());
+                }
+                let (value, rest) = decode_element(rest, depth + 1)?;
+                object.insert(key, value);
+                data = rest;
+            }
+            Value::Object(object)
+        }
+        _ => return Err(malformed()),
+    };
+    Ok((value, rest))
+}
+
+/// Split `data` into element type, payload and remaining bytes
+fn split_element(data: &[u8]) -> FromSqlResult<(u8, &[u8], &[u8])> {
+    let (&first, data) = data.split_first().ok_or_else(malformed)?;
+    let n = match first >> 4 {
+        12 => 1,
+        13 => 2,
+        14 => 4,
+        15 => 8,
+        _ => 0,
+    };
+    if data.len() < n {
+        return Err(malformed());
+    }
+    let (size, data) = data.split_at(n);
+    let size = if n == 0 {
+        u64::from(first >> 4)
+    } else {
+        size.iter().fold(0, |acc, &b| acc << 8 | u64::from(b))
+    };
+    match usize::try_from(size) {
+        Ok(size) if size <= data.len() => {
+            let (payload, rest) = data.split_at(size);
+            Ok((first & 0x0f, payload, rest))
+        }
+        _ => Err(malformed()),
+    }
+}
+
+fn check_depth(depth: usize) -> FromSqlResult<()> {
+    if depth >= MAX_DEPTH {
+        return Err(FromSqlError::Other("JSONB nested too deep".into()));
+    }
+    Ok(())
+}
+
+fn text(payload: &[u8]) -> FromSqlResult<&str> {
+    std::str::from_utf8(payload).map_err(FromSqlError::other)
+}
+
+/// JSON5 integer: optional sign, decimal or hexadecimal digits
+fn int5(s: &str) -> FromSqlResult<Value> {
+    let (negative, digits) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let n = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    }
+    .map_err(|_| malformed())?;
+    if negative {
+        i64::try_from(-i128::from(n))
+            .map(Value::from)
+            .map_err(FromSqlError::other)
+    } else {
+        Ok(Value::from(n))
+    }
+}
+
+/// JSON5 float: leading or trailing decimal point, leading `+`, `Infinity`
+/// or `NaN`
+fn float5(s: &str) -> FromSqlResult<Value> {
+    let f: f64 = s.parse().map_err(|_| malformed())?;
+    Number::from_f64(f)
+        .map(Value::Number)
+        .ok_or_else(|| FromSqlError::Other(format!("non-finite number {s}").into()))
+}
+
+/// Resolve JSON and JSON5 escapes
+fn unescape(s: &str) -> FromSqlResult<String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let c = match chars.next().ok_or_else(malformed)? {
+            c @ ('"' | '\\' | '/' | '\'') => c,
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\u{b}',
+            '0' => '\0',
+            'x' => char::from_u32(hex(&mut chars, 2)?).ok_or_else(malformed)?,
+            'u' => {
+                let mut c = hex(&mut chars, 4)?;
+                if (0xd800..0xdc00).contains(&c) {
+                    // surrogate pair
+                    if chars.next() != Some('\\') || chars.next() != Some('u') {
+                        return Err(malformed());
+                    }
+                    let low = hex(&mut chars, 4)?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(malformed());
+                    }
+                    c = 0x10000 + ((c - 0xd800) << 10) + (low - 0xdc00);
+                }
+                char::from_u32(c).ok_or_else(malformed)?
+            }
+            // line continuations
+            '\r' => {
+                if chars.as_str().starts_with('\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => continue,
+            _ => return Err(malformed()),
+        };
+        unescaped.push(c);
+    }
+    Ok(unescaped)
+}
+
+fn hex(chars: &mut Chars<'_>, n: usize) -> FromSqlResult<u32> {
+    (0..n).try_fold(0, |acc, _| {
+        chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .map(|d| acc << 4 | d)
+            .ok_or_else(malformed)
+    })
+}
+
+fn malformed() -> FromSqlError {
+    FromSqlError::Other("malformed JSONB".into())
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Value, json};
+
+    use super::{Jsonb, decode, encode};
+    use crate::types::Type;
+    use crate::{Connection, Error, Result};
+
+    fn checked_memory_handle() -> Result<Option<Connection>> {
+        if crate::version_number() < 3_045_000 {
+            return Ok(None);
+        }
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (j)")?;
+        Ok(Some(db))
+    }
+
+    #[test]
+    fn test_to_sql() -> Result<()> {
+        let Some(db) = checked_memory_handle()? else {
+            return Ok(());
+        };
+        for value in [
+            json!(null),
+            json!(true),
+            json!([]),
+            json!({}),
+            json!([1, -2, 18446744073709551615_u64, 0.5, 1e300]),
+            json!({"a": "text", "b": "tab\tquote\"", "c": "é", "d": [false, null]}),
+            json!("x".repeat(300)),
+            json!(vec!["y"; 70_000]),
+        ] {
+            let blob: Vec<u8> = db.one_column("SELECT ?1", [Jsonb(&value)])?;
+            // valid JSONB according to SQLite
+            let valid: bool = db.one_column("SELECT json_valid(?1, 8)", [&blob])?;
+            assert!(valid, "{value}");
+            let text: String = db.one_column("SELECT json(?1)", [&blob])?;
+            assert_eq!(value, serde_json::from_str::<Value>(&text).unwrap());
+            assert_eq!(value, decode(&blob).unwrap());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_sql() -> Result<()> {
+        let Some(db) = checked_memory_handle()? else {
+            return Ok(());
+        };
+        // JSONB produced by SQLite
+        let Jsonb(v): Jsonb<Value> = db.one_column(
+            r#"SELECT jsonb('{"a": [1, 2.5, "x\nyé😀", null, true, false], "b": {}}')"#,
+            [],
+        )?;
+        assert_eq!(
+            json!({"a": [1, 2.5, "x\nyé😀", null, true, false], "b": {}}),
+            v
+        );
+        // JSON5
+        let Jsonb(v): Jsonb<Value> = db.one_column(
+            r"SELECT jsonb('{a: 0x1F, b: .5, c: ''it\''s\x41'', d: +1, e: -0x10}')",
+            [],
+        )?;
+        assert_eq!(
+            json!({"a": 31, "b": 0.5, "c": "it'sA", "d": 1, "e": -16}),
+            v
+        );
+        // JSON text
+        let Jsonb(v): Jsonb<Value> = db.one_column(r#"SELECT '{"x": 1}'"#, [])?;
+        assert_eq!(json!({"x": 1}), v);
+        let Jsonb(v): Jsonb<Option<i64>> = db.one_column("SELECT NULL", [])?;
+        assert_eq!(None, v);
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed() -> Result<()> {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Point {
+            x: i64,
+            y: i64,
+        }
+
+        let Some(db) = checked_memory_handle()? else {
+            return Ok(());
+        };
+        db.execute(
+            "INSERT INTO foo (j) VALUES (?1)",
+            [Jsonb(Point { x: 1, y: 2 })],
+        )?;
+        db.execute("INSERT INTO foo (j) VALUES ('{\"x\": 3, \"y\": 4}')", [])?;
+        let x: i64 = db.one_column("SELECT j ->> '$.x' FROM foo WHERE rowid = 1", [])?;
+        assert_eq!(1, x);
+        let mut stmt = db.prepare("SELECT j FROM foo ORDER BY rowid")?;
+        let points = stmt
+            .query_map([], |r| r.get::<_, Jsonb<Point>>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            vec![Jsonb(Point { x: 1, y: 2 }), Jsonb(Point { x: 3, y: 4 })],
+            points
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        for blob in ["x'0b05'", "x'0000'", "x'c7'", "x'1c00'", "x'1c07'", "x'0d'"] {
+            let err = db
+                .one_column::<Jsonb<Value>, _>(&format!("SELECT {blob}"), [])
+                .unwrap_err();
+            assert!(
+                matches!(err, Error::FromSqlConversionFailure(0, Type::Blob, _)),
+                "{blob}: {err}"
+            );
+        }
+        let mut deep = Value::Null;
+        for _ in 0..129 {
+            deep = Value::Array(vec![deep]);
+        }
+        let mut blob = Vec::new();
+        encode(&deep, &mut blob);
+        decode(&blob).unwrap_err();
+        Ok(())
+    }
+}
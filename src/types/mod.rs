@@ -70,23 +70,55 @@ impl ToSql for DateTimeSql {
 //! implements [`ToSql`] or [`FromSql`] for the cases where you want to know if
 //! a value was NULL (which gets translated to `None`).
 
-pub use self::from_sql::{FromSql, FromSqlError, FromSqlResult};
+#[cfg(feature = "bincode")]
+pub use self::bincode::Bincode;
+#[cfg(feature = "cbor")]
+pub use self::cbor::Cbor;
+pub use self::coerce::Coerce;
+pub use self::duration::IsoDuration;
+pub use self::from_sql::{FromSql, FromSqlError, FromSqlRef, FromSqlResult};
+#[cfg(feature = "serde_json")]
+pub use self::jsonb::Jsonb;
+pub use self::net::Octets;
+#[cfg(feature = "serde_json")]
+pub use self::serde_json::Json;
 pub use self::to_sql::{ToSql, ToSqlOutput};
+#[cfg(any(feature = "chrono", feature = "jiff", feature = "time"))]
+pub use self::unix_time::{JulianDay, UnixMillis, UnixSeconds, UnixTime};
 pub use self::value::Value;
 pub use self::value_ref::ValueRef;
+#[cfg(feature = "rusqlite-macros")]
+pub use rusqlite_macros::{FromSql, ToSql};
 
 use std::fmt;
 
+#[cfg(feature = "bigdecimal")]
+mod bigdecimal;
+#[cfg(feature = "bincode")]
+mod bincode;
+#[cfg(feature = "cbor")]
+mod cbor;
 #[cfg(feature = "chrono")]
 mod chrono;
+#[cfg(feature = "chrono-tz")]
+mod chrono_tz;
+mod coerce;
+mod duration;
 mod from_sql;
 #[cfg(feature = "jiff")]
 mod jiff;
 #[cfg(feature = "serde_json")]
+mod jsonb;
+mod net;
+#[cfg(feature = "rust_decimal")]
+mod rust_decimal;
+#[cfg(feature = "serde_json")]
 mod serde_json;
 #[cfg(feature = "time")]
 mod time;
 mod to_sql;
+#[cfg(any(feature = "chrono", feature = "jiff", feature = "time"))]
+mod unix_time;
 #[cfg(feature = "url")]
 mod url;
 mod value;
@@ -0,0 +1,209 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`Octets`].
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::Result;
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+/// Wrapper to persist IP and socket addresses as a compact BLOB, in network
+/// byte order:
+///
+/// | Rust type    | BLOB                                         |
+/// |--------------|----------------------------------------------|
+/// | `Ipv4Addr`   | 4 bytes                                      |
+/// | `Ipv6Addr`   | 16 bytes                                     |
+/// | `IpAddr`     | 4 or 16 bytes                                |
+/// | `SocketAddr` | 4 or 16 bytes of IP address, 2 bytes of port |
+///
+/// IPv6 flow information and scope ID are not persisted.
+///
+/// BLOBs of the same length compare like the addresses, so a CIDR range can
+/// be queried with its first and last addresses:
+///
+/// ```rust,no_run
+/// # use std::net::Ipv4Addr;
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::Octets;
+/// fn count_private(conn: &Connection) -> Result<i64> {
+///     // 10.0.0.0/8
+///     let (first, last) = (Ipv4Addr::new(10, 0, 0, 0), Ipv4Addr::new(10, 255, 255, 255));
+///     conn.query_row(
+///         "SELECT count(*) FROM hosts WHERE ip BETWEEN ?1 AND ?2",
+///         [Octets(first), Octets(last)],
+///         |row| row.get(0),
+///     )
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Octets<T>(pub T);
+
+impl ToSql for Octets<Ipv4Addr> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.octets().to_vec()))
+    }
+}
+
+impl ToSql for Octets<Ipv6Addr> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.octets().to_vec()))
+    }
+}
+
+impl ToSql for Octets<IpAddr> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(ip_octets(self.0)))
+    }
+}
+
+impl ToSql for Octets<SocketAddr> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        let mut blob = ip_octets(self.0.ip());
+        blob.extend_from_slice(&self.0.port().to_be_bytes());
+        Ok(ToSqlOutput::from(blob))
+    }
+}
+
+impl FromSql for Octets<Ipv4Addr> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        <[u8; 4]>::column_result(value).map(|octets| Self(octets.into()))
+    }
+}
+
+impl FromSql for Octets<Ipv6Addr> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        <[u8; 16]>::column_result(value).map(|octets| Self(octets.into()))
+    }
+}
+
+impl FromSql for Octets<IpAddr> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let blob = value.as_blob()?;
+        ip_from_octets(blob)
+            .map(Self)
+            .ok_or(FromSqlError::InvalidBlobSize {
+                expected_size: 16,
+                blob_size: blob.len(),
+            })
+    }
+}
+
+impl FromSql for Octets<SocketAddr> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let blob = value.as_blob()?;
+        blob.split_last_chunk()
+            .and_then(|(ip, port)| {
+                let ip = ip_from_octets(ip)?;
+                Some(Self(SocketAddr::new(ip, u16::from_be_bytes(*port))))
+            })
+            .ok_or(FromSqlError::InvalidBlobSize {
+                expected_size: 18,
+                blob_size: blob.len(),
+            })
+    }
+}
+
+fn ip_octets(ip: IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    }
+}
+
+fn ip_from_octets(octets: &[u8]) -> Option<IpAddr> {
+    match octets.len() {
+        4 => <[u8; 4]>::try_from(octets).ok().map(IpAddr::from),
+        16 => <[u8; 16]>::try_from(octets).ok().map(IpAddr::from),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    use crate::types::{FromSqlError, Octets, Type};
+    use crate::{Connection, Error, Result};
+
+    #[test]
+    fn test_octets() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let v4 = Ipv4Addr::new(192, 168, 0, 1);
+        let v6 = Ipv6Addr::LOCALHOST;
+
+        let b: Vec<u8> = db.one_column("SELECT ?1", [Octets(v4)])?;
+        assert_eq!([192, 168, 0, 1], &b[..]);
+        let Octets(v): Octets<Ipv4Addr> = db.one_column("SELECT ?1", [Octets(v4)])?;
+        assert_eq!(v4, v);
+        let Octets(v): Octets<Ipv6Addr> = db.one_column("SELECT ?1", [Octets(v6)])?;
+        assert_eq!(v6, v);
+        for ip in [IpAddr::V4(v4), IpAddr::V6(v6)] {
+            let Octets(v): Octets<IpAddr> = db.one_column("SELECT ?1", [Octets(ip)])?;
+            assert_eq!(ip, v);
+            let addr = SocketAddr::new(ip, 8080);
+            let Octets(v): Octets<SocketAddr> = db.one_column("SELECT ?1", [Octets(addr)])?;
+            assert_eq!(addr, v);
+        }
+        let b: Vec<u8> =
+            db.one_column("SELECT ?1", [Octets(SocketAddr::new(IpAddr::V4(v4), 8080))])?;
+        assert_eq!([192, 168, 0, 1, 0x1f, 0x90], &b[..]);
+
+        let in_range: bool = db.one_column(
+            "SELECT ?1 BETWEEN ?2 AND ?3",
+            [
+                Octets(v4),
+                Octets(Ipv4Addr::new(192, 168, 0, 0)),
+                Octets(Ipv4Addr::new(192, 168, 255, 255)),
+            ],
+        )?;
+        assert!(in_range);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let err = db
+            .one_column::<Octets<IpAddr>, _>("SELECT '127.0.0.1'", [])
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidColumnType(0, _, Type::Text)));
+        for (sql, expected_size, blob_size) in [
+            ("SELECT x'7f0001'", 16, 3),
+            ("SELECT x'7f'", 18, 1),
+            ("SELECT x''", 18, 0),
+            ("SELECT x'7f0000011f90ff'", 18, 7),
+        ] {
+            let err = if expected_size == 16 {
+                db.one_column::<Octets<IpAddr>, _>(sql, []).map(|_| ())
+            } else {
+                db.one_column::<Octets<SocketAddr>, _>(sql, []).map(|_| ())
+            }
+            .unwrap_err();
+            match err {
+                Error::FromSqlConversionFailure(0, Type::Blob, err) => {
+                    let err = err.downcast::<FromSqlError>().unwrap();
+                    assert_eq!(
+                        FromSqlError::InvalidBlobSize {
+                            expected_size,
+                            blob_size
+                        },
+                        *err,
+                        "{sql}"
+                    );
+                }
+                err => panic!("unexpected error {err}"),
+            }
+        }
+        Ok(())
+    }
+}
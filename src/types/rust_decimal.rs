@@ -0,0 +1,156 @@
+//! [`ToSql`] and [`FromSql`] implementation for [`Decimal`].
+use rust_decimal::Decimal;
+
+use crate::Result;
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Value, ValueRef};
+
+/// Serialize `Decimal` to canonical text: plain notation, without trailing
+/// zeros in the fractional part.
+impl ToSql for Decimal {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.normalize().to_string()))
+    }
+}
+
+/// Deserialize INTEGER, REAL (through its shortest decimal representation)
+/// and TEXT, in plain or scientific notation, to `Decimal`.
+///
+/// Fails with [`FromSqlError::PrecisionLoss`] if the value has more
+/// significant digits than a `Decimal` can hold, and with
+/// [`FromSqlError::Overflow`] if its integer part does not fit.
+impl FromSql for Decimal {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(i) => Ok(Self::from(i)),
+            ValueRef::Real(f) => parse(&f.to_string(), || Value::Real(f)),
+            ValueRef::Text(_) => {
+                let s = value.as_str()?;
+                parse(s, || Value::Text(s.to_owned()))
+            }
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+fn parse(s: &str, value: impl FnOnce() -> Value) -> FromSqlResult<Decimal> {
+    let err = match Decimal::from_str_exact(s) {
+        Ok(d) => return Ok(d),
+        // too many digits, which `from_scientific` would silently truncate
+        Err(rust_decimal::Error::Underflow) => return Err(FromSqlError::PrecisionLoss(value())),
+        Err(err) => err,
+    };
+    match Decimal::from_scientific(s) {
+        Ok(d) => Ok(d),
+        // 2^96, the smallest magnitude without a `Decimal` representation
+        _ if s
+            .parse::<f64>()
+            .is_ok_and(|f| f.is_finite() && f.abs() >= 2f64.powi(96)) =>
+        {
+            Err(FromSqlError::Overflow(value()))
+        }
+        Err(rust_decimal::Error::ScaleExceedsMaximumPrecision(_)) => {
+            Err(FromSqlError::PrecisionLoss(value()))
+        }
+        Err(_) => Err(FromSqlError::other(err)),
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use rust_decimal::Decimal;
+
+    use crate::types::{FromSqlError, Type, Value};
+    use crate::{Connection, Error, Result};
+
+    fn checked_memory_handle() -> Result<Connection> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch("CREATE TABLE foo (d)")?;
+        Ok(db)
+    }
+
+    #[test]
+    fn test_to_sql() -> Result<()> {
+        let db = checked_memory_handle()?;
+        for (d, s) in [
+            ("1.50", "1.5"),
+            ("-0.000", "0"),
+            ("100", "100"),
+            (
+                "79228162514264337593543950335",
+                "79228162514264337593543950335",
+            ),
+            (
+                "0.0000000000000000000000000001",
+                "0.0000000000000000000000000001",
+            ),
+        ] {
+            let d: Decimal = d.parse().unwrap();
+            let v: String = db.one_column("SELECT ?1", [d])?;
+            assert_eq!(s, v);
+            let v: Decimal = db.one_column("SELECT ?1", [d])?;
+            assert_eq!(d, v);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_sql() -> Result<()> {
+        let db = checked_memory_handle()?;
+        db.execute_batch(
+            "INSERT INTO foo VALUES (42), (0.1), ('19.99'), ('-1e3'), (x'00'), ('2.5E-3')",
+        )?;
+        let mut stmt = db.prepare("SELECT d FROM foo")?;
+        let values: Vec<Result<Decimal>> = stmt.query_map([], |r| r.get(0))?.collect();
+        assert_eq!(Decimal::from(42), *values[0].as_ref().unwrap());
+        assert_eq!(Decimal::new(1, 1), *values[1].as_ref().unwrap());
+        assert_eq!(Decimal::new(1999, 2), *values[2].as_ref().unwrap());
+        assert_eq!(Decimal::from(-1000), *values[3].as_ref().unwrap());
+        assert!(matches!(
+            values[4],
+            Err(Error::InvalidColumnType(_, _, Type::Blob))
+        ));
+        assert_eq!(Decimal::new(25, 4), *values[5].as_ref().unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_precision_loss() -> Result<()> {
+        let db = checked_memory_handle()?;
+        let s = "0.123456789012345678901234567890123";
+        let err = db.one_column::<Decimal, _>("SELECT ?1", [s]).unwrap_err();
+        match err {
+            Error::FromSqlConversionFailure(0, Type::Text, err) => {
+                let err = err.downcast::<FromSqlError>().unwrap();
+                assert_eq!(FromSqlError::PrecisionLoss(Value::Text(s.to_owned())), *err);
+            }
+            err => panic!("unexpected error {err}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> Result<()> {
+        let db = checked_memory_handle()?;
+        for (sql, value) in [
+            (
+                "SELECT '79228162514264337593543950336'",
+                Value::Text("79228162514264337593543950336".to_owned()),
+            ),
+            ("SELECT '-1e29'", Value::Text("-1e29".to_owned())),
+            ("SELECT 1e300", Value::Real(1e300)),
+        ] {
+            match db.one_column::<Decimal, _>(sql, []).unwrap_err() {
+                Error::FromSqlConversionFailure(0, _, err) => {
+                    let err = err.downcast::<FromSqlError>().unwrap();
+                    assert_eq!(FromSqlError::Overflow(value), *err);
+                }
+                err => panic!("unexpected error {err}"),
+            }
+        }
+        Ok(())
+    }
+}
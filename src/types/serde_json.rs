@@ -1,5 +1,7 @@
-//! [`ToSql`] and [`FromSql`] implementation for JSON `Value`.
+//! [`ToSql`] and [`FromSql`] implementation for JSON `Value` and [`Json`].
 
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use serde_json::{Number, Value};
 
 use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
@@ -60,13 +62,57 @@ impl FromSql for Value {
     }
 }
 
+/// Wrapper to persist any serializable value as JSON text.
+///
+/// ```rust,no_run
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::Json;
+/// fn insert_tags(conn: &Connection, tags: &[&str]) -> Result<usize> {
+///     conn.execute("INSERT INTO posts (tags) VALUES (?1)", [Json(tags)])
+/// }
+///
+/// fn tags(conn: &Connection, id: i64) -> Result<Vec<String>> {
+///     conn.query_row("SELECT tags FROM posts WHERE id = ?1", [id], |row| {
+///         let Json(tags) = row.get(0)?;
+///         Ok(tags)
+///     })
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Json<T>(pub T);
+
+/// Serialize to TEXT.
+impl<T: Serialize> ToSql for Json<T> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        serde_json::to_string(&self.0)
+            .map(ToSqlOutput::from)
+            .map_err(|err| Error::ToSqlConversionFailure(err.into()))
+    }
+}
+
+/// Deserialize from TEXT or BLOB, or from INTEGER, REAL and NULL like JSON
+/// `Value`.
+impl<T: DeserializeOwned> FromSql for Json<T> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Text(s) | ValueRef::Blob(s) => serde_json::from_slice(s),
+            _ => serde_json::from_value(Value::column_result(value)?),
+        }
+        .map(Json)
+        .map_err(FromSqlError::other)
+    }
+}
+
 #[cfg(all(test, not(miri)))]
 mod test {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
-    use crate::types::ToSql;
-    use crate::{Connection, Result};
+    use crate::types::{Json, ToSql, Type};
+    use crate::{Connection, Error, Result};
+    use serde::{Deserialize, Serialize};
     use serde_json::{Number, Value};
 
     fn checked_memory_handle() -> Result<Connection> {
@@ -130,4 +176,34 @@ mod test {
         assert!(v.is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_json_wrapper() -> Result<()> {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Point {
+            x: i64,
+            y: i64,
+        }
+
+        let db = checked_memory_handle()?;
+        db.execute(
+            "INSERT INTO foo (t) VALUES (?1)",
+            [Json(Point { x: 1, y: 2 })],
+        )?;
+        let t: String = db.one_column("SELECT t FROM foo", [])?;
+        assert_eq!(r#"{"x":1,"y":2}"#, t);
+        let Json(p): Json<Point> = db.one_column("SELECT t FROM foo", [])?;
+        assert_eq!(Point { x: 1, y: 2 }, p);
+        let Json(v): Json<Option<u8>> = db.one_column("SELECT 42", [])?;
+        assert_eq!(Some(42), v);
+
+        let err = db
+            .one_column::<Json<Point>, _>(r#"SELECT '{"x":1}'"#, [])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FromSqlConversionFailure(0, Type::Text, _)
+        ));
+        Ok(())
+    }
 }
@@ -12,7 +12,9 @@
 //! Time String that contain an optional timezone without an explicit date are unsupported.
 //! All other assumptions described in [Time Values](https://sqlite.org/lang_datefunc.html#time_values) section are unsupported.
 
-use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, ValueRef};
+use crate::types::{
+    FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, UnixTime, ValueRef,
+};
 use crate::{Error, Result};
 use time::format_description::FormatItem;
 use time::macros::format_description;
@@ -177,6 +179,56 @@ impl FromSql for UtcDateTime {
     }
 }
 
+impl UnixTime for OffsetDateTime {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        Some(self.unix_timestamp_nanos())
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        Self::from_unix_timestamp_nanos(nanos).ok()
+    }
+}
+
+impl UnixTime for UtcDateTime {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        Some(self.unix_timestamp_nanos())
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        Self::from_unix_timestamp_nanos(nanos).ok()
+    }
+}
+
+/// Interpreted as UTC.
+impl UnixTime for PrimitiveDateTime {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        Some(self.as_utc().unix_timestamp_nanos())
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        UtcDateTime::from_unix_nanos(nanos).map(|dt| Self::new(dt.date(), dt.time()))
+    }
+}
+
+/// Midnight UTC, the time of day is truncated when read.
+impl UnixTime for Date {
+    #[inline]
+    fn unix_nanos(&self) -> Option<i128> {
+        self.midnight().unix_nanos()
+    }
+
+    #[inline]
+    fn from_unix_nanos(nanos: i128) -> Option<Self> {
+        UtcDateTime::from_unix_nanos(nanos).map(|dt| dt.date())
+    }
+}
+
 #[cfg(all(test, not(miri)))]
 mod test {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
@@ -1,8 +1,10 @@
 use super::{Null, Value, ValueRef};
-#[cfg(feature = "fallible_uint")]
-use crate::Error;
-use crate::Result;
+use crate::{Error, Result};
 use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// `ToSqlOutput` represents the possible output types for implementers of the
 /// [`ToSql`] trait.
@@ -155,6 +157,9 @@ impl ToSql for ToSqlOutput<'_> {
 
 /// A trait for types that can be converted into SQLite values. Returns
 /// [`crate::Error::ToSqlConversionFailure`] if the conversion fails.
+///
+/// With the `rusqlite-macros` feature, it can be derived for fieldless enums
+/// and newtypes, see [`FromSql`](crate::types::FromSql).
 pub trait ToSql {
     /// Converts Rust value to SQLite value
     fn to_sql(&self) -> Result<ToSqlOutput<'_>>;
@@ -334,6 +339,75 @@ impl ToSql for Value {
     }
 }
 
+macro_rules! to_sql_display(
+    ($t:ty) => {
+        impl ToSql for $t {
+            #[inline]
+            fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+                Ok(ToSqlOutput::from(self.to_string()))
+            }
+        }
+    }
+);
+
+// IP and socket addresses => TEXT ("192.168.0.1", "[::1]:8080"), see
+// `Octets` for a compact BLOB.
+to_sql_display!(IpAddr);
+to_sql_display!(Ipv4Addr);
+to_sql_display!(Ipv6Addr);
+to_sql_display!(SocketAddr);
+
+/// Duration => INTEGER number of nanoseconds. Fails if the duration exceeds
+/// `i64::MAX` nanoseconds (about 292 years). See
+/// [`IsoDuration`](crate::types::IsoDuration) for ISO 8601 TEXT.
+impl ToSql for Duration {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        i64::try_from(self.as_nanos())
+            .map(ToSqlOutput::from)
+            .map_err(|err| Error::ToSqlConversionFailure(err.into()))
+    }
+}
+
+/// On Unix, `OsStr` => BLOB of its bytes, without loss. Elsewhere, `OsStr` =>
+/// TEXT, failing if it is not valid Unicode.
+impl ToSql for OsStr {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt as _;
+            Ok(ToSqlOutput::from(self.as_bytes()))
+        }
+        #[cfg(not(unix))]
+        self.to_str().map(ToSqlOutput::from).ok_or_else(|| {
+            Error::ToSqlConversionFailure(format!("{self:?} is not valid Unicode").into())
+        })
+    }
+}
+
+impl ToSql for OsString {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        self.as_os_str().to_sql()
+    }
+}
+
+/// Same as `OsStr`.
+impl ToSql for Path {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        self.as_os_str().to_sql()
+    }
+}
+
+impl ToSql for PathBuf {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        self.as_os_str().to_sql()
+    }
+}
+
 impl<T: ToSql> ToSql for Option<T> {
     #[inline]
     fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
@@ -0,0 +1,289 @@
+//! Numeric storage formats for date and time types: unix timestamps (in
+//! seconds or milliseconds) and Julian day numbers.
+//!
+//! | Wrapper          | SQLite  | SQLite function               |
+//! |------------------|---------|-------------------------------|
+//! | [`UnixSeconds`]  | INTEGER | `unixepoch(t)`                |
+//! | [`UnixMillis`]   | INTEGER | `unixepoch(t, 'subsec')*1000` |
+//! | [`JulianDay`]    | REAL    | `julianday(t)`                |
+//!
+//! Values are truncated to the precision of the storage format when written.
+//! INTEGER and REAL values are both accepted when read, REAL values with
+//! millisecond precision like SQLite date and time functions.
+
+use std::fmt;
+
+use crate::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use crate::{Error, Result};
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_MILLI: i128 = 1_000_000;
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+/// Julian day number of the unix epoch, in milliseconds
+const UNIX_EPOCH_JULIAN_MILLIS: i128 = 210_866_760_000_000;
+
+/// Conversion of date and time types to and from nanoseconds since the unix
+/// epoch (1970-01-01T00:00:00Z), used by [`UnixSeconds`], [`UnixMillis`] and
+/// [`JulianDay`].
+///
+/// Date and time types without time zone are interpreted as UTC, and types
+/// with a time zone are built in UTC (or local time for `chrono::Local`).
+/// Date types are interpreted as midnight UTC, and the time of day is
+/// truncated when read.
+pub trait UnixTime: Sized {
+    /// Nanoseconds since the unix epoch, `None` if out of range.
+    fn unix_nanos(&self) -> Option<i128>;
+    /// Date and time `nanos` nanoseconds after the unix epoch, `None` if out
+    /// of range.
+    fn from_unix_nanos(nanos: i128) -> Option<Self>;
+}
+
+/// Wrapper to persist a date and time as an INTEGER number of seconds since
+/// the unix epoch.
+///
+/// ```rust,no_run
+/// # use rusqlite::{Connection, Result};
+/// # use rusqlite::types::{UnixSeconds, UnixTime};
+/// fn insert_event<T: UnixTime>(conn: &Connection, at: T) -> Result<usize> {
+///     conn.execute("INSERT INTO events (at) VALUES (?1)", [UnixSeconds(at)])
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnixSeconds<T>(pub T);
+
+/// Wrapper to persist a date and time as an INTEGER number of milliseconds
+/// since the unix epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnixMillis<T>(pub T);
+
+/// Wrapper to persist a date and time as a REAL Julian day number, with
+/// millisecond precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JulianDay<T>(pub T);
+
+impl<T: UnixTime> ToSql for UnixSeconds<T> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        to_unit(&self.0, NANOS_PER_SECOND).map(ToSqlOutput::from)
+    }
+}
+
+impl<T: UnixTime> FromSql for UnixSeconds<T> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_unit(value, NANOS_PER_SECOND).map(Self)
+    }
+}
+
+impl<T: UnixTime> ToSql for UnixMillis<T> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        to_unit(&self.0, NANOS_PER_MILLI).map(ToSqlOutput::from)
+    }
+}
+
+impl<T: UnixTime> FromSql for UnixMillis<T> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        from_unit(value, NANOS_PER_MILLI).map(Self)
+    }
+}
+
+impl<T: UnixTime> ToSql for JulianDay<T> {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        let millis = self
+            .0
+            .unix_nanos()
+            .ok_or_else(out_of_range)?
+            .div_euclid(NANOS_PER_MILLI);
+        let jd = (millis + UNIX_EPOCH_JULIAN_MILLIS) as f64 / MILLIS_PER_DAY;
+        Ok(ToSqlOutput::from(jd))
+    }
+}
+
+impl<T: UnixTime> FromSql for JulianDay<T> {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let jd = match value {
+            ValueRef::Integer(i) => i as f64,
+            ValueRef::Real(f) => f,
+            _ => return Err(FromSqlError::InvalidType),
+        };
+        from_real_millis(jd * MILLIS_PER_DAY, -UNIX_EPOCH_JULIAN_MILLIS, jd).map(Self)
+    }
+}
+
+fn to_unit(t: &impl UnixTime, unit: i128) -> Result<i64> {
+    t.unix_nanos()
+        .and_then(|nanos| i64::try_from(nanos.div_euclid(unit)).ok())
+        .ok_or_else(out_of_range)
+}
+
+fn from_unit<T: UnixTime>(value: ValueRef<'_>, unit: i128) -> FromSqlResult<T> {
+    match value {
+        ValueRef::Integer(i) => {
+            T::from_unix_nanos(i128::from(i) * unit).ok_or(FromSqlError::OutOfRange(i))
+        }
+        ValueRef::Real(f) => from_real_millis(f * (unit / NANOS_PER_MILLI) as f64, 0, f),
+        _ => Err(FromSqlError::InvalidType),
+    }
+}
+
+/// `millis` since the unix epoch once shifted by `offset`
+fn from_real_millis<T: UnixTime>(millis: f64, offset: i128, value: f64) -> FromSqlResult<T> {
+    if millis.is_finite() {
+        // saturating cast, out of range for all date and time types
+        let millis = millis.round() as i128;
+        if let Some(t) = T::from_unix_nanos((millis + offset).saturating_mul(NANOS_PER_MILLI)) {
+            return Ok(t);
+        }
+    }
+    Err(FromSqlError::other(RealOutOfRange(value)))
+}
+
+fn out_of_range() -> Error {
+    Error::ToSqlConversionFailure("date and time out of range".into())
+}
+
+#[derive(Debug)]
+struct RealOutOfRange(f64);
+
+impl fmt::Display for RealOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "date and time {} out of range", self.0)
+    }
+}
+
+impl std::error::Error for RealOutOfRange {}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::fmt::Debug;
+
+    use super::{JulianDay, UnixMillis, UnixSeconds, UnixTime};
+    use crate::types::Type;
+    use crate::{Connection, Error, Result};
+
+    const TEXT: &str = "2016-02-23 23:56:04.789";
+    const SECONDS: i64 = 1_456_271_764;
+    const MILLIS: i64 = 1_456_271_764_789;
+    const DATE: &str = "2016-02-23";
+    const DATE_SECONDS: i64 = 1_456_185_600;
+
+    /// `t` must be `TEXT` in UTC
+    fn check<T: UnixTime + PartialEq + Debug + Copy>(t: T) -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        // seconds
+        let i: i64 = db.one_column("SELECT ?1", [UnixSeconds(t)])?;
+        assert_eq!(SECONDS, i);
+        let s: String = db.one_column("SELECT datetime(?1, 'unixepoch')", [UnixSeconds(t)])?;
+        assert_eq!(TEXT[..19], s);
+        let UnixSeconds(v): UnixSeconds<T> = db.one_column("SELECT unixepoch(?1)", [TEXT])?;
+        assert_eq!(Some(i128::from(SECONDS) * 1_000_000_000), v.unix_nanos());
+        let UnixSeconds(v): UnixSeconds<T> =
+            db.one_column("SELECT unixepoch(?1, 'subsec')", [TEXT])?;
+        assert_eq!(t, v);
+        // millis
+        let i: i64 = db.one_column("SELECT ?1", [UnixMillis(t)])?;
+        assert_eq!(MILLIS, i);
+        let UnixMillis(v): UnixMillis<T> = db.one_column(
+            "SELECT CAST(unixepoch(?1, 'subsec') * 1000 AS INTEGER)",
+            [TEXT],
+        )?;
+        assert_eq!(t, v);
+        // Julian day
+        let s: String =
+            db.one_column("SELECT strftime('%Y-%m-%d %H:%M:%f', ?1)", [JulianDay(t)])?;
+        assert_eq!(TEXT, s);
+        let JulianDay(v): JulianDay<T> = db.one_column("SELECT julianday(?1)", [TEXT])?;
+        assert_eq!(t, v);
+        let JulianDay(v): JulianDay<T> = db.one_column("SELECT ?1", [JulianDay(t)])?;
+        assert_eq!(t, v);
+        // errors
+        let err = db
+            .one_column::<UnixSeconds<T>, _>("SELECT ?1", [TEXT])
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidColumnType(0, _, Type::Text)));
+        let err = db
+            .one_column::<UnixSeconds<T>, _>("SELECT ?1", [i64::MAX])
+            .unwrap_err();
+        assert_eq!(Error::IntegralValueOutOfRange(0, i64::MAX), err);
+        let err = db
+            .one_column::<JulianDay<T>, _>("SELECT 1e300", [])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FromSqlConversionFailure(0, Type::Real, _)
+        ));
+        Ok(())
+    }
+
+    /// `d` must be `DATE`
+    fn check_date<T: UnixTime + PartialEq + Debug + Copy>(d: T) -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let i: i64 = db.one_column("SELECT ?1", [UnixSeconds(d)])?;
+        assert_eq!(DATE_SECONDS, i);
+        let s: String = db.one_column("SELECT date(?1)", [JulianDay(d)])?;
+        assert_eq!(DATE, s);
+        let JulianDay(v): JulianDay<T> = db.one_column("SELECT julianday(?1)", [DATE])?;
+        assert_eq!(d, v);
+        // time of day truncated
+        let UnixMillis(v): UnixMillis<T> = db.one_column("SELECT ?1", [MILLIS])?;
+        assert_eq!(d, v);
+        let JulianDay(v): JulianDay<T> = db.one_column("SELECT julianday(?1)", [TEXT])?;
+        assert_eq!(d, v);
+        // before the unix epoch
+        let UnixSeconds(v): UnixSeconds<T> = db.one_column("SELECT -1", [])?;
+        let s: String = db.one_column("SELECT date(?1)", [JulianDay(v)])?;
+        assert_eq!("1969-12-31", s);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn test_chrono() -> Result<()> {
+        use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+
+        let naive = NaiveDateTime::parse_from_str(TEXT, "%F %T%.f").unwrap();
+        check(naive)?;
+        check(naive.and_utc())?;
+        check::<DateTime<FixedOffset>>(naive.and_utc().fixed_offset())?;
+        let before_epoch = DateTime::<Utc>::from_unix_nanos(-1_500_000_000).unwrap();
+        assert_eq!(
+            "1969-12-31 23:59:58.500 UTC",
+            before_epoch.format("%F %T%.3f %Z").to_string()
+        );
+        check_date(NaiveDate::parse_from_str(DATE, "%F").unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn test_time() -> Result<()> {
+        use time::macros::{date, datetime};
+
+        let dt = datetime!(2016-02-23 23:56:04.789);
+        check(dt)?;
+        check(dt.assume_utc())?;
+        check(dt.as_utc())?;
+        check_date(date!(2016 - 02 - 23))?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "jiff")]
+    fn test_jiff() -> Result<()> {
+        use jiff::civil::{Date, DateTime};
+        use jiff::tz::TimeZone;
+
+        let dt: DateTime = TEXT.parse().unwrap();
+        check(dt)?;
+        check(dt.to_zoned(TimeZone::UTC).unwrap().timestamp())?;
+        check_date::<Date>(DATE.parse().unwrap())?;
+        Ok(())
+    }
+}
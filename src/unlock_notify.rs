@@ -1,10 +1,55 @@
 //! [Unlock Notification](http://sqlite.org/unlock_notify.html)
+//!
+//! In [shared-cache mode](https://sqlite.org/sharedcache.html), a statement
+//! fails with `SQLITE_LOCKED_SHAREDCACHE` when a table it uses is locked by
+//! another connection sharing the same cache. With this feature, statements
+//! wait until the lock is released instead (see [`UnlockWait`]), and fail with
+//! [`Error::Deadlock`] if waiting would deadlock.
 
 use std::ffi::{c_int, c_void};
 use std::panic::catch_unwind;
+use std::ptr;
 use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::ffi;
+use crate::{Connection, Error, Result, ffi};
+
+/// Outcome of [`wait_for_unlock_notify`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Unlock {
+    /// The lock has been released: the operation should be retried.
+    Released,
+    /// Waiting would deadlock: the current transaction should be rolled back.
+    Deadlock,
+    /// The deadline has been reached.
+    TimedOut,
+}
+
+/// What a statement does when a table is locked by another connection sharing
+/// the same cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnlockWait {
+    /// Fail with `SQLITE_LOCKED_SHAREDCACHE`.
+    Fail,
+    /// Wait until the lock is released.
+    #[default]
+    Forever,
+    /// Wait until the lock is released, or fail with
+    /// `SQLITE_LOCKED_SHAREDCACHE` after the given duration.
+    Timeout(Duration),
+}
+
+impl UnlockWait {
+    /// Deadline of a wait starting now, `None` for no deadline
+    pub(crate) fn deadline(self) -> Option<Option<Instant>> {
+        match self {
+            Self::Fail => None,
+            Self::Forever => Some(None),
+            Self::Timeout(timeout) => Some(Some(Instant::now() + timeout)),
+        }
+    }
+}
 
 struct UnlockNotification {
     cond: Condvar,      // Condition variable to wait on
@@ -25,11 +70,23 @@ impl UnlockNotification {
         self.cond.notify_one();
     }
 
-    fn wait(&self) {
+    /// Returns `false` if `deadline` is reached before the notification.
+    fn wait(&self, deadline: Option<Instant>) -> bool {
         let mut fired = unpoison(self.mutex.lock());
         while !*fired {
-            fired = unpoison(self.cond.wait(fired));
+            match deadline {
+                None => fired = unpoison(self.cond.wait(fired)),
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    let (guard, result) = unpoison(self.cond.wait_timeout(fired, timeout));
+                    if result.timed_out() {
+                        return *guard;
+                    }
+                    fired = guard;
+                }
+            }
         }
+        true
     }
 }
 
@@ -49,7 +106,7 @@ unsafe extern "C" fn unlock_notify_cb(ap_arg: *mut *mut c_void, n_arg: c_int) {
     }
 }
 
-pub unsafe fn is_locked(db: *mut ffi::sqlite3, rc: c_int) -> bool {
+pub(crate) unsafe fn is_locked(db: *mut ffi::sqlite3, rc: c_int) -> bool {
     unsafe {
         rc == ffi::SQLITE_LOCKED_SHAREDCACHE
             || (rc & 0xFF) == ffi::SQLITE_LOCKED
@@ -63,14 +120,19 @@ pub unsafe fn is_locked(db: *mut ffi::sqlite3, rc: c_int) -> bool {
 ///
 /// This function calls `sqlite3_unlock_notify()` to register for an
 /// unlock-notify callback, then blocks until that callback is delivered
-/// and returns `SQLITE_OK`. The caller should then retry the failed operation.
+/// and returns [`Unlock::Released`]. The caller should then retry the failed
+/// operation.
 ///
 /// Or, if `sqlite3_unlock_notify()` indicates that to block would deadlock
-/// the system, then this function returns `SQLITE_LOCKED` immediately. In
-/// this case the caller should not retry the operation and should roll
+/// the system, then this function returns [`Unlock::Deadlock`] immediately.
+/// In this case the caller should not retry the operation and should roll
 /// back the current transaction (if any).
-#[cfg(feature = "unlock_notify")]
-pub unsafe fn wait_for_unlock_notify(db: *mut ffi::sqlite3) -> c_int {
+///
+/// If `deadline` is reached first, [`Unlock::TimedOut`] is returned.
+pub(crate) unsafe fn wait_for_unlock_notify(
+    db: *mut ffi::sqlite3,
+    deadline: Option<Instant>,
+) -> Unlock {
     let un = UnlockNotification::new();
     let rc = unsafe {
         /* Register for an unlock-notify callback. */
@@ -83,10 +145,42 @@ pub unsafe fn wait_for_unlock_notify(db: *mut ffi::sqlite3) -> c_int {
     debug_assert!(
         rc == ffi::SQLITE_LOCKED || rc == ffi::SQLITE_LOCKED_SHAREDCACHE || rc == ffi::SQLITE_OK
     );
-    if rc == ffi::SQLITE_OK {
-        un.wait();
+    if rc != ffi::SQLITE_OK {
+        return Unlock::Deadlock;
+    }
+    if un.wait(deadline) {
+        return Unlock::Released;
+    }
+    // Cancel the callback, which cannot run concurrently (both are serialized
+    // by the SQLite main mutex), so `un` can be dropped safely.
+    unsafe { ffi::sqlite3_unlock_notify(db, None, ptr::null_mut()) };
+    if un.wait(Some(Instant::now())) {
+        Unlock::Released
+    } else {
+        Unlock::TimedOut
+    }
+}
+
+impl Connection {
+    /// Block until the transaction holding the lock which made the last
+    /// statement of this connection fail with `SQLITE_LOCKED_SHAREDCACHE` is
+    /// over, or until `timeout` is elapsed.
+    ///
+    /// Returns immediately if this connection is not blocked.
+    ///
+    /// # Failure
+    ///
+    /// Will return [`Error::Deadlock`] if waiting would deadlock, or
+    /// `SQLITE_LOCKED_SHAREDCACHE` if `timeout` is elapsed.
+    pub fn wait_for_unlock(&self, timeout: Option<Duration>) -> Result<()> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let db = self.db.borrow();
+        match unsafe { wait_for_unlock_notify(db.db(), deadline) } {
+            Unlock::Released => Ok(()),
+            Unlock::Deadlock => Err(Error::Deadlock),
+            Unlock::TimedOut => Err(err!(ffi::SQLITE_LOCKED_SHAREDCACHE)),
+        }
     }
-    rc
 }
 
 #[cfg(all(test, not(miri)))]
@@ -94,11 +188,19 @@ mod test {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
-    use crate::{Connection, OpenFlags, Result, Transaction, TransactionBehavior};
+    use super::UnlockWait;
+    use crate::{Connection, Error, OpenFlags, Result, Transaction, TransactionBehavior, ffi};
+    use std::sync::Barrier;
     use std::sync::mpsc::sync_channel;
     use std::thread;
     use std::time;
 
+    fn open_shared(name: &str) -> Result<Connection> {
+        let url = format!("file:{name}?mode=memory&cache=shared");
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI;
+        Connection::open_with_flags(url, flags)
+    }
+
     #[cfg_attr(
         all(target_family = "wasm", target_os = "unknown"),
         ignore = "no thread on this platform"
@@ -124,4 +226,97 @@ mod test {
         child.join().unwrap();
         Ok(())
     }
+
+    #[test]
+    fn test_unlock_wait_fail() -> Result<()> {
+        let db1 = open_shared("unlock_wait_fail")?;
+        db1.execute_batch("CREATE TABLE foo (x)")?;
+        let db2 = open_shared("unlock_wait_fail")?;
+        let mut stmt = db1.prepare("SELECT x FROM foo")?;
+        db2.execute_batch("BEGIN; INSERT INTO foo VALUES (42)")?;
+
+        stmt.set_unlock_wait(UnlockWait::Fail);
+        let err = stmt.query_row([], |r| r.get::<_, i64>(0)).unwrap_err();
+        assert_eq!(
+            Some(ffi::SQLITE_LOCKED_SHAREDCACHE),
+            err.sqlite_extended_error_code()
+        );
+
+        let start = time::Instant::now();
+        stmt.set_unlock_wait(UnlockWait::Timeout(time::Duration::from_millis(20)));
+        let err = stmt.query_row([], |r| r.get::<_, i64>(0)).unwrap_err();
+        assert_eq!(
+            Some(ffi::SQLITE_LOCKED_SHAREDCACHE),
+            err.sqlite_extended_error_code()
+        );
+        assert!(start.elapsed() >= time::Duration::from_millis(20));
+
+        db2.execute_batch("COMMIT")?;
+        assert_eq!(42, stmt.query_row([], |r| r.get::<_, i64>(0))?);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        all(target_family = "wasm", target_os = "unknown"),
+        ignore = "no thread on this platform"
+    )]
+    #[test]
+    fn test_unlock_wait_timeout() -> Result<()> {
+        let db1 = open_shared("unlock_wait_timeout")?;
+        db1.execute_batch("CREATE TABLE foo (x)")?;
+        let db2 = open_shared("unlock_wait_timeout")?;
+        db2.execute_batch("BEGIN; INSERT INTO foo VALUES (42)")?;
+        let child = thread::spawn(move || {
+            thread::sleep(time::Duration::from_millis(10));
+            db2.execute_batch("COMMIT").unwrap();
+        });
+        let mut stmt = db1.prepare("SELECT x FROM foo")?;
+        stmt.set_unlock_wait(UnlockWait::Timeout(time::Duration::from_secs(10)));
+        assert_eq!(42, stmt.query_row([], |r| r.get::<_, i64>(0))?);
+        child.join().unwrap();
+        db1.wait_for_unlock(None)?;
+        Ok(())
+    }
+
+    #[cfg_attr(
+        all(target_family = "wasm", target_os = "unknown"),
+        ignore = "no thread on this platform"
+    )]
+    #[test]
+    fn test_deadlock() -> Result<()> {
+        let db1 = open_shared("unlock_deadlock")?;
+        db1.execute_batch("CREATE TABLE foo (x); CREATE TABLE bar (x)")?;
+        let db2 = open_shared("unlock_deadlock")?;
+        let barrier = &Barrier::new(2);
+        // whichever connection waits last detects the deadlock and rolls back,
+        // which unblocks the other one
+        let finish = |db: &Connection, r: Result<usize>| -> Result<bool> {
+            match r {
+                Ok(_) => db.execute_batch("COMMIT").map(|()| false),
+                Err(Error::Deadlock) => db.execute_batch("ROLLBACK").map(|()| true),
+                Err(err) => Err(err),
+            }
+        };
+        let (deadlock1, deadlock2) = thread::scope(|s| {
+            let t = s.spawn(move || {
+                // read lock on foo, held until the end of the transaction
+                db1.execute_batch("BEGIN")?;
+                let n: i64 = db1.query_row("SELECT count(*) FROM foo", [], |r| r.get(0))?;
+                assert_eq!(0, n);
+                barrier.wait();
+                // blocked by db2
+                let r = db1.query_row("SELECT count(*) FROM bar", [], |r| r.get(0));
+                finish(&db1, r.map(|n: i64| n as usize))
+            });
+            db2.execute_batch("BEGIN; INSERT INTO bar VALUES (2)")?;
+            barrier.wait();
+            // blocked by db1
+            let r = db2.execute("INSERT INTO foo VALUES (1)", []);
+            let deadlock2 = finish(&db2, r)?;
+            Ok::<_, Error>((t.join().unwrap()?, deadlock2))
+        })?;
+        assert!(deadlock1 != deadlock2);
+        db2.execute("INSERT INTO foo VALUES (1)", [])?;
+        Ok(())
+    }
 }
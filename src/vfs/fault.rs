@@ -0,0 +1,517 @@
+//! Fault-injection VFS shim.
+//!
+//! [`FaultVfs`] wraps another VFS and makes selected operations fail
+//! according to scripted [`Rule`]s, so that the handling of I/O errors can be
+//! tested deterministically. Every operation attempted through the shim is
+//! recorded as an [`Event`].
+//!
+//! ```rust
+//! use rusqlite::vfs::FileKind;
+//! use rusqlite::vfs::fault::{FaultVfs, Op, Rule};
+//! use rusqlite::{Connection, OpenFlags, Result, ffi};
+//!
+//! fn wal_full(path: &str) -> Result<()> {
+//!     let faults = FaultVfs::register("faulty")?;
+//!     // fail every write to the -wal file after 10 MiB
+//!     faults.add_rule(
+//!         Rule::new(Op::Write)
+//!             .kind(FileKind::Wal)
+//!             .beyond(10 << 20)
+//!             .error(ffi::SQLITE_FULL),
+//!     );
+//!     let db = Connection::open_with_flags_and_vfs(path, OpenFlags::default(), "faulty")?;
+//!     db.pragma_update(None, "journal_mode", "wal")?;
+//!     // ...
+//!     Ok(())
+//! }
+//! ```
+use std::ffi::{c_int, c_void};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::{
+    AccessCheck, DeviceCharacteristics, FileKind, FileName, LockLevel, OpenOptions, ShmLockFlags,
+    SyncFlags, SystemVfs, Vfs, VfsFile, register_vfs,
+};
+use crate::{Result, ffi};
+
+/// Operation which can be made to fail
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Op {
+    /// [`Vfs::open`]
+    Open,
+    /// [`Vfs::delete`]
+    Delete,
+    /// [`VfsFile::read`]
+    Read,
+    /// [`VfsFile::write`]
+    Write,
+    /// [`VfsFile::truncate`]
+    Truncate,
+    /// [`VfsFile::sync`]
+    Sync,
+    /// [`VfsFile::file_size`]
+    FileSize,
+    /// [`VfsFile::lock`]
+    Lock,
+    /// [`VfsFile::shm_map`]
+    ShmMap,
+    /// [`VfsFile::shm_lock`]
+    ShmLock,
+}
+
+impl Op {
+    fn default_error(self) -> c_int {
+        match self {
+            Self::Open => ffi::SQLITE_CANTOPEN,
+            Self::Delete => ffi::SQLITE_IOERR_DELETE,
+            Self::Read => ffi::SQLITE_IOERR_READ,
+            Self::Write => ffi::SQLITE_IOERR_WRITE,
+            Self::Truncate => ffi::SQLITE_IOERR_TRUNCATE,
+            Self::Sync => ffi::SQLITE_IOERR_FSYNC,
+            Self::FileSize => ffi::SQLITE_IOERR_FSTAT,
+            Self::Lock => ffi::SQLITE_IOERR_LOCK,
+            Self::ShmMap => ffi::SQLITE_IOERR_SHMMAP,
+            Self::ShmLock => ffi::SQLITE_IOERR_SHMLOCK,
+        }
+    }
+}
+
+/// What happens when a [`Rule`] triggers
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Fault {
+    /// The operation fails with the given (extended) result code.
+    Error(c_int),
+    /// The operation is performed but only half of the requested bytes are
+    /// returned ([`Op::Read`] only).
+    ShortRead,
+}
+
+/// Scripted failure of an operation.
+///
+/// By default, a rule makes every matching operation fail with the
+/// `SQLITE_IOERR_*` code of the operation.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    op: Op,
+    kind: Option<FileKind>,
+    beyond: Option<u64>,
+    skip: u64,
+    times: Option<u64>,
+    fault: Fault,
+    // number of matching operations seen so far
+    seen: u64,
+}
+
+impl Rule {
+    /// Rule matching all `op` operations
+    #[must_use]
+    pub fn new(op: Op) -> Self {
+        Self {
+            op,
+            kind: None,
+            beyond: None,
+            skip: 0,
+            times: None,
+            fault: Fault::Error(op.default_error()),
+            seen: 0,
+        }
+    }
+
+    /// Only match operations on files of the given `kind`.
+    #[must_use]
+    pub fn kind(mut self, kind: FileKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only match reads, writes and truncations which reach past `offset`.
+    #[must_use]
+    pub fn beyond(mut self, offset: u64) -> Self {
+        self.beyond = Some(offset);
+        self
+    }
+
+    /// Fail starting from the `n`th (1-based) matching operation.
+    #[must_use]
+    pub fn nth(mut self, n: u64) -> Self {
+        self.skip = n.saturating_sub(1);
+        self
+    }
+
+    /// Stop failing after `n` failures.
+    #[must_use]
+    pub fn times(mut self, n: u64) -> Self {
+        self.times = Some(n);
+        self
+    }
+
+    /// Fail with the given (extended) result code.
+    #[must_use]
+    pub fn error(mut self, code: c_int) -> Self {
+        self.fault = Fault::Error(code);
+        self
+    }
+
+    /// Return only half of the requested bytes.
+    #[must_use]
+    pub fn short_read(mut self) -> Self {
+        self.fault = Fault::ShortRead;
+        self
+    }
+
+    fn trigger(&mut self, op: Op, kind: Option<FileKind>, end: u64) -> Option<Fault> {
+        if op != self.op
+            || self.kind.is_some_and(|k| Some(k) != kind)
+            || self.beyond.is_some_and(|o| end <= o)
+            || self.times == Some(0)
+        {
+            return None;
+        }
+        self.seen += 1;
+        if self.seen <= self.skip {
+            return None;
+        }
+        if let Some(times) = self.times.as_mut() {
+            *times -= 1;
+        }
+        Some(self.fault)
+    }
+}
+
+/// Operation attempted through a [`FaultVfs`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Event {
+    /// Operation
+    pub op: Op,
+    /// Kind of the file, if known
+    pub kind: Option<FileKind>,
+    /// File offset (or size for [`Op::Truncate`])
+    pub offset: u64,
+    /// Number of bytes read or written
+    pub len: usize,
+    /// Fault injected, if any
+    pub fault: Option<Fault>,
+}
+
+#[derive(Default)]
+struct State {
+    rules: Vec<Rule>,
+    events: Vec<Event>,
+}
+
+/// Handle to the rules and events of a [`FaultVfs`]
+#[derive(Clone, Default)]
+pub struct Faults(Arc<Mutex<State>>);
+
+impl Faults {
+    /// Add a rule. Rules are checked in the order they were added.
+    pub fn add_rule(&self, rule: Rule) {
+        self.state().rules.push(rule);
+    }
+
+    /// Remove all rules.
+    pub fn clear_rules(&self) {
+        self.state().rules.clear();
+    }
+
+    /// Remove and return the recorded events.
+    pub fn take_events(&self) -> Vec<Event> {
+        std::mem::take(&mut self.state().events)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap()
+    }
+
+    fn check(
+        &self,
+        op: Op,
+        kind: Option<FileKind>,
+        offset: u64,
+        len: usize,
+    ) -> Result<Option<Fault>> {
+        let mut state = self.state();
+        let end = offset + len as u64;
+        let fault = state
+            .rules
+            .iter_mut()
+            .find_map(|r| r.trigger(op, kind, end));
+        state.events.push(Event {
+            op,
+            kind,
+            offset,
+            len,
+            fault,
+        });
+        match fault {
+            Some(Fault::Error(code)) => Err(err!(code)),
+            fault => Ok(fault),
+        }
+    }
+}
+
+/// VFS shim injecting faults into an underlying VFS.
+pub struct FaultVfs<V = SystemVfs> {
+    inner: V,
+    faults: Faults,
+}
+
+impl FaultVfs {
+    /// Wrap the default VFS, register the shim under `name` and return the
+    /// handle to control it.
+    pub fn register(name: &str) -> Result<Faults> {
+        let vfs = Self::new(SystemVfs::find(None::<&str>)?);
+        let faults = vfs.faults();
+        register_vfs(name, vfs, false)?;
+        Ok(faults)
+    }
+}
+
+impl<V: Vfs> FaultVfs<V> {
+    /// Wrap `inner`, without any rule.
+    #[must_use]
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            faults: Faults::default(),
+        }
+    }
+
+    /// Handle to control this VFS once registered
+    #[must_use]
+    pub fn faults(&self) -> Faults {
+        self.faults.clone()
+    }
+}
+
+impl<V: Vfs> Vfs for FaultVfs<V> {
+    type File = FaultFile<V::File>;
+
+    fn max_pathname(&self) -> c_int {
+        self.inner.max_pathname()
+    }
+
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<Self::File> {
+        let kind = options.kind();
+        self.faults.check(Op::Open, Some(kind), 0, 0)?;
+        Ok(FaultFile {
+            inner: self.inner.open(name, options)?,
+            kind,
+            faults: self.faults.clone(),
+        })
+    }
+
+    fn delete(&self, name: &str, sync_dir: bool) -> Result<()> {
+        self.faults.check(Op::Delete, None, 0, 0)?;
+        self.inner.delete(name, sync_dir)
+    }
+
+    fn access(&self, name: &str, check: AccessCheck) -> Result<bool> {
+        self.inner.access(name, check)
+    }
+
+    fn full_pathname(&self, name: &str) -> Result<String> {
+        self.inner.full_pathname(name)
+    }
+
+    fn randomness(&self, buf: &mut [u8]) -> usize {
+        self.inner.randomness(buf)
+    }
+
+    fn sleep(&self, duration: Duration) -> Duration {
+        self.inner.sleep(duration)
+    }
+
+    fn current_time(&self) -> i64 {
+        self.inner.current_time()
+    }
+}
+
+/// File opened by a [`FaultVfs`]
+pub struct FaultFile<F> {
+    inner: F,
+    kind: FileKind,
+    faults: Faults,
+}
+
+impl<F: VfsFile> FaultFile<F> {
+    fn check(&self, op: Op, offset: u64, len: usize) -> Result<Option<Fault>> {
+        self.faults.check(op, Some(self.kind), offset, len)
+    }
+}
+
+impl<F: VfsFile> VfsFile for FaultFile<F> {
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let fault = self.check(Op::Read, offset, buf.len())?;
+        let n = self.inner.read(buf, offset)?;
+        Ok(match fault {
+            Some(Fault::ShortRead) => n.min(buf.len() / 2),
+            _ => n,
+        })
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        self.check(Op::Write, offset, buf.len())?;
+        self.inner.write(buf, offset)
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.check(Op::Truncate, size, 0)?;
+        self.inner.truncate(size)
+    }
+
+    fn sync(&mut self, flags: SyncFlags) -> Result<()> {
+        self.check(Op::Sync, 0, 0)?;
+        self.inner.sync(flags)
+    }
+
+    fn file_size(&mut self) -> Result<u64> {
+        self.check(Op::FileSize, 0, 0)?;
+        self.inner.file_size()
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.check(Op::Lock, level as u64, 0)?;
+        self.inner.lock(level)
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    fn check_reserved_lock(&mut self) -> Result<bool> {
+        self.inner.check_reserved_lock()
+    }
+
+    unsafe fn file_control(&mut self, op: c_int, arg: *mut c_void) -> Result<()> {
+        unsafe { self.inner.file_control(op, arg) }
+    }
+
+    fn sector_size(&mut self) -> c_int {
+        self.inner.sector_size()
+    }
+
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        self.inner.device_characteristics()
+    }
+
+    fn supports_shm(&self) -> bool {
+        self.inner.supports_shm()
+    }
+
+    fn shm_map(&mut self, region: usize, size: usize, extend: bool) -> Result<*mut u8> {
+        self.check(Op::ShmMap, region as u64, size)?;
+        self.inner.shm_map(region, size, extend)
+    }
+
+    fn shm_lock(&mut self, offset: usize, n: usize, flags: ShmLockFlags) -> Result<()> {
+        // Unlocking must always succeed
+        if flags.contains(ShmLockFlags::SQLITE_SHM_LOCK) {
+            self.check(Op::ShmLock, offset as u64, n)?;
+        }
+        self.inner.shm_lock(offset, n, flags)
+    }
+
+    fn shm_barrier(&mut self) {
+        self.inner.shm_barrier();
+    }
+
+    fn shm_unmap(&mut self, delete: bool) -> Result<()> {
+        self.inner.shm_unmap(delete)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::{Fault, FaultVfs, Op, Rule};
+    use crate::vfs::FileKind;
+    use crate::{Connection, ErrorCode, OpenFlags, Result, ffi};
+
+    #[test]
+    fn test_rule() {
+        let mut rule = Rule::new(Op::Write).kind(FileKind::Wal).nth(2).times(1);
+        assert_eq!(None, rule.trigger(Op::Write, Some(FileKind::Wal), 10));
+        assert_eq!(None, rule.trigger(Op::Write, Some(FileKind::MainDb), 10));
+        assert_eq!(None, rule.trigger(Op::Read, Some(FileKind::Wal), 10));
+        assert_eq!(
+            Some(Fault::Error(ffi::SQLITE_IOERR_WRITE)),
+            rule.trigger(Op::Write, Some(FileKind::Wal), 10)
+        );
+        assert_eq!(None, rule.trigger(Op::Write, Some(FileKind::Wal), 10));
+
+        let mut rule = Rule::new(Op::Read).beyond(100).short_read();
+        assert_eq!(None, rule.trigger(Op::Read, None, 100));
+        assert_eq!(Some(Fault::ShortRead), rule.trigger(Op::Read, None, 101));
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_sync_failure() -> Result<()> {
+        let faults = FaultVfs::register("test_fault_sync")?;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("sync.db3");
+        let db =
+            Connection::open_with_flags_and_vfs(&path, OpenFlags::default(), "test_fault_sync")?;
+        db.execute_batch("CREATE TABLE foo(x);")?;
+
+        faults.add_rule(Rule::new(Op::Sync).kind(FileKind::MainDb).times(1));
+        let err = db.execute("INSERT INTO foo VALUES (1)", []).unwrap_err();
+        assert_eq!(
+            Some(ffi::SQLITE_IOERR_FSYNC),
+            err.sqlite_extended_error_code()
+        );
+        let events = faults.take_events();
+        assert!(
+            events
+                .iter()
+                .any(|e| e.op == Op::Write && e.kind == Some(FileKind::MainJournal))
+        );
+        assert_eq!(1, events.iter().filter(|e| e.fault.is_some()).count());
+
+        db.execute("INSERT INTO foo VALUES (2)", [])?;
+        let n: i64 = db.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(1, n);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_wal_full() -> Result<()> {
+        let faults = FaultVfs::register("test_fault_wal_full")?;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("wal.db3");
+        let db = Connection::open_with_flags_and_vfs(
+            &path,
+            OpenFlags::default(),
+            "test_fault_wal_full",
+        )?;
+        db.pragma_update(None, "journal_mode", "wal")?;
+        db.execute_batch("CREATE TABLE foo(x);")?;
+
+        faults.add_rule(
+            Rule::new(Op::Write)
+                .kind(FileKind::Wal)
+                .beyond(64 * 1024)
+                .error(ffi::SQLITE_FULL),
+        );
+        let err = (0..100)
+            .try_for_each(|_| {
+                db.execute("INSERT INTO foo VALUES (zeroblob(4096))", [])
+                    .map(|_| ())
+            })
+            .unwrap_err();
+        assert_eq!(Some(ErrorCode::DiskFull), err.sqlite_error_code());
+        let events = faults.take_events();
+        let failed = events.iter().find(|e| e.fault.is_some()).unwrap();
+        assert_eq!(Some(FileKind::Wal), failed.kind);
+        assert!(failed.offset + failed.len as u64 > 64 * 1024);
+        Ok(())
+    }
+}
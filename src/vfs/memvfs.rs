@@ -0,0 +1,576 @@
+//! In-memory VFS with copy-on-write snapshots.
+//!
+//! Databases live in process memory, split into fixed-size chunks shared
+//! between a database and its snapshots: taking a snapshot only copies
+//! pointers, and a chunk is duplicated the first time it is written.
+//!
+//! ```rust
+//! use rusqlite::vfs::memvfs::MemVfs;
+//! use rusqlite::{Connection, OpenFlags, Result};
+//!
+//! fn main() -> Result<()> {
+//!     let vfs = MemVfs::register("memvfs-doc")?;
+//!     let db = Connection::open_with_flags_and_vfs("fixture.db", OpenFlags::default(), vfs.name())?;
+//!     db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (1);")?;
+//!
+//!     let fork = db.fork()?;
+//!     fork.execute("INSERT INTO foo VALUES (2)", [])?;
+//!     let n: i64 = db.query_row("SELECT count(*) FROM foo", [], |r| r.get(0))?;
+//!     assert_eq!(1, n);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Shared memory is not implemented, so WAL mode requires
+//! `PRAGMA locking_mode = EXCLUSIVE`.
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::ffi::{c_int, c_void};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{
+    AccessCheck, DeviceCharacteristics, FileName, LockLevel, OpenOptions, SyncFlags, Vfs, VfsFile,
+    register_vfs,
+};
+use crate::{Connection, MAIN_DB, OpenFlags, Result, ffi};
+
+const CHUNK_SIZE: usize = 4096;
+
+// Private file-control opcode used by `Connection::fork` to find the MemVfs
+// and file name of a connection.
+const FCNTL_MEMVFS_FILE: c_int = 0x6d65_6d76; // 'memv'
+
+type Chunk = Arc<[u8; CHUNK_SIZE]>;
+
+/// File content, shared chunk by chunk with snapshots
+#[derive(Clone, Default)]
+struct Content {
+    chunks: Vec<Chunk>,
+    len: u64,
+}
+
+impl Content {
+    fn read(&self, buf: &mut [u8], offset: u64) -> usize {
+        if offset >= self.len {
+            return 0;
+        }
+        let n = buf.len().min((self.len - offset) as usize);
+        let mut done = 0;
+        while done < n {
+            let pos = offset as usize + done;
+            let (idx, off) = (pos / CHUNK_SIZE, pos % CHUNK_SIZE);
+            let len = (CHUNK_SIZE - off).min(n - done);
+            buf[done..done + len].copy_from_slice(&self.chunks[idx][off..off + len]);
+            done += len;
+        }
+        n
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) {
+        let end = offset as usize + buf.len();
+        self.resize_chunks(end);
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset as usize + done;
+            let (idx, off) = (pos / CHUNK_SIZE, pos % CHUNK_SIZE);
+            let len = (CHUNK_SIZE - off).min(buf.len() - done);
+            Arc::make_mut(&mut self.chunks[idx])[off..off + len]
+                .copy_from_slice(&buf[done..done + len]);
+            done += len;
+        }
+        self.len = self.len.max(end as u64);
+    }
+
+    fn truncate(&mut self, size: u64) {
+        if size < self.len {
+            let size = size as usize;
+            self.chunks.truncate(size.div_ceil(CHUNK_SIZE));
+            // so that bytes are zero if the file is extended again
+            let off = size % CHUNK_SIZE;
+            if off != 0
+                && let Some(last) = self.chunks.last_mut()
+            {
+                Arc::make_mut(last)[off..].fill(0);
+            }
+        } else {
+            self.resize_chunks(size as usize);
+        }
+        self.len = size;
+    }
+
+    fn resize_chunks(&mut self, len: usize) {
+        let n = len.div_ceil(CHUNK_SIZE);
+        if n > self.chunks.len() {
+            self.chunks.resize_with(n, || Arc::new([0; CHUNK_SIZE]));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Node {
+    content: Content,
+    // number of open handles
+    handles: usize,
+    // number of SHARED locks (or higher)
+    shared: usize,
+    reserved: bool,
+    pending: bool,
+    exclusive: bool,
+    // deleted when the last handle is closed
+    transient: bool,
+}
+
+struct Inner {
+    name: String,
+    files: Mutex<HashMap<String, Arc<Mutex<Node>>>>,
+    forks: AtomicUsize,
+}
+
+/// In-memory VFS where databases can be cheaply snapshotted and forked.
+///
+/// Cloning a `MemVfs` gives another handle to the same set of files.
+#[derive(Clone)]
+pub struct MemVfs(Arc<Inner>);
+
+impl MemVfs {
+    /// Create a new, empty, in-memory VFS and register it under `name`.
+    pub fn register(name: &str) -> Result<Self> {
+        let vfs = Self(Arc::new(Inner {
+            name: name.to_owned(),
+            files: Mutex::new(HashMap::new()),
+            forks: AtomicUsize::new(0),
+        }));
+        register_vfs(name, vfs.clone(), false)?;
+        Ok(vfs)
+    }
+
+    /// Name under which this VFS is registered
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Create the database `dst` as a copy-on-write snapshot of the database
+    /// `src`.
+    ///
+    /// Fails with `SQLITE_BUSY` while a connection is writing to `src` and
+    /// with `SQLITE_CANTOPEN` if `dst` already exists.
+    pub fn snapshot(&self, src: &str, dst: &str) -> Result<()> {
+        self.snapshot_node(src, dst, false)
+    }
+
+    /// Delete the file `name`.
+    ///
+    /// Connections which have the file open keep their own view of it.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        match self.files().remove(name) {
+            Some(_) => Ok(()),
+            None => Err(err!(ffi::SQLITE_IOERR_DELETE_NOENT)),
+        }
+    }
+
+    /// Names of all files
+    #[must_use]
+    pub fn file_names(&self) -> Vec<String> {
+        self.files().keys().cloned().collect()
+    }
+
+    fn files(&self) -> MutexGuard<'_, HashMap<String, Arc<Mutex<Node>>>> {
+        self.0.files.lock().unwrap()
+    }
+
+    fn snapshot_node(&self, src: &str, dst: &str, transient: bool) -> Result<()> {
+        let mut files = self.files();
+        let content = {
+            let node = files
+                .get(src)
+                .ok_or_else(|| err!(ffi::SQLITE_CANTOPEN, "no such database: {src}"))?;
+            let node = node.lock().unwrap();
+            if node.pending || node.exclusive {
+                return Err(err!(ffi::SQLITE_BUSY));
+            }
+            node.content.clone()
+        };
+        match files.entry(dst.to_owned()) {
+            Entry::Occupied(_) => Err(err!(ffi::SQLITE_CANTOPEN, "database exists: {dst}")),
+            Entry::Vacant(e) => {
+                e.insert(Arc::new(Mutex::new(Node {
+                    content,
+                    transient,
+                    ..Node::default()
+                })));
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Vfs for MemVfs {
+    type File = MemFile;
+
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<MemFile> {
+        let Some(name) = name else {
+            // temporary file
+            return Ok(MemFile {
+                vfs: self.clone(),
+                name: None,
+                node: Arc::new(Mutex::new(Node {
+                    handles: 1,
+                    ..Node::default()
+                })),
+                level: LockLevel::None,
+                holds_reserved: false,
+                holds_pending: false,
+                delete_on_close: true,
+            });
+        };
+        let name = name.as_str()?.to_owned();
+        let node = match self.files().entry(name.clone()) {
+            Entry::Occupied(e) => {
+                if options.exclusive() && options.create() {
+                    return Err(err!(ffi::SQLITE_CANTOPEN));
+                }
+                e.get().clone()
+            }
+            Entry::Vacant(e) => {
+                if !options.create() {
+                    return Err(err!(ffi::SQLITE_CANTOPEN));
+                }
+                e.insert(Arc::default()).clone()
+            }
+        };
+        node.lock().unwrap().handles += 1;
+        Ok(MemFile {
+            vfs: self.clone(),
+            name: Some(name),
+            node,
+            level: LockLevel::None,
+            holds_reserved: false,
+            holds_pending: false,
+            delete_on_close: options.delete_on_close(),
+        })
+    }
+
+    fn delete(&self, name: &str, _: bool) -> Result<()> {
+        Self::delete(self, name)
+    }
+
+    fn access(&self, name: &str, _: AccessCheck) -> Result<bool> {
+        Ok(self.files().contains_key(name))
+    }
+}
+
+/// File opened by a [`MemVfs`]
+pub struct MemFile {
+    vfs: MemVfs,
+    name: Option<String>,
+    node: Arc<Mutex<Node>>,
+    level: LockLevel,
+    /// Whether this handle set `reserved` on the node: SQLite may go from
+    /// SHARED to EXCLUSIVE directly (hot journal rollback)
+    holds_reserved: bool,
+    /// Whether this handle set `pending` on the node
+    holds_pending: bool,
+    delete_on_close: bool,
+}
+
+impl MemFile {
+    fn node(&self) -> MutexGuard<'_, Node> {
+        self.node.lock().unwrap()
+    }
+}
+
+impl VfsFile for MemFile {
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        Ok(self.node().content.read(buf, offset))
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        self.node().content.write(buf, offset);
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.node().content.truncate(size);
+        Ok(())
+    }
+
+    fn sync(&mut self, _: SyncFlags) -> Result<()> {
+        Ok(())
+    }
+
+    fn file_size(&mut self) -> Result<u64> {
+        Ok(self.node().content.len)
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        if level <= self.level {
+            return Ok(());
+        }
+        let mut node = self.node.lock().unwrap();
+        match level {
+            LockLevel::Shared => {
+                if node.pending || node.exclusive {
+                    return Err(err!(ffi::SQLITE_BUSY));
+                }
+                node.shared += 1;
+            }
+            LockLevel::Reserved => {
+                if node.reserved || node.pending || node.exclusive {
+                    return Err(err!(ffi::SQLITE_BUSY));
+                }
+                node.reserved = true;
+                self.holds_reserved = true;
+            }
+            LockLevel::Pending | LockLevel::Exclusive => {
+                if self.level < LockLevel::Pending {
+                    if node.pending || node.exclusive {
+                        return Err(err!(ffi::SQLITE_BUSY));
+                    }
+                    node.pending = true;
+                    self.holds_pending = true;
+                    drop(node);
+                    self.level = LockLevel::Pending;
+                    node = self.node.lock().unwrap();
+                }
+                if level == LockLevel::Exclusive {
+                    if node.shared > 1 {
+                        return Err(err!(ffi::SQLITE_BUSY));
+                    }
+                    node.exclusive = true;
+                }
+            }
+            LockLevel::None => {}
+        }
+        drop(node);
+        self.level = level;
+        Ok(())
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        if level >= self.level {
+            return Ok(());
+        }
+        let mut node = self.node.lock().unwrap();
+        if self.holds_reserved {
+            node.reserved = false;
+            self.holds_reserved = false;
+        }
+        if self.holds_pending {
+            node.pending = false;
+            self.holds_pending = false;
+        }
+        if self.level == LockLevel::Exclusive {
+            node.exclusive = false;
+        }
+        if level == LockLevel::None && self.level >= LockLevel::Shared {
+            node.shared -= 1;
+        }
+        drop(node);
+        self.level = level;
+        Ok(())
+    }
+
+    fn check_reserved_lock(&mut self) -> Result<bool> {
+        let node = self.node();
+        Ok(node.reserved || node.pending || node.exclusive)
+    }
+
+    unsafe fn file_control(&mut self, op: c_int, arg: *mut c_void) -> Result<()> {
+        match (op, &self.name) {
+            (FCNTL_MEMVFS_FILE, Some(name)) => {
+                unsafe {
+                    *arg.cast::<Option<(MemVfs, String)>>() =
+                        Some((self.vfs.clone(), name.clone()));
+                };
+                Ok(())
+            }
+            _ => Err(err!(ffi::SQLITE_NOTFOUND)),
+        }
+    }
+
+    fn sector_size(&mut self) -> c_int {
+        1024
+    }
+
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        DeviceCharacteristics::SQLITE_IOCAP_ATOMIC
+            | DeviceCharacteristics::SQLITE_IOCAP_POWERSAFE_OVERWRITE
+            | DeviceCharacteristics::SQLITE_IOCAP_SAFE_APPEND
+            | DeviceCharacteristics::SQLITE_IOCAP_SEQUENTIAL
+    }
+}
+
+impl Drop for MemFile {
+    fn drop(&mut self) {
+        let _ = self.unlock(LockLevel::None);
+        let remove = {
+            let mut node = self.node();
+            node.handles -= 1;
+            self.delete_on_close || (node.transient && node.handles == 0)
+        };
+        if let (true, Some(name)) = (remove, &self.name) {
+            let mut files = self.vfs.files();
+            if files.get(name).is_some_and(|n| Arc::ptr_eq(n, &self.node)) {
+                files.remove(name);
+            }
+        }
+    }
+}
+
+impl Connection {
+    /// Open a new connection on a copy-on-write snapshot of the main
+    /// database of this connection, which must have been opened with a
+    /// [`MemVfs`].
+    ///
+    /// The snapshot is deleted when the returned connection is closed.
+    pub fn fork(&self) -> Result<Self> {
+        let mut file: Option<(MemVfs, String)> = None;
+        unsafe {
+            self.db
+                .borrow()
+                .file_control(Some(MAIN_DB), FCNTL_MEMVFS_FILE, (&raw mut file).cast())
+        }
+        .map_err(|_| err!(ffi::SQLITE_MISUSE, "database not opened with a MemVfs"))?;
+        let (vfs, name) = file.ok_or_else(|| err!(ffi::SQLITE_MISUSE))?;
+        let n = vfs.0.forks.fetch_add(1, Ordering::Relaxed);
+        let fork = format!("{name}-fork{n}");
+        vfs.snapshot_node(&name, &fork, true)?;
+        match Self::open_with_flags_and_vfs(&fork, OpenFlags::default(), vfs.name()) {
+            Ok(db) => Ok(db),
+            Err(e) => {
+                let _ = vfs.delete(&fork);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::sync::Arc;
+
+    use super::{CHUNK_SIZE, Content, MemFile, MemVfs, Node};
+    use crate::vfs::{LockLevel, VfsFile as _};
+    use crate::{Connection, ErrorCode, OpenFlags, Result};
+
+    #[test]
+    fn test_content() {
+        let mut content = Content::default();
+        content.write(&[1; 10], CHUNK_SIZE as u64 - 5);
+        assert_eq!(CHUNK_SIZE as u64 + 5, content.len);
+        assert_eq!(2, content.chunks.len());
+
+        let snapshot = content.clone();
+        content.write(&[2; 3], 0);
+        assert!(!Arc::ptr_eq(&snapshot.chunks[0], &content.chunks[0]));
+        assert!(Arc::ptr_eq(&snapshot.chunks[1], &content.chunks[1]));
+
+        let mut buf = [0; 12];
+        assert_eq!(10, content.read(&mut buf, CHUNK_SIZE as u64 - 5));
+        assert_eq!([1; 10], buf[..10]);
+        assert_eq!(3, snapshot.read(&mut buf[..3], 0));
+        assert_eq!([0; 3], buf[..3]);
+
+        content.truncate(CHUNK_SIZE as u64 - 3);
+        content.truncate(CHUNK_SIZE as u64 + 5);
+        assert_eq!(10, content.read(&mut buf, CHUNK_SIZE as u64 - 5));
+        assert_eq!([1, 1, 0, 0, 0, 0, 0, 0, 0, 0], buf[..10]);
+    }
+
+    #[test]
+    fn test_snapshot() -> Result<()> {
+        let vfs = MemVfs::register("test_memvfs_snapshot")?;
+        let db = Connection::open_with_flags_and_vfs("main.db", OpenFlags::default(), vfs.name())?;
+        db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (1);")?;
+        vfs.snapshot("main.db", "copy.db")?;
+        db.execute("INSERT INTO foo VALUES (2)", [])?;
+
+        let copy =
+            Connection::open_with_flags_and_vfs("copy.db", OpenFlags::default(), vfs.name())?;
+        let n: i64 = copy.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(1, n);
+        let n: i64 = db.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(2, n);
+
+        let err = vfs.snapshot("main.db", "copy.db").unwrap_err();
+        assert_eq!(Some(ErrorCode::CannotOpen), err.sqlite_error_code());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fork() -> Result<()> {
+        let vfs = MemVfs::register("test_memvfs_fork")?;
+        let db = Connection::open_with_flags_and_vfs("main.db", OpenFlags::default(), vfs.name())?;
+        db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (1);")?;
+
+        let fork = db.fork()?;
+        fork.execute("INSERT INTO foo VALUES (2)", [])?;
+        let n: i64 = fork.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(2, n);
+        let n: i64 = db.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(1, n);
+        assert_eq!(2, vfs.file_names().len());
+        drop(fork);
+        assert_eq!(vec!["main.db".to_owned()], vfs.file_names());
+
+        let db = Connection::open_in_memory()?;
+        db.fork().unwrap_err();
+        Ok(())
+    }
+
+    #[test]
+    fn test_locking() -> Result<()> {
+        let vfs = MemVfs::register("test_memvfs_locking")?;
+        let db1 = Connection::open_with_flags_and_vfs("main.db", OpenFlags::default(), vfs.name())?;
+        let db2 = Connection::open_with_flags_and_vfs("main.db", OpenFlags::default(), vfs.name())?;
+        db1.execute_batch("CREATE TABLE foo(x);")?;
+
+        db1.execute_batch("BEGIN EXCLUSIVE; INSERT INTO foo VALUES (1);")?;
+        let err = db2
+            .one_column::<i64, _>("SELECT count(*) FROM foo", [])
+            .unwrap_err();
+        assert_eq!(Some(ErrorCode::DatabaseBusy), err.sqlite_error_code());
+        db1.execute_batch("COMMIT")?;
+
+        let n: i64 = db2.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(1, n);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unlock_keeps_other_locks() -> Result<()> {
+        let vfs = MemVfs::register("test_memvfs_unlock")?;
+        let node = Arc::new(std::sync::Mutex::new(Node {
+            handles: 3,
+            ..Node::default()
+        }));
+        let file = || MemFile {
+            vfs: vfs.clone(),
+            name: None,
+            node: node.clone(),
+            level: LockLevel::None,
+            holds_reserved: false,
+            holds_pending: false,
+            delete_on_close: false,
+        };
+        let (mut a, mut b, mut c) = (file(), file(), file());
+        a.lock(LockLevel::Shared)?;
+        a.lock(LockLevel::Reserved)?;
+        b.lock(LockLevel::Shared)?;
+        // hot journal rollback: SHARED to EXCLUSIVE, busy because of `a`
+        b.lock(LockLevel::Exclusive).unwrap_err();
+        b.unlock(LockLevel::None)?;
+        assert!(c.check_reserved_lock()?);
+        c.lock(LockLevel::Shared)?;
+        let err = c.lock(LockLevel::Reserved).unwrap_err();
+        assert_eq!(Some(ErrorCode::DatabaseBusy), err.sqlite_error_code());
+        a.unlock(LockLevel::None)?;
+        c.lock(LockLevel::Reserved)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,1389 @@
+//! Implement and register custom VFS (OS interface) layers.
+//!
+//! Follow these steps to create your own VFS:
+//! 1. Write implementation of [`Vfs`] and [`VfsFile`] traits.
+//! 2. Register your [`Vfs`] using [`register_vfs`].
+//! 3. Open a database with
+//!    [`Connection::open_with_flags_and_vfs`](crate::Connection::open_with_flags_and_vfs)
+//!    and the name given at registration.
+//!
+//! [`SystemVfs`] gives access to an already registered VFS (for example, the
+//! default one of the platform) so that shims can delegate to it.
+//!
+//! (See [SQLite doc](https://sqlite.org/vfs.html))
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::marker::PhantomData;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr::{self, NonNull};
+use std::slice;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::ffi;
+use crate::{Error, Name, Result};
+
+/// File locking levels
+///
+/// (See [SQLite doc](https://sqlite.org/c3ref/c_lock_exclusive.html))
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum LockLevel {
+    /// Equivalent to `SQLITE_LOCK_NONE`
+    None = ffi::SQLITE_LOCK_NONE,
+    /// Equivalent to `SQLITE_LOCK_SHARED`
+    Shared = ffi::SQLITE_LOCK_SHARED,
+    /// Equivalent to `SQLITE_LOCK_RESERVED`
+    Reserved = ffi::SQLITE_LOCK_RESERVED,
+    /// Equivalent to `SQLITE_LOCK_PENDING`
+    Pending = ffi::SQLITE_LOCK_PENDING,
+    /// Equivalent to `SQLITE_LOCK_EXCLUSIVE`
+    Exclusive = ffi::SQLITE_LOCK_EXCLUSIVE,
+}
+
+impl LockLevel {
+    fn from_raw(level: c_int) -> Self {
+        match level {
+            ffi::SQLITE_LOCK_NONE => Self::None,
+            ffi::SQLITE_LOCK_SHARED => Self::Shared,
+            ffi::SQLITE_LOCK_RESERVED => Self::Reserved,
+            ffi::SQLITE_LOCK_PENDING => Self::Pending,
+            _ => Self::Exclusive,
+        }
+    }
+}
+
+/// Kind of check performed by [`Vfs::access`]
+///
+/// (See [SQLite doc](https://sqlite.org/c3ref/c_access_exists.html))
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AccessCheck {
+    /// Equivalent to `SQLITE_ACCESS_EXISTS`
+    Exists = ffi::SQLITE_ACCESS_EXISTS,
+    /// Equivalent to `SQLITE_ACCESS_READWRITE`
+    ReadWrite = ffi::SQLITE_ACCESS_READWRITE,
+    /// Equivalent to `SQLITE_ACCESS_READ`
+    Read = ffi::SQLITE_ACCESS_READ,
+}
+
+/// Kind of file opened by SQLite
+///
+/// (See [SQLite doc](https://sqlite.org/c3ref/c_open_autoproxy.html))
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum FileKind {
+    /// Main database file (`SQLITE_OPEN_MAIN_DB`)
+    MainDb,
+    /// Rollback journal of a main database (`SQLITE_OPEN_MAIN_JOURNAL`)
+    MainJournal,
+    /// Temporary database (`SQLITE_OPEN_TEMP_DB`)
+    TempDb,
+    /// Rollback journal of a temporary database (`SQLITE_OPEN_TEMP_JOURNAL`)
+    TempJournal,
+    /// Transient database used by sorting and materialization
+    /// (`SQLITE_OPEN_TRANSIENT_DB`)
+    TransientDb,
+    /// Statement journal (`SQLITE_OPEN_SUBJOURNAL`)
+    SubJournal,
+    /// Super-journal of a multi-database transaction
+    /// (`SQLITE_OPEN_SUPER_JOURNAL`)
+    SuperJournal,
+    /// Write-ahead log (`SQLITE_OPEN_WAL`)
+    Wal,
+}
+
+impl FileKind {
+    const MASK: c_int = ffi::SQLITE_OPEN_MAIN_DB
+        | ffi::SQLITE_OPEN_MAIN_JOURNAL
+        | ffi::SQLITE_OPEN_TEMP_DB
+        | ffi::SQLITE_OPEN_TEMP_JOURNAL
+        | ffi::SQLITE_OPEN_TRANSIENT_DB
+        | ffi::SQLITE_OPEN_SUBJOURNAL
+        | ffi::SQLITE_OPEN_SUPER_JOURNAL
+        | ffi::SQLITE_OPEN_WAL;
+
+    fn from_flags(flags: c_int) -> Self {
+        match flags & Self::MASK {
+            ffi::SQLITE_OPEN_MAIN_JOURNAL => Self::MainJournal,
+            ffi::SQLITE_OPEN_TEMP_DB => Self::TempDb,
+            ffi::SQLITE_OPEN_TEMP_JOURNAL => Self::TempJournal,
+            ffi::SQLITE_OPEN_TRANSIENT_DB => Self::TransientDb,
+            ffi::SQLITE_OPEN_SUBJOURNAL => Self::SubJournal,
+            ffi::SQLITE_OPEN_SUPER_JOURNAL => Self::SuperJournal,
+            ffi::SQLITE_OPEN_WAL => Self::Wal,
+            _ => Self::MainDb,
+        }
+    }
+}
+
+/// Options given to [`Vfs::open`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OpenOptions {
+    flags: c_int,
+}
+
+impl OpenOptions {
+    /// Build options from raw `SQLITE_OPEN_*` flags.
+    #[must_use]
+    pub const fn from_raw(flags: c_int) -> Self {
+        Self { flags }
+    }
+
+    /// Raw `SQLITE_OPEN_*` flags
+    #[must_use]
+    pub const fn raw(&self) -> c_int {
+        self.flags
+    }
+
+    /// Kind of file to open
+    #[must_use]
+    pub fn kind(&self) -> FileKind {
+        FileKind::from_flags(self.flags)
+    }
+
+    /// `SQLITE_OPEN_READONLY`
+    #[must_use]
+    pub const fn read_only(&self) -> bool {
+        self.flags & ffi::SQLITE_OPEN_READONLY != 0
+    }
+
+    /// `SQLITE_OPEN_READWRITE`
+    #[must_use]
+    pub const fn read_write(&self) -> bool {
+        self.flags & ffi::SQLITE_OPEN_READWRITE != 0
+    }
+
+    /// `SQLITE_OPEN_CREATE`
+    #[must_use]
+    pub const fn create(&self) -> bool {
+        self.flags & ffi::SQLITE_OPEN_CREATE != 0
+    }
+
+    /// `SQLITE_OPEN_DELETEONCLOSE`
+    #[must_use]
+    pub const fn delete_on_close(&self) -> bool {
+        self.flags & ffi::SQLITE_OPEN_DELETEONCLOSE != 0
+    }
+
+    /// `SQLITE_OPEN_EXCLUSIVE`
+    #[must_use]
+    pub const fn exclusive(&self) -> bool {
+        self.flags & ffi::SQLITE_OPEN_EXCLUSIVE != 0
+    }
+
+    /// Report to SQLite that a file requested as read-write has been opened
+    /// read-only (for example, because it is write-protected).
+    pub fn set_read_only(&mut self) {
+        self.flags &= !(ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE);
+        self.flags |= ffi::SQLITE_OPEN_READONLY;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags given to [`VfsFile::sync`]
+    ///
+    /// (See [SQLite doc](https://sqlite.org/c3ref/c_sync_dataonly.html))
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    #[repr(C)]
+    pub struct SyncFlags: c_int {
+        /// Normal fsync()
+        const SQLITE_SYNC_NORMAL = ffi::SQLITE_SYNC_NORMAL;
+        /// Mac OS X style fullsync
+        const SQLITE_SYNC_FULL = ffi::SQLITE_SYNC_FULL;
+        /// Only the data of the file needs to be synced, not its inode
+        const SQLITE_SYNC_DATAONLY = ffi::SQLITE_SYNC_DATAONLY;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags given to [`VfsFile::shm_lock`]
+    ///
+    /// (See [SQLite doc](https://sqlite.org/c3ref/c_shm_exclusive.html))
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    #[repr(C)]
+    pub struct ShmLockFlags: c_int {
+        /// Release the lock
+        const SQLITE_SHM_UNLOCK = ffi::SQLITE_SHM_UNLOCK;
+        /// Acquire the lock
+        const SQLITE_SHM_LOCK = ffi::SQLITE_SHM_LOCK;
+        /// Shared lock
+        const SQLITE_SHM_SHARED = ffi::SQLITE_SHM_SHARED;
+        /// Exclusive lock
+        const SQLITE_SHM_EXCLUSIVE = ffi::SQLITE_SHM_EXCLUSIVE;
+    }
+}
+
+bitflags::bitflags! {
+    /// I/O characteristics of a file returned by
+    /// [`VfsFile::device_characteristics`]
+    ///
+    /// (See [SQLite doc](https://sqlite.org/c3ref/c_iocap_atomic.html))
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    #[repr(C)]
+    pub struct DeviceCharacteristics: c_int {
+        /// Writes of any size are atomic
+        const SQLITE_IOCAP_ATOMIC = ffi::SQLITE_IOCAP_ATOMIC;
+        /// Data is appended to the file before its size is increased
+        const SQLITE_IOCAP_SAFE_APPEND = ffi::SQLITE_IOCAP_SAFE_APPEND;
+        /// Writes are performed in the order they are issued
+        const SQLITE_IOCAP_SEQUENTIAL = ffi::SQLITE_IOCAP_SEQUENTIAL;
+        /// The file can still be used after being deleted
+        const SQLITE_IOCAP_UNDELETABLE_WHEN_OPEN = ffi::SQLITE_IOCAP_UNDELETABLE_WHEN_OPEN;
+        /// A write does not modify bytes outside the written range
+        const SQLITE_IOCAP_POWERSAFE_OVERWRITE = ffi::SQLITE_IOCAP_POWERSAFE_OVERWRITE;
+        /// The file never changes
+        const SQLITE_IOCAP_IMMUTABLE = ffi::SQLITE_IOCAP_IMMUTABLE;
+        /// Batch atomic writes are supported
+        const SQLITE_IOCAP_BATCH_ATOMIC = ffi::SQLITE_IOCAP_BATCH_ATOMIC;
+        /// Partial page reads are supported (3.48.0)
+        const SQLITE_IOCAP_SUBPAGE_READ = 0x0000_8000;
+        // Any other bit set by the underlying VFS
+        const _ = !0;
+    }
+}
+
+/// Name of a file opened by SQLite.
+///
+/// This is the pointer given by SQLite to `xOpen` which gives access to URI
+/// parameters and must be forwarded as-is to an underlying VFS (see
+/// [`SystemVfs::open`]).
+#[derive(Clone, Copy)]
+pub struct FileName<'a> {
+    ptr: NonNull<c_char>,
+    phantom: PhantomData<&'a CStr>,
+}
+
+impl<'a> FileName<'a> {
+    /// # Safety
+    ///
+    /// `ptr` must be a filename given by SQLite to `xOpen`.
+    #[must_use]
+    pub unsafe fn from_ptr(ptr: NonNull<c_char>) -> Self {
+        Self {
+            ptr,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Raw pointer to pass to an underlying VFS
+    #[must_use]
+    pub fn as_ptr(&self) -> *const c_char {
+        self.ptr.as_ptr()
+    }
+
+    /// Filename as a C string
+    #[must_use]
+    pub fn as_cstr(&self) -> &'a CStr {
+        unsafe { CStr::from_ptr(self.ptr.as_ptr()) }
+    }
+
+    /// Filename as UTF-8
+    pub fn as_str(&self) -> Result<&'a str> {
+        Ok(self.as_cstr().to_str()?)
+    }
+
+    /// Value of the URI parameter `key`, if any
+    #[must_use]
+    pub fn parameter(&self, key: &CStr) -> Option<&'a CStr> {
+        unsafe {
+            let value = ffi::sqlite3_uri_parameter(self.ptr.as_ptr(), key.as_ptr());
+            if value.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(value))
+            }
+        }
+    }
+
+    /// Name of the main database file associated with this journal or WAL
+    /// file
+    #[must_use]
+    pub fn database(&self) -> &'a CStr {
+        unsafe { CStr::from_ptr(ffi::sqlite3_filename_database(self.ptr.as_ptr())) }
+    }
+}
+
+impl std::fmt::Debug for FileName<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_cstr().fmt(f)
+    }
+}
+
+/// OS interface trait.
+///
+/// Methods take `&self` because a VFS is shared by all connections and
+/// threads that use it.
+///
+/// (See [SQLite doc](https://sqlite.org/c3ref/vfs.html))
+pub trait Vfs: Send + Sync + 'static {
+    /// Specific file implementation
+    type File: VfsFile;
+
+    /// Maximum length of a pathname returned by
+    /// [`full_pathname`](Vfs::full_pathname)
+    fn max_pathname(&self) -> c_int {
+        1024
+    }
+
+    /// Open a file.
+    ///
+    /// `name` is `None` for temporary files, which are expected to be deleted
+    /// on close. Implementations which open a file read-only when
+    /// read-write access was requested must report it with
+    /// [`OpenOptions::set_read_only`].
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<Self::File>;
+
+    /// Delete a file.
+    fn delete(&self, name: &str, sync_dir: bool) -> Result<()>;
+
+    /// Check whether a file exists or is readable / writable.
+    fn access(&self, name: &str, check: AccessCheck) -> Result<bool>;
+
+    /// Canonical form of `name`.
+    ///
+    /// Return `name` as-is by default.
+    fn full_pathname(&self, name: &str) -> Result<String> {
+        Ok(name.to_owned())
+    }
+
+    /// Fill `buf` with random bytes and return the number of bytes written.
+    fn randomness(&self, buf: &mut [u8]) -> usize {
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher as _, Hasher as _};
+        let state = RandomState::new();
+        for (i, chunk) in buf.chunks_mut(8).enumerate() {
+            let mut hasher = state.build_hasher();
+            hasher.write_usize(i);
+            let bytes = hasher.finish().to_ne_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        buf.len()
+    }
+
+    /// Suspend the calling thread for at least `duration` and return the time
+    /// actually slept.
+    fn sleep(&self, duration: Duration) -> Duration {
+        std::thread::sleep(duration);
+        duration
+    }
+
+    /// Current time as milliseconds since the Julian epoch.
+    fn current_time(&self) -> i64 {
+        const UNIX_EPOCH_JULIAN_MS: i64 = 210_866_760_000_000;
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        UNIX_EPOCH_JULIAN_MS + since_epoch.as_millis() as i64
+    }
+}
+
+/// Open file trait.
+///
+/// (See [SQLite doc](https://sqlite.org/c3ref/io_methods.html))
+pub trait VfsFile: Send + Sized + 'static {
+    /// Read `buf.len()` bytes at `offset` and return the number of bytes
+    /// actually read.
+    ///
+    /// Reading fewer bytes than requested is reported to SQLite as a short
+    /// read and the remaining bytes are zero-filled.
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize>;
+
+    /// Write `buf` at `offset`.
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()>;
+
+    /// Truncate or extend the file to `size` bytes.
+    fn truncate(&mut self, size: u64) -> Result<()>;
+
+    /// Flush the content of the file to persistent storage.
+    fn sync(&mut self, flags: SyncFlags) -> Result<()>;
+
+    /// Current size of the file in bytes.
+    fn file_size(&mut self) -> Result<u64>;
+
+    /// Upgrade the lock held on the file to `level`.
+    fn lock(&mut self, level: LockLevel) -> Result<()>;
+
+    /// Downgrade the lock held on the file to `level`
+    /// ([`None`](LockLevel::None) or [`Shared`](LockLevel::Shared)).
+    fn unlock(&mut self, level: LockLevel) -> Result<()>;
+
+    /// Whether any connection holds a `RESERVED`, `PENDING` or `EXCLUSIVE`
+    /// lock on the file.
+    fn check_reserved_lock(&mut self) -> Result<bool>;
+
+    /// Handle a file-control opcode.
+    ///
+    /// Unknown opcodes must return `SQLITE_NOTFOUND` which is what the
+    /// default implementation does.
+    ///
+    /// (See [SQLite doc](https://sqlite.org/c3ref/c_fcntl_begin_atomic_write.html))
+    ///
+    /// # Safety
+    ///
+    /// `arg` must be valid for the type expected by `op`.
+    unsafe fn file_control(&mut self, op: c_int, arg: *mut c_void) -> Result<()> {
+        let _ = (op, arg);
+        Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_NOTFOUND),
+            None,
+        ))
+    }
+
+    /// Sector size of the underlying storage
+    fn sector_size(&mut self) -> c_int {
+        4096
+    }
+
+    /// I/O capabilities of the underlying storage
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        DeviceCharacteristics::empty()
+    }
+
+    /// Whether shared-memory methods are implemented, which is required for
+    /// WAL mode (unless `locking_mode` is `EXCLUSIVE`).
+    ///
+    /// Checked once, just after [`Vfs::open`].
+    fn supports_shm(&self) -> bool {
+        false
+    }
+
+    /// Map the shared-memory region number `region` of `size` bytes.
+    ///
+    /// The returned pointer must remain valid until
+    /// [`shm_unmap`](VfsFile::shm_unmap) is called. A null pointer may be
+    /// returned when the region does not exist and `extend` is false.
+    fn shm_map(&mut self, region: usize, size: usize, extend: bool) -> Result<*mut u8> {
+        let _ = (region, size, extend);
+        Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_IOERR_SHMMAP),
+            None,
+        ))
+    }
+
+    /// Acquire or release locks on `n` shared-memory slots starting at
+    /// `offset`.
+    fn shm_lock(&mut self, offset: usize, n: usize, flags: ShmLockFlags) -> Result<()> {
+        let _ = (offset, n, flags);
+        Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_IOERR_SHMLOCK),
+            None,
+        ))
+    }
+
+    /// Memory barrier on shared-memory
+    fn shm_barrier(&mut self) {
+        std::sync::atomic::fence(std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Unmap shared-memory regions, deleting the underlying storage if
+    /// `delete` is true.
+    fn shm_unmap(&mut self, delete: bool) -> Result<()> {
+        let _ = delete;
+        Ok(())
+    }
+}
+
+/// Register `vfs` under `name`, optionally as the default VFS.
+///
+/// The VFS is never deallocated: SQLite keeps a pointer to it for the
+/// lifetime of the process.
+///
+/// (See [SQLite doc](https://sqlite.org/c3ref/vfs_find.html))
+pub fn register_vfs<V: Vfs, N: Name>(name: N, vfs: V, make_default: bool) -> Result<()> {
+    let name = CString::from(name.as_cstr()?.as_ref());
+    let state = Box::new(VfsState {
+        base: ffi::sqlite3_vfs {
+            iVersion: 2,
+            szOsFile: size_of::<FileState<V::File>>() as c_int,
+            mxPathname: vfs.max_pathname(),
+            pNext: ptr::null_mut(),
+            zName: name.as_ptr(),
+            pAppData: ptr::null_mut(),
+            xOpen: Some(rust_open::<V>),
+            xDelete: Some(rust_delete::<V>),
+            xAccess: Some(rust_access::<V>),
+            xFullPathname: Some(rust_full_pathname::<V>),
+            xDlOpen: Some(rust_dl_open),
+            xDlError: Some(rust_dl_error),
+            xDlSym: Some(rust_dl_sym),
+            xDlClose: Some(rust_dl_close),
+            xRandomness: Some(rust_randomness::<V>),
+            xSleep: Some(rust_sleep::<V>),
+            xCurrentTime: Some(rust_current_time::<V>),
+            xGetLastError: Some(rust_get_last_error),
+            xCurrentTimeInt64: Some(rust_current_time_int64::<V>),
+            xSetSystemCall: None,
+            xGetSystemCall: None,
+            xNextSystemCall: None,
+        },
+        name,
+        vfs,
+    });
+    let state = Box::into_raw(state);
+    let rc = unsafe { ffi::sqlite3_vfs_register(state.cast(), c_int::from(make_default)) };
+    if rc != ffi::SQLITE_OK {
+        drop(unsafe { Box::from_raw(state) });
+    }
+    crate::error::check(rc)
+}
+
+/// Unregister the VFS named `name`.
+///
+/// The VFS itself is not deallocated as connections may still use it.
+pub fn unregister_vfs<N: Name>(name: N) -> Result<()> {
+    let name = name.as_cstr()?;
+    unsafe {
+        let vfs = ffi::sqlite3_vfs_find(name.as_ptr());
+        if vfs.is_null() {
+            return Err(Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_NOTFOUND),
+                Some(format!("no such vfs: {:?}", &*name)),
+            ));
+        }
+        crate::error::check(ffi::sqlite3_vfs_unregister(vfs))
+    }
+}
+
+/// VFS already registered into SQLite, like the default one of the
+/// platform.
+///
+/// Use it as the underlying layer of a shim [`Vfs`].
+#[derive(Debug)]
+pub struct SystemVfs(NonNull<ffi::sqlite3_vfs>);
+
+unsafe impl Send for SystemVfs {}
+unsafe impl Sync for SystemVfs {}
+
+impl SystemVfs {
+    /// Find the VFS named `name`, or the default one if `name` is `None`.
+    pub fn find<N: Name>(name: Option<N>) -> Result<Self> {
+        let name = name.as_ref().map(Name::as_cstr).transpose()?;
+        let vfs =
+            unsafe { ffi::sqlite3_vfs_find(name.as_ref().map_or(ptr::null(), |n| n.as_ptr())) };
+        NonNull::new(vfs).map(Self).ok_or_else(|| {
+            Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_NOTFOUND),
+                Some(format!("no such vfs: {:?}", name.as_deref())),
+            )
+        })
+    }
+
+    /// Name of the VFS
+    #[must_use]
+    pub fn name(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.0.as_ref().zName) }
+    }
+
+    fn base(&self) -> &ffi::sqlite3_vfs {
+        unsafe { self.0.as_ref() }
+    }
+
+    fn path(name: &str) -> Result<CString> {
+        Ok(CString::new(name)?)
+    }
+}
+
+impl Vfs for SystemVfs {
+    type File = SystemFile;
+
+    fn max_pathname(&self) -> c_int {
+        self.base().mxPathname
+    }
+
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<SystemFile> {
+        let vfs = self.base();
+        let words = (vfs.szOsFile as usize).div_ceil(size_of::<u64>()).max(1);
+        let mut file = SystemFile {
+            storage: vec![0; words].into_boxed_slice(),
+        };
+        let mut out_flags = 0;
+        let rc = unsafe {
+            (vfs.xOpen.unwrap())(
+                self.0.as_ptr(),
+                name.map_or(ptr::null(), |n| n.as_ptr()),
+                file.as_ptr(),
+                options.raw(),
+                &mut out_flags,
+            )
+        };
+        crate::error::check(rc)?;
+        if out_flags & ffi::SQLITE_OPEN_READONLY != 0 && !options.read_only() {
+            options.set_read_only();
+        }
+        Ok(file)
+    }
+
+    fn delete(&self, name: &str, sync_dir: bool) -> Result<()> {
+        let path = Self::path(name)?;
+        crate::error::check(unsafe {
+            (self.base().xDelete.unwrap())(self.0.as_ptr(), path.as_ptr(), c_int::from(sync_dir))
+        })
+    }
+
+    fn access(&self, name: &str, check: AccessCheck) -> Result<bool> {
+        let path = Self::path(name)?;
+        let mut res = 0;
+        crate::error::check(unsafe {
+            (self.base().xAccess.unwrap())(self.0.as_ptr(), path.as_ptr(), check as c_int, &mut res)
+        })?;
+        Ok(res != 0)
+    }
+
+    fn full_pathname(&self, name: &str) -> Result<String> {
+        let path = Self::path(name)?;
+        let mut out = vec![0 as c_char; self.max_pathname() as usize + 1];
+        crate::error::check(unsafe {
+            (self.base().xFullPathname.unwrap())(
+                self.0.as_ptr(),
+                path.as_ptr(),
+                out.len() as c_int,
+                out.as_mut_ptr(),
+            )
+        })?;
+        let full = unsafe { CStr::from_ptr(out.as_ptr()) };
+        Ok(full.to_str()?.to_owned())
+    }
+
+    fn randomness(&self, buf: &mut [u8]) -> usize {
+        match self.base().xRandomness {
+            Some(f) => unsafe {
+                f(self.0.as_ptr(), buf.len() as c_int, buf.as_mut_ptr().cast()) as usize
+            },
+            None => 0,
+        }
+    }
+
+    fn sleep(&self, duration: Duration) -> Duration {
+        match self.base().xSleep {
+            Some(f) => {
+                let micros = unsafe { f(self.0.as_ptr(), duration.as_micros() as c_int) };
+                Duration::from_micros(micros as u64)
+            }
+            None => duration,
+        }
+    }
+
+    fn current_time(&self) -> i64 {
+        let vfs = self.base();
+        if vfs.iVersion >= 2
+            && let Some(f) = vfs.xCurrentTimeInt64
+        {
+            let mut now = 0;
+            unsafe { f(self.0.as_ptr(), &mut now) };
+            return now;
+        }
+        let mut now = 0.0;
+        if let Some(f) = vfs.xCurrentTime {
+            unsafe { f(self.0.as_ptr(), &mut now) };
+        }
+        (now * 86_400_000.0) as i64
+    }
+}
+
+/// File opened by a [`SystemVfs`]
+pub struct SystemFile {
+    // `sqlite3_file` subclass of `szOsFile` bytes
+    storage: Box<[u64]>,
+}
+
+unsafe impl Send for SystemFile {}
+
+impl SystemFile {
+    fn as_ptr(&mut self) -> *mut ffi::sqlite3_file {
+        self.storage.as_mut_ptr().cast()
+    }
+
+    fn methods(&mut self) -> &ffi::sqlite3_io_methods {
+        unsafe { &*(*self.as_ptr()).pMethods }
+    }
+}
+
+macro_rules! call {
+    ($file:ident, $method:ident $(, $arg:expr)*) => {{
+        let p = $file.as_ptr();
+        match $file.methods().$method {
+            Some(f) => unsafe { f(p $(, $arg)*) },
+            None => ffi::SQLITE_MISUSE,
+        }
+    }};
+}
+
+impl VfsFile for SystemFile {
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let rc = call!(
+            self,
+            xRead,
+            buf.as_mut_ptr().cast(),
+            buf.len() as c_int,
+            offset as i64
+        );
+        if rc == ffi::SQLITE_IOERR_SHORT_READ {
+            let size = self.file_size()?;
+            return Ok((size.saturating_sub(offset) as usize).min(buf.len()));
+        }
+        crate::error::check(rc).map(|_| buf.len())
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        crate::error::check(call!(
+            self,
+            xWrite,
+            buf.as_ptr().cast(),
+            buf.len() as c_int,
+            offset as i64
+        ))
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        crate::error::check(call!(self, xTruncate, size as i64))
+    }
+
+    fn sync(&mut self, flags: SyncFlags) -> Result<()> {
+        crate::error::check(call!(self, xSync, flags.bits()))
+    }
+
+    fn file_size(&mut self) -> Result<u64> {
+        let mut size = 0;
+        crate::error::check(call!(self, xFileSize, &mut size))?;
+        Ok(size as u64)
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        crate::error::check(call!(self, xLock, level as c_int))
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        crate::error::check(call!(self, xUnlock, level as c_int))
+    }
+
+    fn check_reserved_lock(&mut self) -> Result<bool> {
+        let mut res = 0;
+        crate::error::check(call!(self, xCheckReservedLock, &mut res))?;
+        Ok(res != 0)
+    }
+
+    unsafe fn file_control(&mut self, op: c_int, arg: *mut c_void) -> Result<()> {
+        crate::error::check(call!(self, xFileControl, op, arg))
+    }
+
+    fn sector_size(&mut self) -> c_int {
+        call!(self, xSectorSize)
+    }
+
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        DeviceCharacteristics::from_bits_retain(call!(self, xDeviceCharacteristics))
+    }
+
+    fn supports_shm(&self) -> bool {
+        let methods = unsafe { &*(*self.storage.as_ptr().cast::<ffi::sqlite3_file>()).pMethods };
+        methods.iVersion >= 2 && methods.xShmMap.is_some()
+    }
+
+    fn shm_map(&mut self, region: usize, size: usize, extend: bool) -> Result<*mut u8> {
+        let mut p = ptr::null_mut();
+        crate::error::check(call!(
+            self,
+            xShmMap,
+            region as c_int,
+            size as c_int,
+            c_int::from(extend),
+            &mut p
+        ))?;
+        Ok(p.cast())
+    }
+
+    fn shm_lock(&mut self, offset: usize, n: usize, flags: ShmLockFlags) -> Result<()> {
+        crate::error::check(call!(
+            self,
+            xShmLock,
+            offset as c_int,
+            n as c_int,
+            flags.bits()
+        ))
+    }
+
+    fn shm_barrier(&mut self) {
+        let p = self.as_ptr();
+        if let Some(f) = self.methods().xShmBarrier {
+            unsafe { f(p) };
+        }
+    }
+
+    fn shm_unmap(&mut self, delete: bool) -> Result<()> {
+        crate::error::check(call!(self, xShmUnmap, c_int::from(delete)))
+    }
+}
+
+impl Drop for SystemFile {
+    fn drop(&mut self) {
+        let p = self.as_ptr();
+        unsafe {
+            if !(*p).pMethods.is_null()
+                && let Some(close) = (*(*p).pMethods).xClose
+            {
+                close(p);
+            }
+        }
+    }
+}
+
+// `sqlite3_vfs` subclass
+#[repr(C)]
+struct VfsState<V> {
+    base: ffi::sqlite3_vfs,
+    name: CString,
+    vfs: V,
+}
+
+// `sqlite3_file` subclass
+#[repr(C)]
+struct FileState<F> {
+    base: ffi::sqlite3_file,
+    file: *mut F,
+}
+
+struct IoMethods<F>(PhantomData<F>);
+
+impl<F: VfsFile> IoMethods<F> {
+    const V1: ffi::sqlite3_io_methods = ffi::sqlite3_io_methods {
+        iVersion: 1,
+        xClose: Some(rust_close::<F>),
+        xRead: Some(rust_read::<F>),
+        xWrite: Some(rust_write::<F>),
+        xTruncate: Some(rust_truncate::<F>),
+        xSync: Some(rust_sync::<F>),
+        xFileSize: Some(rust_file_size::<F>),
+        xLock: Some(rust_lock::<F>),
+        xUnlock: Some(rust_unlock::<F>),
+        xCheckReservedLock: Some(rust_check_reserved_lock::<F>),
+        xFileControl: Some(rust_file_control::<F>),
+        xSectorSize: Some(rust_sector_size::<F>),
+        xDeviceCharacteristics: Some(rust_device_characteristics::<F>),
+        xShmMap: None,
+        xShmLock: None,
+        xShmBarrier: None,
+        xShmUnmap: None,
+        xFetch: None,
+        xUnfetch: None,
+    };
+    const V2: ffi::sqlite3_io_methods = ffi::sqlite3_io_methods {
+        iVersion: 2,
+        xShmMap: Some(rust_shm_map::<F>),
+        xShmLock: Some(rust_shm_lock::<F>),
+        xShmBarrier: Some(rust_shm_barrier::<F>),
+        xShmUnmap: Some(rust_shm_unmap::<F>),
+        ..Self::V1
+    };
+}
+
+unsafe fn vfs_ref<'a, V>(vfs: *mut ffi::sqlite3_vfs) -> &'a V {
+    unsafe { &(*vfs.cast::<VfsState<V>>()).vfs }
+}
+
+unsafe fn file_mut<'a, F>(file: *mut ffi::sqlite3_file) -> &'a mut F {
+    unsafe { &mut *(*file.cast::<FileState<F>>()).file }
+}
+
+/// Run `f`, catching panics, and map its error to a SQLite result code
+/// (`default` when the error does not come from SQLite).
+fn guard<T>(default: c_int, f: impl FnOnce() -> Result<T>) -> Result<T, c_int> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(t)) => Ok(t),
+        Ok(Err(Error::SqliteFailure(err, _))) => Err(err.extended_code),
+        Ok(Err(_)) | Err(_) => Err(default),
+    }
+}
+
+fn result_code(r: Result<(), c_int>) -> c_int {
+    r.err().unwrap_or(ffi::SQLITE_OK)
+}
+
+unsafe fn c_str<'a>(s: *const c_char) -> Result<&'a str> {
+    Ok(unsafe { CStr::from_ptr(s) }.to_str()?)
+}
+
+unsafe extern "C" fn rust_open<V: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: *const c_char,
+    file: *mut ffi::sqlite3_file,
+    flags: c_int,
+    out_flags: *mut c_int,
+) -> c_int {
+    unsafe {
+        let state = file.cast::<FileState<V::File>>();
+        (*state).base.pMethods = ptr::null();
+        let vfs = vfs_ref::<V>(vfs);
+        let name = NonNull::new(name.cast_mut()).map(|p| FileName::from_ptr(p));
+        let mut options = OpenOptions::from_raw(flags);
+        match guard(ffi::SQLITE_CANTOPEN, || vfs.open(name, &mut options)) {
+            Ok(f) => {
+                let methods: &'static ffi::sqlite3_io_methods = if f.supports_shm() {
+                    &IoMethods::<V::File>::V2
+                } else {
+                    &IoMethods::<V::File>::V1
+                };
+                (*state).file = Box::into_raw(Box::new(f));
+                (*state).base.pMethods = methods;
+                if !out_flags.is_null() {
+                    *out_flags = options.raw();
+                }
+                ffi::SQLITE_OK
+            }
+            Err(rc) => rc,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_delete<V: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: *const c_char,
+    sync_dir: c_int,
+) -> c_int {
+    unsafe {
+        let vfs = vfs_ref::<V>(vfs);
+        result_code(guard(ffi::SQLITE_IOERR_DELETE, || {
+            vfs.delete(c_str(name)?, sync_dir != 0)
+        }))
+    }
+}
+
+unsafe extern "C" fn rust_access<V: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: *const c_char,
+    flags: c_int,
+    res_out: *mut c_int,
+) -> c_int {
+    unsafe {
+        let vfs = vfs_ref::<V>(vfs);
+        let check = match flags {
+            ffi::SQLITE_ACCESS_READWRITE => AccessCheck::ReadWrite,
+            ffi::SQLITE_ACCESS_READ => AccessCheck::Read,
+            _ => AccessCheck::Exists,
+        };
+        match guard(ffi::SQLITE_IOERR_ACCESS, || vfs.access(c_str(name)?, check)) {
+            Ok(b) => {
+                *res_out = c_int::from(b);
+                ffi::SQLITE_OK
+            }
+            Err(rc) => rc,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_full_pathname<V: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    name: *const c_char,
+    n_out: c_int,
+    z_out: *mut c_char,
+) -> c_int {
+    unsafe {
+        let vfs = vfs_ref::<V>(vfs);
+        match guard(ffi::SQLITE_CANTOPEN, || vfs.full_pathname(c_str(name)?)) {
+            Ok(path) if path.len() < n_out as usize => {
+                ptr::copy_nonoverlapping(path.as_ptr().cast(), z_out, path.len());
+                *z_out.add(path.len()) = 0;
+                ffi::SQLITE_OK
+            }
+            Ok(_) => ffi::SQLITE_CANTOPEN,
+            Err(rc) => rc,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_dl_open(_: *mut ffi::sqlite3_vfs, _: *const c_char) -> *mut c_void {
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn rust_dl_error(_: *mut ffi::sqlite3_vfs, n_byte: c_int, err_msg: *mut c_char) {
+    const MSG: &[u8] = b"Loadable extensions are not supported\0";
+    if n_byte > 0 {
+        let n = MSG.len().min(n_byte as usize);
+        unsafe {
+            ptr::copy_nonoverlapping(MSG.as_ptr().cast(), err_msg, n);
+            *err_msg.add(n - 1) = 0;
+        }
+    }
+}
+
+unsafe extern "C" fn rust_dl_sym(
+    _: *mut ffi::sqlite3_vfs,
+    _: *mut c_void,
+    _: *const c_char,
+) -> Option<unsafe extern "C" fn(*mut ffi::sqlite3_vfs, *mut c_void, *const c_char)> {
+    None
+}
+
+unsafe extern "C" fn rust_dl_close(_: *mut ffi::sqlite3_vfs, _: *mut c_void) {}
+
+unsafe extern "C" fn rust_randomness<V: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    n_byte: c_int,
+    out: *mut c_char,
+) -> c_int {
+    unsafe {
+        let vfs = vfs_ref::<V>(vfs);
+        let buf = slice::from_raw_parts_mut(out.cast::<u8>(), n_byte as usize);
+        catch_unwind(AssertUnwindSafe(|| vfs.randomness(buf))).map_or(0, |n| n as c_int)
+    }
+}
+
+unsafe extern "C" fn rust_sleep<V: Vfs>(vfs: *mut ffi::sqlite3_vfs, micros: c_int) -> c_int {
+    unsafe {
+        let vfs = vfs_ref::<V>(vfs);
+        let duration = Duration::from_micros(micros.max(0) as u64);
+        catch_unwind(AssertUnwindSafe(|| vfs.sleep(duration))).map_or(0, |d| d.as_micros() as c_int)
+    }
+}
+
+unsafe extern "C" fn rust_current_time<V: Vfs>(vfs: *mut ffi::sqlite3_vfs, out: *mut f64) -> c_int {
+    unsafe {
+        let mut now = 0;
+        let rc = rust_current_time_int64::<V>(vfs, &mut now);
+        *out = now as f64 / 86_400_000.0;
+        rc
+    }
+}
+
+unsafe extern "C" fn rust_current_time_int64<V: Vfs>(
+    vfs: *mut ffi::sqlite3_vfs,
+    out: *mut ffi::sqlite3_int64,
+) -> c_int {
+    unsafe {
+        let vfs = vfs_ref::<V>(vfs);
+        match catch_unwind(AssertUnwindSafe(|| vfs.current_time())) {
+            Ok(now) => {
+                *out = now;
+                ffi::SQLITE_OK
+            }
+            Err(_) => ffi::SQLITE_ERROR,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_get_last_error(
+    _: *mut ffi::sqlite3_vfs,
+    _: c_int,
+    _: *mut c_char,
+) -> c_int {
+    0
+}
+
+unsafe extern "C" fn rust_close<F: VfsFile>(file: *mut ffi::sqlite3_file) -> c_int {
+    unsafe {
+        let state = file.cast::<FileState<F>>();
+        let f = Box::from_raw((*state).file);
+        (*state).file = ptr::null_mut();
+        (*state).base.pMethods = ptr::null();
+        match catch_unwind(AssertUnwindSafe(|| drop(f))) {
+            Ok(()) => ffi::SQLITE_OK,
+            Err(_) => ffi::SQLITE_IOERR_CLOSE,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_read<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    buf: *mut c_void,
+    amt: c_int,
+    offset: ffi::sqlite3_int64,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        let buf = slice::from_raw_parts_mut(buf.cast::<u8>(), amt as usize);
+        match guard(ffi::SQLITE_IOERR_READ, || f.read(buf, offset as u64)) {
+            Ok(n) if n < buf.len() => {
+                buf[n..].fill(0);
+                ffi::SQLITE_IOERR_SHORT_READ
+            }
+            Ok(_) => ffi::SQLITE_OK,
+            Err(rc) => rc,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_write<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    buf: *const c_void,
+    amt: c_int,
+    offset: ffi::sqlite3_int64,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        let buf = slice::from_raw_parts(buf.cast::<u8>(), amt as usize);
+        result_code(guard(ffi::SQLITE_IOERR_WRITE, || {
+            f.write(buf, offset as u64)
+        }))
+    }
+}
+
+unsafe extern "C" fn rust_truncate<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    size: ffi::sqlite3_int64,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        result_code(guard(ffi::SQLITE_IOERR_TRUNCATE, || {
+            f.truncate(size as u64)
+        }))
+    }
+}
+
+unsafe extern "C" fn rust_sync<F: VfsFile>(file: *mut ffi::sqlite3_file, flags: c_int) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        let flags = SyncFlags::from_bits_retain(flags);
+        result_code(guard(ffi::SQLITE_IOERR_FSYNC, || f.sync(flags)))
+    }
+}
+
+unsafe extern "C" fn rust_file_size<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    size: *mut ffi::sqlite3_int64,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        match guard(ffi::SQLITE_IOERR_FSTAT, || f.file_size()) {
+            Ok(s) => {
+                *size = s as ffi::sqlite3_int64;
+                ffi::SQLITE_OK
+            }
+            Err(rc) => rc,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_lock<F: VfsFile>(file: *mut ffi::sqlite3_file, level: c_int) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        result_code(guard(ffi::SQLITE_IOERR_LOCK, || {
+            f.lock(LockLevel::from_raw(level))
+        }))
+    }
+}
+
+unsafe extern "C" fn rust_unlock<F: VfsFile>(file: *mut ffi::sqlite3_file, level: c_int) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        result_code(guard(ffi::SQLITE_IOERR_UNLOCK, || {
+            f.unlock(LockLevel::from_raw(level))
+        }))
+    }
+}
+
+unsafe extern "C" fn rust_check_reserved_lock<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    res_out: *mut c_int,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        match guard(ffi::SQLITE_IOERR_CHECKRESERVEDLOCK, || {
+            f.check_reserved_lock()
+        }) {
+            Ok(b) => {
+                *res_out = c_int::from(b);
+                ffi::SQLITE_OK
+            }
+            Err(rc) => rc,
+        }
+    }
+}
+
+unsafe extern "C" fn rust_file_control<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    op: c_int,
+    arg: *mut c_void,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        result_code(guard(ffi::SQLITE_ERROR, || f.file_control(op, arg)))
+    }
+}
+
+unsafe extern "C" fn rust_sector_size<F: VfsFile>(file: *mut ffi::sqlite3_file) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        catch_unwind(AssertUnwindSafe(|| f.sector_size())).unwrap_or(4096)
+    }
+}
+
+unsafe extern "C" fn rust_device_characteristics<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        catch_unwind(AssertUnwindSafe(|| f.device_characteristics().bits())).unwrap_or(0)
+    }
+}
+
+unsafe extern "C" fn rust_shm_map<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    region: c_int,
+    size: c_int,
+    extend: c_int,
+    pp: *mut *mut c_void,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        match guard(ffi::SQLITE_IOERR_SHMMAP, || {
+            f.shm_map(region as usize, size as usize, extend != 0)
+        }) {
+            Ok(p) => {
+                *pp = p.cast();
+                ffi::SQLITE_OK
+            }
+            Err(rc) => {
+                *pp = ptr::null_mut();
+                rc
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn rust_shm_lock<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    offset: c_int,
+    n: c_int,
+    flags: c_int,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        let flags = ShmLockFlags::from_bits_retain(flags);
+        result_code(guard(ffi::SQLITE_IOERR_SHMLOCK, || {
+            f.shm_lock(offset as usize, n as usize, flags)
+        }))
+    }
+}
+
+unsafe extern "C" fn rust_shm_barrier<F: VfsFile>(file: *mut ffi::sqlite3_file) {
+    unsafe {
+        let f = file_mut::<F>(file);
+        drop(catch_unwind(AssertUnwindSafe(|| f.shm_barrier())));
+    }
+}
+
+unsafe extern "C" fn rust_shm_unmap<F: VfsFile>(
+    file: *mut ffi::sqlite3_file,
+    delete: c_int,
+) -> c_int {
+    unsafe {
+        let f = file_mut::<F>(file);
+        result_code(guard(ffi::SQLITE_IOERR, || f.shm_unmap(delete != 0)))
+    }
+}
+
+#[cfg(feature = "fault_vfs")]
+pub mod fault;
+#[cfg(feature = "memvfs")]
+pub mod memvfs;
+#[cfg(feature = "readonly_vfs")]
+pub mod readonly;
+#[cfg(feature = "stats_vfs")]
+pub mod stats;
+#[cfg(feature = "transform_vfs")]
+pub mod transform;
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{FileName, OpenOptions, SystemFile, SystemVfs, Vfs, register_vfs};
+    use crate::{Connection, OpenFlags, Result};
+
+    struct Counting {
+        inner: SystemVfs,
+        opens: Arc<AtomicUsize>,
+    }
+
+    impl Vfs for Counting {
+        type File = SystemFile;
+
+        fn max_pathname(&self) -> std::ffi::c_int {
+            self.inner.max_pathname()
+        }
+
+        fn open(
+            &self,
+            name: Option<FileName<'_>>,
+            options: &mut OpenOptions,
+        ) -> Result<SystemFile> {
+            self.opens.fetch_add(1, Ordering::Relaxed);
+            self.inner.open(name, options)
+        }
+
+        fn delete(&self, name: &str, sync_dir: bool) -> Result<()> {
+            self.inner.delete(name, sync_dir)
+        }
+
+        fn access(&self, name: &str, check: super::AccessCheck) -> Result<bool> {
+            self.inner.access(name, check)
+        }
+
+        fn full_pathname(&self, name: &str) -> Result<String> {
+            self.inner.full_pathname(name)
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_pass_through() -> Result<()> {
+        let opens = Arc::new(AtomicUsize::new(0));
+        let vfs = Counting {
+            inner: SystemVfs::find(None::<&str>)?,
+            opens: opens.clone(),
+        };
+        register_vfs("test_pass_through", vfs, false)?;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test.db3");
+        let db =
+            Connection::open_with_flags_and_vfs(&path, OpenFlags::default(), "test_pass_through")?;
+        let mode: String =
+            db.pragma_update_and_check(None, "journal_mode", "wal", |row| row.get(0))?;
+        assert_eq!("wal", mode);
+        db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (42);")?;
+        drop(db);
+        assert!(opens.load(Ordering::Relaxed) >= 2); // main db + wal
+
+        let db = Connection::open(&path)?;
+        let x: i64 = db.one_column("SELECT x FROM foo", [])?;
+        assert_eq!(42, x);
+        Ok(())
+    }
+
+    struct Failing;
+
+    impl Vfs for Failing {
+        type File = SystemFile;
+
+        fn open(&self, _: Option<FileName<'_>>, _: &mut OpenOptions) -> Result<SystemFile> {
+            Err(crate::Error::SqliteFailure(
+                crate::ffi::Error::new(crate::ffi::SQLITE_PERM),
+                None,
+            ))
+        }
+
+        fn delete(&self, _: &str, _: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn access(&self, _: &str, _: super::AccessCheck) -> Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_open_error() -> Result<()> {
+        register_vfs("test_open_error", Failing, false)?;
+        let err =
+            Connection::open_with_flags_and_vfs("foo.db", OpenFlags::default(), "test_open_error")
+                .unwrap_err();
+        assert_eq!(
+            Some(crate::ErrorCode::PermissionDenied),
+            err.sqlite_error_code()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,409 @@
+//! Read-only VFS serving databases from memory.
+//!
+//! A database image can be a byte slice (for example, embedded with
+//! `include_bytes!`), a memory-mapped file, or a database appended to the end
+//! of another file (like an executable) in the format used by SQLite's
+//! [appendvfs](https://sqlite.org/src/file/ext/misc/appendvfs.c).
+//!
+//! Images are never copied and are shared by all connections, which do not
+//! need any locking. Databases in WAL mode are served as rollback-journal
+//! ones.
+//!
+//! ```rust
+//! use rusqlite::vfs::readonly::ReadOnlyVfs;
+//! use rusqlite::{Connection, OpenFlags, Result};
+//!
+//! fn open_reference(image: &'static [u8]) -> Result<Connection> {
+//!     let vfs = ReadOnlyVfs::register("reference")?;
+//!     vfs.add_static("reference.db", image);
+//!     Connection::open_with_flags_and_vfs("reference.db", OpenFlags::SQLITE_OPEN_READ_ONLY, vfs.name())
+//! }
+//! ```
+use std::collections::HashMap;
+use std::ffi::c_int;
+use std::fs::File;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use super::{
+    AccessCheck, DeviceCharacteristics, FileKind, FileName, LockLevel, OpenOptions, SyncFlags, Vfs,
+    VfsFile, register_vfs,
+};
+use crate::{Result, ffi};
+
+/// Trailer of an appended database: prefix followed by the big-endian offset
+/// of the database.
+const APPEND_MARK_PREFIX: &[u8] = b"Start-Of-SQLite3-";
+const APPEND_MARK_SIZE: usize = APPEND_MARK_PREFIX.len() + 8;
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+type Bytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+#[derive(Clone)]
+struct Image {
+    data: Bytes,
+    offset: usize,
+    len: usize,
+}
+
+impl Image {
+    fn as_slice(&self) -> &[u8] {
+        &(*self.data).as_ref()[self.offset..self.offset + self.len]
+    }
+}
+
+/// Read-only VFS serving database images from memory.
+///
+/// Cloning a `ReadOnlyVfs` gives another handle to the same set of images.
+#[derive(Clone)]
+pub struct ReadOnlyVfs {
+    name: Arc<str>,
+    images: Arc<RwLock<HashMap<String, Image>>>,
+}
+
+impl ReadOnlyVfs {
+    /// Create a new VFS, without any image, and register it under `name`.
+    pub fn register(name: &str) -> Result<Self> {
+        let vfs = Self {
+            name: name.into(),
+            images: Arc::default(),
+        };
+        register_vfs(name, vfs.clone(), false)?;
+        Ok(vfs)
+    }
+
+    /// Name under which this VFS is registered
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Serve `bytes` as the database `name`.
+    pub fn add_static(&self, name: &str, bytes: &'static [u8]) {
+        self.add_bytes(name, Arc::new(bytes));
+    }
+
+    /// Serve `bytes` as the database `name`.
+    ///
+    /// Any type which can be viewed as a byte slice can be used, for example,
+    /// a `Vec<u8>` or a `memmap2::Mmap`.
+    pub fn add_bytes(&self, name: &str, bytes: Bytes) {
+        let len = (*bytes).as_ref().len();
+        self.insert(
+            name,
+            Image {
+                data: bytes,
+                offset: 0,
+                len,
+            },
+        );
+    }
+
+    /// Memory-map the file at `path` and serve it as the database `name`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified while it is mapped (that is, until the
+    /// image is removed and all connections using it are closed).
+    pub unsafe fn add_mmap<P: AsRef<Path>>(&self, name: &str, path: P) -> Result<()> {
+        let map = unsafe { map(path.as_ref())? };
+        self.add_bytes(name, Arc::new(map));
+        Ok(())
+    }
+
+    /// Memory-map the file at `path` and serve the database appended to its
+    /// end as the database `name`.
+    ///
+    /// A file which is itself a database is served as-is.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified while it is mapped (that is, until the
+    /// image is removed and all connections using it are closed).
+    pub unsafe fn add_appended<P: AsRef<Path>>(&self, name: &str, path: P) -> Result<()> {
+        let map = unsafe { map(path.as_ref())? };
+        let (offset, len) = appended_range(&map).ok_or_else(|| {
+            err!(
+                ffi::SQLITE_NOTADB,
+                "no database appended to {}",
+                path.as_ref().display()
+            )
+        })?;
+        self.insert(
+            name,
+            Image {
+                data: Arc::new(map),
+                offset,
+                len,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop serving the database `name`.
+    ///
+    /// Connections which have the database open keep using its image.
+    pub fn remove(&self, name: &str) -> bool {
+        self.images.write().unwrap().remove(name).is_some()
+    }
+
+    fn insert(&self, name: &str, image: Image) {
+        self.images.write().unwrap().insert(name.to_owned(), image);
+    }
+}
+
+unsafe fn map(path: &Path) -> Result<memmap2::Mmap> {
+    let file =
+        File::open(path).map_err(|e| err!(ffi::SQLITE_CANTOPEN, "{}: {e}", path.display()))?;
+    unsafe { memmap2::Mmap::map(&file) }
+        .map_err(|e| err!(ffi::SQLITE_IOERR_MMAP, "{}: {e}", path.display()))
+}
+
+/// Location of the database inside `data`
+fn appended_range(data: &[u8]) -> Option<(usize, usize)> {
+    if data.starts_with(SQLITE_HEADER) {
+        return Some((0, data.len()));
+    }
+    let end = data.len().checked_sub(APPEND_MARK_SIZE)?;
+    let mark = &data[end..];
+    if !mark.starts_with(APPEND_MARK_PREFIX) {
+        return None;
+    }
+    let offset = u64::from_be_bytes(mark[APPEND_MARK_PREFIX.len()..].try_into().unwrap());
+    let offset = usize::try_from(offset).ok().filter(|&o| o <= end)?;
+    Some((offset, end - offset))
+}
+
+impl Vfs for ReadOnlyVfs {
+    type File = ReadOnlyFile;
+
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<ReadOnlyFile> {
+        let Some(name) = name else {
+            // temporary files used for sorting, materialization, ...
+            return Ok(ReadOnlyFile(Content::Temp(Vec::new())));
+        };
+        if options.kind() != FileKind::MainDb {
+            return Err(err!(ffi::SQLITE_CANTOPEN));
+        }
+        let image = self
+            .images
+            .read()
+            .unwrap()
+            .get(name.as_str()?)
+            .cloned()
+            .ok_or_else(|| err!(ffi::SQLITE_CANTOPEN))?;
+        if !options.read_only() {
+            options.set_read_only();
+        }
+        Ok(ReadOnlyFile(Content::Image(image)))
+    }
+
+    fn delete(&self, _: &str, _: bool) -> Result<()> {
+        Err(err!(ffi::SQLITE_IOERR_DELETE))
+    }
+
+    fn access(&self, name: &str, check: AccessCheck) -> Result<bool> {
+        Ok(check != AccessCheck::ReadWrite && self.images.read().unwrap().contains_key(name))
+    }
+}
+
+/// File opened by a [`ReadOnlyVfs`]
+pub struct ReadOnlyFile(Content);
+
+enum Content {
+    // Database image
+    Image(Image),
+    // Temporary file
+    Temp(Vec<u8>),
+}
+
+impl VfsFile for ReadOnlyFile {
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let data = match &self.0 {
+            Content::Image(image) => image.as_slice(),
+            Content::Temp(data) => data.as_slice(),
+        };
+        let Ok(start) = usize::try_from(offset) else {
+            return Ok(0);
+        };
+        let src = data.get(start..).unwrap_or_default();
+        let n = buf.len().min(src.len());
+        buf[..n].copy_from_slice(&src[..n]);
+        if let Content::Image(_) = self.0 {
+            // file format read / write versions: WAL (2) => legacy (1)
+            for i in 18_usize..20 {
+                if let Some(b) = i.checked_sub(start).and_then(|i| buf[..n].get_mut(i))
+                    && *b == 2
+                {
+                    *b = 1;
+                }
+            }
+        }
+        Ok(n)
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        match &mut self.0 {
+            Content::Image(_) => Err(err!(ffi::SQLITE_READONLY)),
+            Content::Temp(data) => {
+                let start = offset as usize;
+                let end = start + buf.len();
+                if data.len() < end {
+                    data.resize(end, 0);
+                }
+                data[start..end].copy_from_slice(buf);
+                Ok(())
+            }
+        }
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        match &mut self.0 {
+            Content::Image(_) => Err(err!(ffi::SQLITE_READONLY)),
+            Content::Temp(data) => {
+                data.resize(size as usize, 0);
+                Ok(())
+            }
+        }
+    }
+
+    fn sync(&mut self, _: SyncFlags) -> Result<()> {
+        Ok(())
+    }
+
+    fn file_size(&mut self) -> Result<u64> {
+        Ok(match &mut self.0 {
+            Content::Image(image) => image.len,
+            Content::Temp(data) => data.len(),
+        } as u64)
+    }
+
+    fn lock(&mut self, _: LockLevel) -> Result<()> {
+        Ok(())
+    }
+
+    fn unlock(&mut self, _: LockLevel) -> Result<()> {
+        Ok(())
+    }
+
+    fn check_reserved_lock(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn sector_size(&mut self) -> c_int {
+        512
+    }
+
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        match &mut self.0 {
+            Content::Image(_) => DeviceCharacteristics::SQLITE_IOCAP_IMMUTABLE,
+            Content::Temp(_) => DeviceCharacteristics::empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::io::Write as _;
+    use std::sync::Arc;
+
+    use super::{APPEND_MARK_PREFIX, ReadOnlyVfs, appended_range};
+    use crate::{Connection, ErrorCode, OpenFlags, Result};
+
+    fn image(journal_mode: &str) -> Result<Vec<u8>> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("image.db3");
+        let db = Connection::open(&path)?;
+        db.execute_batch(&format!(
+            "PRAGMA journal_mode = {journal_mode};
+             CREATE TABLE foo(x);
+             INSERT INTO foo VALUES (3), (1), (2);"
+        ))?;
+        db.close().map_err(|(_, e)| e)?;
+        Ok(std::fs::read(&path).unwrap())
+    }
+
+    #[test]
+    fn test_appended_range() {
+        assert_eq!(None, appended_range(b"foo"));
+        assert_eq!(Some((0, 16)), appended_range(b"SQLite format 3\0"));
+        let mut data = b"exe|db".to_vec();
+        data.extend_from_slice(APPEND_MARK_PREFIX);
+        data.extend_from_slice(&4u64.to_be_bytes());
+        assert_eq!(Some((4, 2)), appended_range(&data));
+    }
+
+    #[test]
+    fn test_bytes() -> Result<()> {
+        let vfs = ReadOnlyVfs::register("test_readonly_bytes")?;
+        vfs.add_bytes("ref.db", Arc::new(image("delete")?));
+
+        let db1 = Connection::open_with_flags_and_vfs(
+            "ref.db",
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+            vfs.name(),
+        )?;
+        let db2 = Connection::open_with_flags_and_vfs("ref.db", OpenFlags::default(), vfs.name())?;
+        let mut stmt = db1.prepare("SELECT x FROM foo ORDER BY x")?;
+        let rows = stmt
+            .query_map([], |r| r.get(0))?
+            .collect::<Result<Vec<i64>>>()?;
+        assert_eq!(vec![1, 2, 3], rows);
+        let n: i64 = db2.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(3, n);
+
+        let err = db2.execute("INSERT INTO foo VALUES (4)", []).unwrap_err();
+        assert_eq!(Some(ErrorCode::ReadOnly), err.sqlite_error_code());
+
+        Connection::open_with_flags_and_vfs(
+            "unknown.db",
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+            vfs.name(),
+        )
+        .unwrap_err();
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_image() -> Result<()> {
+        let vfs = ReadOnlyVfs::register("test_readonly_wal")?;
+        vfs.add_bytes("wal.db", Arc::new(image("wal")?));
+        let db = Connection::open_with_flags_and_vfs(
+            "wal.db",
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+            vfs.name(),
+        )?;
+        let n: i64 = db.one_column("SELECT count(*) FROM foo", [])?;
+        assert_eq!(3, n);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_appended() -> Result<()> {
+        let vfs = ReadOnlyVfs::register("test_readonly_appended")?;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let prefix = b"\x7fELF not really an executable";
+        file.write_all(prefix).unwrap();
+        file.write_all(&image("delete")?).unwrap();
+        file.write_all(APPEND_MARK_PREFIX).unwrap();
+        file.write_all(&(prefix.len() as u64).to_be_bytes())
+            .unwrap();
+        file.flush().unwrap();
+
+        unsafe { vfs.add_appended("appended.db", file.path())? };
+        let db = Connection::open_with_flags_and_vfs(
+            "appended.db",
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+            vfs.name(),
+        )?;
+        let n: i64 = db.one_column("SELECT sum(x) FROM foo", [])?;
+        assert_eq!(6, n);
+
+        unsafe { vfs.add_appended("not_appended.db", file.path().parent().unwrap()) }.unwrap_err();
+        Ok(())
+    }
+}
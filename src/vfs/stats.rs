@@ -0,0 +1,423 @@
+//! I/O statistics VFS shim.
+//!
+//! [`StatsVfs`] wraps another VFS and counts reads, writes and syncs, split
+//! by kind of file. Counters are kept for the whole VFS ([`Stats`]) and for
+//! each connection ([`Connection::io_stats`]).
+//!
+//! ```rust,no_run
+//! use rusqlite::vfs::stats::StatsVfs;
+//! use rusqlite::{Connection, OpenFlags, Result};
+//!
+//! fn main() -> Result<()> {
+//!     let stats = StatsVfs::register("stats")?;
+//!     let db = Connection::open_with_flags_and_vfs("stats.db", OpenFlags::default(), "stats")?;
+//!     db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (1);")?;
+//!     let per_connection = db.io_stats()?;
+//!     let total = stats.get();
+//!     assert!(total.main_db.bytes_written >= per_connection.main_db.bytes_written);
+//!     Ok(())
+//! }
+//! ```
+use std::collections::HashMap;
+use std::ffi::{c_int, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use super::{
+    AccessCheck, DeviceCharacteristics, FileKind, FileName, LockLevel, OpenOptions, ShmLockFlags,
+    SyncFlags, SystemVfs, Vfs, VfsFile, register_vfs,
+};
+use crate::{Connection, MAIN_DB, Result, ffi};
+
+const FCNTL_STATS_VFS: c_int = 0x7374_6174; // 'stat'
+
+/// I/O counters for one kind of file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FileStats {
+    /// Number of reads
+    pub reads: u64,
+    /// Number of bytes read
+    pub bytes_read: u64,
+    /// Number of writes
+    pub writes: u64,
+    /// Number of bytes written
+    pub bytes_written: u64,
+    /// Number of syncs
+    pub syncs: u64,
+    /// Time spent syncing
+    pub sync_time: Duration,
+}
+
+/// I/O counters split by kind of file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IoStats {
+    /// Main database files
+    pub main_db: FileStats,
+    /// Rollback and super-journals
+    pub journal: FileStats,
+    /// WAL files
+    pub wal: FileStats,
+    /// Temporary databases, temporary journals and sub-journals
+    pub temp: FileStats,
+}
+
+#[derive(Default)]
+struct Counters {
+    reads: AtomicU64,
+    bytes_read: AtomicU64,
+    writes: AtomicU64,
+    bytes_written: AtomicU64,
+    syncs: AtomicU64,
+    sync_nanos: AtomicU64,
+}
+
+impl Counters {
+    fn get(&self, reset: bool) -> FileStats {
+        let load = |c: &AtomicU64| {
+            if reset {
+                c.swap(0, Ordering::Relaxed)
+            } else {
+                c.load(Ordering::Relaxed)
+            }
+        };
+        FileStats {
+            reads: load(&self.reads),
+            bytes_read: load(&self.bytes_read),
+            writes: load(&self.writes),
+            bytes_written: load(&self.bytes_written),
+            syncs: load(&self.syncs),
+            sync_time: Duration::from_nanos(load(&self.sync_nanos)),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Category {
+    MainDb,
+    Journal,
+    Wal,
+    Temp,
+}
+
+impl From<FileKind> for Category {
+    fn from(kind: FileKind) -> Self {
+        match kind {
+            FileKind::MainDb => Self::MainDb,
+            FileKind::MainJournal | FileKind::SuperJournal => Self::Journal,
+            FileKind::Wal => Self::Wal,
+            _ => Self::Temp,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Group([Counters; 4]);
+
+impl Group {
+    fn get(&self, reset: bool) -> IoStats {
+        IoStats {
+            main_db: self.0[Category::MainDb as usize].get(reset),
+            journal: self.0[Category::Journal as usize].get(reset),
+            wal: self.0[Category::Wal as usize].get(reset),
+            temp: self.0[Category::Temp as usize].get(reset),
+        }
+    }
+}
+
+/// Handle to the counters of a [`StatsVfs`], for all connections
+#[derive(Clone, Default)]
+pub struct Stats(Arc<Group>);
+
+impl Stats {
+    /// Current values of the counters
+    #[must_use]
+    pub fn get(&self) -> IoStats {
+        self.0.get(false)
+    }
+
+    /// Reset the counters, returning the values they had before resetting.
+    pub fn reset(&self) -> IoStats {
+        self.0.get(true)
+    }
+}
+
+/// VFS shim collecting I/O statistics.
+pub struct StatsVfs<V = SystemVfs> {
+    inner: V,
+    stats: Stats,
+    // per-connection counters, by address of the main database file name
+    // owned by the pager, which is shared by its journal and WAL
+    connections: Mutex<HashMap<usize, Weak<Group>>>,
+}
+
+impl StatsVfs {
+    /// Wrap the default VFS, register the shim under `name` and return the
+    /// handle to its counters.
+    pub fn register(name: &str) -> Result<Stats> {
+        let vfs = Self::new(SystemVfs::find(None::<&str>)?);
+        let stats = vfs.stats();
+        register_vfs(name, vfs, false)?;
+        Ok(stats)
+    }
+}
+
+impl<V: Vfs> StatsVfs<V> {
+    /// Wrap `inner`.
+    #[must_use]
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            stats: Stats::default(),
+            connections: Mutex::default(),
+        }
+    }
+
+    /// Handle to the counters of this VFS once registered
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        self.stats.clone()
+    }
+
+    fn connection(&self, name: Option<&FileName<'_>>, kind: FileKind) -> Option<Arc<Group>> {
+        let key = name?.database().as_ptr() as usize;
+        let mut connections = self.connections.lock().unwrap();
+        connections.retain(|_, v| v.strong_count() > 0);
+        if kind == FileKind::MainDb {
+            let group = Arc::<Group>::default();
+            connections.insert(key, Arc::downgrade(&group));
+            Some(group)
+        } else {
+            connections.get(&key).and_then(Weak::upgrade)
+        }
+    }
+}
+
+impl<V: Vfs> Vfs for StatsVfs<V> {
+    type File = StatsFile<V::File>;
+
+    fn max_pathname(&self) -> c_int {
+        self.inner.max_pathname()
+    }
+
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<Self::File> {
+        let kind = options.kind();
+        let connection = self.connection(name.as_ref(), kind);
+        Ok(StatsFile {
+            inner: self.inner.open(name, options)?,
+            category: kind.into(),
+            vfs: self.stats.0.clone(),
+            connection,
+        })
+    }
+
+    fn delete(&self, name: &str, sync_dir: bool) -> Result<()> {
+        self.inner.delete(name, sync_dir)
+    }
+
+    fn access(&self, name: &str, check: AccessCheck) -> Result<bool> {
+        self.inner.access(name, check)
+    }
+
+    fn full_pathname(&self, name: &str) -> Result<String> {
+        self.inner.full_pathname(name)
+    }
+
+    fn randomness(&self, buf: &mut [u8]) -> usize {
+        self.inner.randomness(buf)
+    }
+
+    fn sleep(&self, duration: Duration) -> Duration {
+        self.inner.sleep(duration)
+    }
+
+    fn current_time(&self) -> i64 {
+        self.inner.current_time()
+    }
+}
+
+/// File opened by a [`StatsVfs`]
+pub struct StatsFile<F> {
+    inner: F,
+    category: Category,
+    vfs: Arc<Group>,
+    connection: Option<Arc<Group>>,
+}
+
+impl<F> StatsFile<F> {
+    fn count(&self, f: impl Fn(&Counters)) {
+        f(&self.vfs.0[self.category as usize]);
+        if let Some(ref connection) = self.connection {
+            f(&connection.0[self.category as usize]);
+        }
+    }
+}
+
+impl<F: VfsFile> VfsFile for StatsFile<F> {
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let n = self.inner.read(buf, offset)?;
+        self.count(|c| {
+            c.reads.fetch_add(1, Ordering::Relaxed);
+            c.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+        });
+        Ok(n)
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        self.inner.write(buf, offset)?;
+        self.count(|c| {
+            c.writes.fetch_add(1, Ordering::Relaxed);
+            c.bytes_written
+                .fetch_add(buf.len() as u64, Ordering::Relaxed);
+        });
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.inner.truncate(size)
+    }
+
+    fn sync(&mut self, flags: SyncFlags) -> Result<()> {
+        let start = Instant::now();
+        let r = self.inner.sync(flags);
+        let nanos = start.elapsed().as_nanos() as u64;
+        self.count(|c| {
+            c.syncs.fetch_add(1, Ordering::Relaxed);
+            c.sync_nanos.fetch_add(nanos, Ordering::Relaxed);
+        });
+        r
+    }
+
+    fn file_size(&mut self) -> Result<u64> {
+        self.inner.file_size()
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.lock(level)
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    fn check_reserved_lock(&mut self) -> Result<bool> {
+        self.inner.check_reserved_lock()
+    }
+
+    unsafe fn file_control(&mut self, op: c_int, arg: *mut c_void) -> Result<()> {
+        match (op, &self.connection) {
+            (FCNTL_STATS_VFS, Some(connection)) => {
+                unsafe { *arg.cast::<Option<Arc<Group>>>() = Some(connection.clone()) };
+                Ok(())
+            }
+            _ => unsafe { self.inner.file_control(op, arg) },
+        }
+    }
+
+    fn sector_size(&mut self) -> c_int {
+        self.inner.sector_size()
+    }
+
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        self.inner.device_characteristics()
+    }
+
+    fn supports_shm(&self) -> bool {
+        self.inner.supports_shm()
+    }
+
+    fn shm_map(&mut self, region: usize, size: usize, extend: bool) -> Result<*mut u8> {
+        self.inner.shm_map(region, size, extend)
+    }
+
+    fn shm_lock(&mut self, offset: usize, n: usize, flags: ShmLockFlags) -> Result<()> {
+        self.inner.shm_lock(offset, n, flags)
+    }
+
+    fn shm_barrier(&mut self) {
+        self.inner.shm_barrier();
+    }
+
+    fn shm_unmap(&mut self, delete: bool) -> Result<()> {
+        self.inner.shm_unmap(delete)
+    }
+}
+
+impl Connection {
+    fn io_stats_group(&self) -> Result<Arc<Group>> {
+        let mut group: Option<Arc<Group>> = None;
+        unsafe {
+            self.db
+                .borrow()
+                .file_control(Some(MAIN_DB), FCNTL_STATS_VFS, (&raw mut group).cast())
+        }
+        .ok()
+        .and(group)
+        .ok_or_else(|| err!(ffi::SQLITE_MISUSE, "database not opened with a StatsVfs"))
+    }
+
+    /// Get the I/O counters of this connection, which must have been opened
+    /// with a [`StatsVfs`].
+    ///
+    /// Temporary files are opened without a name and cannot be attributed to
+    /// a connection: they are only counted by [`Stats`].
+    pub fn io_stats(&self) -> Result<IoStats> {
+        Ok(self.io_stats_group()?.get(false))
+    }
+
+    /// Reset the I/O counters of this connection, returning the values they
+    /// had before resetting.
+    pub fn reset_io_stats(&self) -> Result<IoStats> {
+        Ok(self.io_stats_group()?.get(true))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::{IoStats, StatsVfs};
+    use crate::{Connection, OpenFlags, Result};
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_io_stats() -> Result<()> {
+        let stats = StatsVfs::register("test_io_stats")?;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("stats.db3");
+        let db1 =
+            Connection::open_with_flags_and_vfs(&path, OpenFlags::default(), "test_io_stats")?;
+        db1.pragma_update(None, "journal_mode", "wal")?;
+        db1.pragma_update(None, "synchronous", "FULL")?;
+        db1.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES (zeroblob(10000));")?;
+
+        let io1 = db1.io_stats()?;
+        assert!(io1.main_db.reads > 0);
+        assert!(io1.wal.bytes_written >= 10000);
+        assert!(io1.wal.syncs > 0);
+        assert_eq!(0, io1.temp.writes);
+
+        let db2 =
+            Connection::open_with_flags_and_vfs(&path, OpenFlags::default(), "test_io_stats")?;
+        let x: Vec<u8> = db2.one_column("SELECT x FROM foo", [])?;
+        assert_eq!(10000, x.len());
+        let io2 = db2.io_stats()?;
+        assert!(io2.wal.bytes_read >= 10000);
+        assert_eq!(0, io2.wal.bytes_written);
+        assert_eq!(io1, db1.io_stats()?);
+
+        let total = stats.get();
+        assert!(total.wal.bytes_written >= io1.wal.bytes_written);
+        assert!(total.wal.bytes_read >= io2.wal.bytes_read);
+
+        assert_eq!(io2, db2.reset_io_stats()?);
+        assert_eq!(IoStats::default(), db2.io_stats()?);
+
+        let db3 = Connection::open_in_memory()?;
+        db3.io_stats().unwrap_err();
+        Ok(())
+    }
+}
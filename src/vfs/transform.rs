@@ -0,0 +1,467 @@
+//! Page-transform VFS shim.
+//!
+//! [`TransformVfs`] wraps another VFS and runs a [`PageTransform`] on every
+//! page written to or read from main database and WAL files. The transform
+//! can use the reserved bytes at the end of each page (see
+//! [`FileControl::ReserveBytes`](crate::FileControl::ReserveBytes)) to store
+//! a checksum, a nonce or a MAC, like SQLite's
+//! [cksumvfs](https://sqlite.org/cksumvfs.html).
+//!
+//! Pages are only transformed when the number of reserved bytes recorded in
+//! the database header matches [`PageTransform::reserve_bytes`]: it must be
+//! set on a new database before anything is written to it, or followed by a
+//! `VACUUM` on an existing database.
+//! Rollback journals and temporary files are not transformed.
+//!
+//! ```rust,no_run
+//! use rusqlite::vfs::transform::{PageTransform, TransformVfs};
+//! use rusqlite::{Connection, FileControl, MAIN_DB, OpenFlags, Result};
+//!
+//! struct Xor;
+//!
+//! impl PageTransform for Xor {
+//!     fn reserve_bytes(&self) -> u8 {
+//!         0
+//!     }
+//!
+//!     fn encode(&self, page_no: u32, page: &mut [u8]) -> Result<()> {
+//!         let start = if page_no == 1 { 100 } else { 0 };
+//!         page[start..].iter_mut().for_each(|b| *b ^= 0x5a);
+//!         Ok(())
+//!     }
+//!
+//!     fn decode(&self, page_no: u32, page: &mut [u8]) -> Result<()> {
+//!         self.encode(page_no, page)
+//!     }
+//! }
+//!
+//! fn main() -> Result<()> {
+//!     TransformVfs::register("xor", Xor)?;
+//!     let db = Connection::open_with_flags_and_vfs("xor.db", OpenFlags::default(), "xor")?;
+//!     let mut n = Xor.reserve_bytes().into();
+//!     db.file_control(Some(MAIN_DB), FileControl::ReserveBytes(&mut n))?;
+//!     db.execute_batch("CREATE TABLE IF NOT EXISTS foo(x);")?;
+//!     Ok(())
+//! }
+//! ```
+use std::collections::HashMap;
+use std::ffi::{CString, c_int, c_void};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use super::{
+    AccessCheck, DeviceCharacteristics, FileKind, FileName, LockLevel, OpenOptions, ShmLockFlags,
+    SyncFlags, SystemVfs, Vfs, VfsFile, register_vfs,
+};
+use crate::Result;
+
+// Offset of the number of reserved bytes in the database header
+const RESERVE_OFFSET: usize = 20;
+const WAL_HEADER_SIZE: u64 = 32;
+const WAL_FRAME_HEADER_SIZE: u64 = 24;
+
+/// Transformation applied to database pages
+///
+/// `page` is a whole page, including the reserved bytes at its end. The first
+/// 100 bytes of page 1 (the database header) must be left unchanged, so that
+/// SQLite can read the page size and the number of reserved bytes. Pages are
+/// transformed in place so `encode` and `decode` must preserve their size.
+pub trait PageTransform: Send + Sync + 'static {
+    /// Number of bytes the transform uses at the end of each page
+    fn reserve_bytes(&self) -> u8;
+    /// Transform a page before it is written.
+    fn encode(&self, page_no: u32, page: &mut [u8]) -> Result<()>;
+    /// Reverse [`encode`](PageTransform::encode) after a page has been read.
+    ///
+    /// Should fail with `SQLITE_IOERR_DATA` if the page is found to be corrupt.
+    fn decode(&self, page_no: u32, page: &mut [u8]) -> Result<()>;
+}
+
+/// VFS shim transforming database and WAL pages.
+pub struct TransformVfs<T, V = SystemVfs> {
+    inner: V,
+    transform: Arc<T>,
+    // by main database file name
+    databases: Mutex<HashMap<CString, Weak<Database>>>,
+}
+
+impl<T: PageTransform> TransformVfs<T> {
+    /// Wrap the default VFS and register the shim under `name`.
+    pub fn register(name: &str, transform: T) -> Result<()> {
+        register_vfs(
+            name,
+            Self::new(SystemVfs::find(None::<&str>)?, transform),
+            false,
+        )
+    }
+}
+
+impl<T: PageTransform, V: Vfs> TransformVfs<T, V> {
+    /// Wrap `inner`.
+    #[must_use]
+    pub fn new(inner: V, transform: T) -> Self {
+        Self {
+            inner,
+            transform: Arc::new(transform),
+            databases: Mutex::default(),
+        }
+    }
+
+    fn database(&self, name: Option<&FileName<'_>>) -> Arc<Database> {
+        let Some(name) = name else {
+            return Arc::default();
+        };
+        let mut databases = self.databases.lock().unwrap();
+        databases.retain(|_, v| v.strong_count() > 0);
+        if let Some(database) = databases.get(name.database()).and_then(Weak::upgrade) {
+            return database;
+        }
+        let database = Arc::<Database>::default();
+        databases.insert(name.database().to_owned(), Arc::downgrade(&database));
+        database
+    }
+}
+
+impl<T: PageTransform, V: Vfs> Vfs for TransformVfs<T, V> {
+    type File = TransformFile<T, V::File>;
+
+    fn max_pathname(&self) -> c_int {
+        self.inner.max_pathname()
+    }
+
+    fn open(&self, name: Option<FileName<'_>>, options: &mut OpenOptions) -> Result<Self::File> {
+        let kind = match options.kind() {
+            FileKind::MainDb => Kind::Db,
+            FileKind::Wal => Kind::Wal,
+            _ => Kind::Other,
+        };
+        let database = match kind {
+            Kind::Other => Arc::default(),
+            _ => self.database(name.as_ref()),
+        };
+        Ok(TransformFile {
+            inner: self.inner.open(name, options)?,
+            transform: self.transform.clone(),
+            kind,
+            database,
+        })
+    }
+
+    fn delete(&self, name: &str, sync_dir: bool) -> Result<()> {
+        self.inner.delete(name, sync_dir)
+    }
+
+    fn access(&self, name: &str, check: AccessCheck) -> Result<bool> {
+        self.inner.access(name, check)
+    }
+
+    fn full_pathname(&self, name: &str) -> Result<String> {
+        self.inner.full_pathname(name)
+    }
+
+    fn randomness(&self, buf: &mut [u8]) -> usize {
+        self.inner.randomness(buf)
+    }
+
+    fn sleep(&self, duration: Duration) -> Duration {
+        self.inner.sleep(duration)
+    }
+
+    fn current_time(&self) -> i64 {
+        self.inner.current_time()
+    }
+}
+
+enum Kind {
+    Db,
+    Wal,
+    Other,
+}
+
+/// File opened by a [`TransformVfs`]
+pub struct TransformFile<T, F> {
+    inner: F,
+    transform: Arc<T>,
+    kind: Kind,
+    // shared by a main database file and its WAL
+    database: Arc<Database>,
+}
+
+/// What is known about a database from its header
+#[derive(Default)]
+struct Database {
+    // whether pages are transformed
+    enabled: AtomicBool,
+    page_size: AtomicU64,
+}
+
+impl<T: PageTransform, F: VfsFile> TransformFile<T, F> {
+    /// Check the page size and the number of reserved bytes when the
+    /// database header goes through.
+    fn check_header(&self, buf: &[u8], offset: u64) {
+        if matches!(self.kind, Kind::Db) && offset == 0 && buf.len() > RESERVE_OFFSET {
+            let enabled = buf[RESERVE_OFFSET] == self.transform.reserve_bytes();
+            self.database.enabled.store(enabled, Ordering::Relaxed);
+            let page_size = match u16::from_be_bytes([buf[16], buf[17]]) {
+                1 => 65536,
+                n => u64::from(n),
+            };
+            self.database.page_size.store(page_size, Ordering::Relaxed);
+        }
+    }
+
+    /// Locate the page in `buf`, if any: returns the page number and the
+    /// offset of the page in `buf`.
+    fn page(&mut self, buf: &[u8], offset: u64) -> Result<Option<(u32, usize)>> {
+        let len = buf.len() as u64;
+        match self.kind {
+            Kind::Other => Ok(None),
+            _ if !self.database.enabled.load(Ordering::Relaxed) => Ok(None),
+            Kind::Db => {
+                if len >= 512 && len.is_power_of_two() && offset.is_multiple_of(len) {
+                    Ok(Some(((offset / len + 1) as u32, 0)))
+                } else {
+                    Ok(None)
+                }
+            }
+            Kind::Wal => {
+                let page_size = self.database.page_size.load(Ordering::Relaxed);
+                let frame_size = page_size + WAL_FRAME_HEADER_SIZE;
+                if offset < WAL_HEADER_SIZE {
+                    return Ok(None);
+                }
+                let frame_offset = (offset - WAL_HEADER_SIZE) % frame_size;
+                if len == frame_size && frame_offset == 0 {
+                    // whole frame: header followed by page
+                    let page_no = u32::from_be_bytes(buf[..4].try_into().unwrap());
+                    Ok(Some((page_no, WAL_FRAME_HEADER_SIZE as usize)))
+                } else if len == page_size && frame_offset == WAL_FRAME_HEADER_SIZE {
+                    // page only: the frame header precedes it in the file
+                    let mut header = [0; 4];
+                    if self
+                        .inner
+                        .read(&mut header, offset - WAL_FRAME_HEADER_SIZE)?
+                        < 4
+                    {
+                        return Ok(None);
+                    }
+                    Ok(Some((u32::from_be_bytes(header), 0)))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+impl<T: PageTransform, F: VfsFile> VfsFile for TransformFile<T, F> {
+    fn read(&mut self, buf: &mut [u8], offset: u64) -> Result<usize> {
+        let n = self.inner.read(buf, offset)?;
+        if n < buf.len() {
+            return Ok(n);
+        }
+        self.check_header(buf, offset);
+        if let Some((page_no, start)) = self.page(buf, offset)? {
+            self.transform.decode(page_no, &mut buf[start..])?;
+        }
+        Ok(n)
+    }
+
+    fn write(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        self.check_header(buf, offset);
+        match self.page(buf, offset)? {
+            Some((page_no, start)) => {
+                let mut page = buf.to_vec();
+                self.transform.encode(page_no, &mut page[start..])?;
+                self.inner.write(&page, offset)
+            }
+            None => self.inner.write(buf, offset),
+        }
+    }
+
+    fn truncate(&mut self, size: u64) -> Result<()> {
+        self.inner.truncate(size)
+    }
+
+    fn sync(&mut self, flags: SyncFlags) -> Result<()> {
+        self.inner.sync(flags)
+    }
+
+    fn file_size(&mut self) -> Result<u64> {
+        self.inner.file_size()
+    }
+
+    fn lock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.lock(level)
+    }
+
+    fn unlock(&mut self, level: LockLevel) -> Result<()> {
+        self.inner.unlock(level)
+    }
+
+    fn check_reserved_lock(&mut self) -> Result<bool> {
+        self.inner.check_reserved_lock()
+    }
+
+    unsafe fn file_control(&mut self, op: c_int, arg: *mut c_void) -> Result<()> {
+        unsafe { self.inner.file_control(op, arg) }
+    }
+
+    fn sector_size(&mut self) -> c_int {
+        self.inner.sector_size()
+    }
+
+    fn device_characteristics(&mut self) -> DeviceCharacteristics {
+        self.inner.device_characteristics()
+    }
+
+    fn supports_shm(&self) -> bool {
+        self.inner.supports_shm()
+    }
+
+    fn shm_map(&mut self, region: usize, size: usize, extend: bool) -> Result<*mut u8> {
+        self.inner.shm_map(region, size, extend)
+    }
+
+    fn shm_lock(&mut self, offset: usize, n: usize, flags: ShmLockFlags) -> Result<()> {
+        self.inner.shm_lock(offset, n, flags)
+    }
+
+    fn shm_barrier(&mut self) {
+        self.inner.shm_barrier();
+    }
+
+    fn shm_unmap(&mut self, delete: bool) -> Result<()> {
+        self.inner.shm_unmap(delete)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::fs;
+    use std::path::Path;
+
+    use super::{PageTransform, TransformVfs};
+    use crate::{Connection, FileControl, MAIN_DB, OpenFlags, Result, ffi};
+
+    /// XOR cipher followed by a checksum in the reserved bytes
+    struct Checksum;
+
+    impl Checksum {
+        fn checksum(page: &[u8]) -> [u8; 4] {
+            page.iter()
+                .fold(0u32, |sum, b| sum.rotate_left(5) ^ u32::from(*b))
+                .to_le_bytes()
+        }
+    }
+
+    impl PageTransform for Checksum {
+        fn reserve_bytes(&self) -> u8 {
+            4
+        }
+
+        fn encode(&self, page_no: u32, page: &mut [u8]) -> Result<()> {
+            let (data, reserved) = page.split_at_mut(page.len() - 4);
+            let start = if page_no == 1 { 100 } else { 0 };
+            data[start..]
+                .iter_mut()
+                .for_each(|b| *b ^= page_no as u8 | 0x80);
+            reserved.copy_from_slice(&Self::checksum(data));
+            Ok(())
+        }
+
+        fn decode(&self, page_no: u32, page: &mut [u8]) -> Result<()> {
+            let (data, reserved) = page.split_at_mut(page.len() - 4);
+            if reserved != Self::checksum(data) {
+                return Err(err!(
+                    ffi::SQLITE_IOERR_DATA,
+                    "page {page_no}: checksum mismatch"
+                ));
+            }
+            let start = if page_no == 1 { 100 } else { 0 };
+            data[start..]
+                .iter_mut()
+                .for_each(|b| *b ^= page_no as u8 | 0x80);
+            Ok(())
+        }
+    }
+
+    fn open(path: &Path, reserve: bool) -> Result<Connection> {
+        let db = Connection::open_with_flags_and_vfs(path, OpenFlags::default(), "test_transform")?;
+        if reserve {
+            let mut n = Checksum.reserve_bytes().into();
+            db.file_control(Some(MAIN_DB), FileControl::ReserveBytes(&mut n))?;
+        }
+        Ok(db)
+    }
+
+    fn contains(path: &Path, needle: &[u8]) -> bool {
+        fs::read(path)
+            .unwrap()
+            .windows(needle.len())
+            .any(|w| w == needle)
+    }
+
+    #[test]
+    #[cfg_attr(all(target_family = "wasm", target_os = "unknown"), ignore)]
+    fn test_transform() -> Result<()> {
+        static REGISTER: std::sync::Once = std::sync::Once::new();
+        REGISTER.call_once(|| TransformVfs::register("test_transform", Checksum).unwrap());
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // rollback journal
+        let path = temp_dir.path().join("journal.db3");
+        let db = open(&path, true)?;
+        db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES ('hello world');")?;
+        drop(db);
+        assert!(!contains(&path, b"hello world"));
+        let db = open(&path, false)?;
+        let x: String = db.one_column("SELECT x FROM foo", [])?;
+        assert_eq!("hello world", x);
+        drop(db);
+
+        // WAL, with the pages read back from the WAL before the checkpoint
+        let path = temp_dir.path().join("wal.db3");
+        let db = open(&path, true)?;
+        db.pragma_update(None, "journal_mode", "wal")?;
+        db.pragma_update(None, "wal_autocheckpoint", 0)?;
+        db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES ('hello world');")?;
+        let reader = open(&path, false)?;
+        let x: String = reader.one_column("SELECT x FROM foo", [])?;
+        assert_eq!("hello world", x);
+        assert!(!contains(
+            &temp_dir.path().join("wal.db3-wal"),
+            b"hello world"
+        ));
+        db.pragma_update(None, "wal_checkpoint", "TRUNCATE")?;
+        drop((db, reader));
+        assert!(!contains(&path, b"hello world"));
+
+        // corruption is detected
+        let mut data = fs::read(&path).unwrap();
+        let len = data.len();
+        data[len - 10] ^= 1;
+        fs::write(&path, data).unwrap();
+        let db = open(&path, false)?;
+        let err = db
+            .one_column::<String, _>("SELECT x FROM foo", [])
+            .unwrap_err();
+        assert_eq!(
+            Some(ffi::SQLITE_IOERR_DATA),
+            err.sqlite_extended_error_code()
+        );
+        drop(db);
+
+        // no reserved bytes: pages are stored as is
+        let path = temp_dir.path().join("plain.db3");
+        let db = open(&path, false)?;
+        db.execute_batch("CREATE TABLE foo(x); INSERT INTO foo VALUES ('hello world');")?;
+        drop(db);
+        assert!(contains(&path, b"hello world"));
+        Ok(())
+    }
+}
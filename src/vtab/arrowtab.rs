@@ -0,0 +1,632 @@
+//! Arrow Virtual Table.
+//!
+//! Exposes in-memory Arrow [`RecordBatch`]es, passed as pointer parameters
+//! like [`rarray`](crate::vtab::array), as a table-valued function whose
+//! columns are those of an Arrow schema:
+//!
+//! | Arrow                                  | SQLite  |
+//! |----------------------------------------|---------|
+//! | `Boolean`, `Int*`, `UInt*`             | INTEGER |
+//! | `Float32`, `Float64`                   | REAL    |
+//! | `Utf8`, `LargeUtf8`, `Utf8View`        | TEXT    |
+//! | `*Binary`, `BinaryView`                | BLOB    |
+//! | `Null`                                 | NULL    |
+//!
+//! Columns of other types can be declared but not read: a query using one of
+//! them (see [`IndexInfo::col_used`]) fails to prepare. `UInt64` values above
+//! `i64::MAX` fail when read.
+//!
+//! `=`, `<`, `<=`, `>` and `>=` constraints are pushed down to skip rows
+//! before they reach SQLite, which still evaluates them afterwards.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use std::rc::Rc;
+//! # use rusqlite::{Connection, Result};
+//! # use arrow_array::RecordBatch;
+//! fn example(db: &Connection, orders: RecordBatch) -> Result<()> {
+//!     // Note: This should be done once (usually when opening the DB).
+//!     rusqlite::vtab::arrowtab::load_module(db, "orders", orders.schema())?;
+//!     // Note: A `Rc<RecordBatch>` must be used as the parameter.
+//!     let mut stmt = db.prepare(
+//!         "SELECT c.name, sum(o.amount) FROM orders(?1) o \
+//!          JOIN customers c ON c.id = o.customer_id \
+//!          WHERE o.amount > 100 GROUP BY c.name",
+//!     )?;
+//!     let mut rows = stmt.query([Rc::new(orders)])?;
+//!     while let Some(row) = rows.next()? {
+//!         println!("{}: {}", row.get::<_, String>(0)?, row.get::<_, f64>(1)?);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ffi::{CStr, CString, c_int};
+use std::fmt::Write as _;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use arrow_array::cast::AsArray as _;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type, UInt8Type, UInt16Type,
+    UInt32Type, UInt64Type,
+};
+use arrow_array::{Array, RecordBatch};
+use arrow_schema::{DataType, SchemaRef};
+
+use crate::types::{ToSql, ToSqlOutput, Value, ValueRef};
+use crate::vtab::{
+    Context, Filters, IndexConstraintOp, IndexInfo, Module, VTab, VTabConnection, VTabCursor,
+};
+use crate::{Connection, Error, Name, Result, ffi};
+
+// http://sqlite.org/bindptr.html
+
+const BATCH_TYPE: &CStr = c"arrow_batch";
+
+/// Record batch parameter / pointer
+pub type Batch = Rc<RecordBatch>;
+
+impl ToSql for Batch {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from_rc(self.clone(), BATCH_TYPE))
+    }
+}
+
+/// Register a table-valued function named `module_name`, with the columns of
+/// `schema` followed by a hidden `batch` column, which takes the
+/// [`Batch`] to read.
+///
+/// Batches bound to `batch` must have the same fields as `schema`.
+pub fn load_module<M: Name>(conn: &Connection, module_name: M, schema: SchemaRef) -> Result<()> {
+    const MODULE: Module<ArrowTab> = Module::eponymous_only_module();
+    conn.create_module(module_name, &MODULE, Some(schema))
+}
+
+/// An instance of the Arrow virtual table
+#[repr(C)]
+struct ArrowTab {
+    /// Base class. Must be first
+    base: ffi::sqlite3_vtab,
+    schema: SchemaRef,
+}
+
+unsafe impl<'vtab> VTab<'vtab> for ArrowTab {
+    type Aux = SchemaRef;
+    type Cursor = ArrowTabCursor<'vtab>;
+
+    fn connect(
+        _: &mut VTabConnection,
+        aux: Option<&SchemaRef>,
+        _module_name: &[u8],
+        _database_name: &[u8],
+        _table_name: &[u8],
+        args: &[&[u8]],
+    ) -> Result<(Cow<'static, CStr>, Self)> {
+        debug_assert_eq!(args.len(), 0);
+        let schema = aux.unwrap().clone();
+        let mut sql = String::from("CREATE TABLE x(");
+        for field in schema.fields() {
+            let decl_type = decl_type(field.data_type()).unwrap_or_default();
+            write!(
+                sql,
+                "\"{}\" {decl_type},",
+                field.name().replace('"', "\"\"")
+            )
+            .unwrap();
+        }
+        sql.push_str("batch hidden)");
+        let sql = CString::new(sql).map_err(|err| Error::ModuleError(err.to_string()))?;
+        let vtab = Self {
+            base: ffi::sqlite3_vtab::default(),
+            schema,
+        };
+        Ok((Cow::Owned(sql), vtab))
+    }
+
+    fn best_index(&self, info: &mut IndexInfo) -> Result<bool> {
+        let fields = self.schema.fields();
+        let col_used = info.col_used();
+        for (i, field) in fields.iter().enumerate() {
+            // the last bit stands for all the remaining columns
+            let used = col_used & (1 << i.min(63)) != 0;
+            if used && decl_type(field.data_type()).is_none() {
+                return Err(Error::ModuleError(format!(
+                    "column {} of type {} is not supported",
+                    field.name(),
+                    field.data_type()
+                )));
+            }
+        }
+        let batch_column = fields.len() as c_int;
+        // Index of the batch= constraint
+        let mut batch_idx = None;
+        // Pushed down constraints: (constraint index, column, operator)
+        let mut filters = Vec::new();
+        for (i, constraint) in info.constraints().enumerate() {
+            if !constraint.is_usable() {
+                continue;
+            }
+            let column = constraint.column();
+            if column == batch_column {
+                if constraint.operator() == IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_EQ {
+                    batch_idx = Some(i);
+                }
+                continue;
+            }
+            let Some(op) = Op::from(constraint.operator()) else {
+                continue;
+            };
+            let Ok(column) = usize::try_from(column) else {
+                // rowid
+                continue;
+            };
+            let data_type = fields[column].data_type();
+            // text comparisons depend on the collation
+            if decl_type(data_type) == Some("TEXT") && info.collation(i)? != "BINARY" {
+                continue;
+            }
+            filters.push((i, column, op));
+        }
+        let Some(batch_idx) = batch_idx else {
+            info.set_estimated_cost(2_147_483_647_f64);
+            info.set_estimated_rows(2_147_483_647);
+            info.set_idx_num(0);
+            return Ok(true);
+        };
+        let mut constraint_usage = info.constraint_usage(batch_idx);
+        constraint_usage.set_argv_index(1);
+        constraint_usage.set_omit(true);
+        let mut idx_str = String::new();
+        let mut estimated_rows = 1_000_000_f64;
+        for (n_arg, &(i, column, op)) in (2..).zip(&filters) {
+            // SQLite still checks the constraint
+            info.constraint_usage(i).set_argv_index(n_arg);
+            if !idx_str.is_empty() {
+                idx_str.push(',');
+            }
+            write!(idx_str, "{column}{}", op.as_str()).unwrap();
+            estimated_rows /= if op == Op::Eq { 10.0 } else { 2.0 };
+        }
+        info.set_estimated_cost(estimated_rows);
+        info.set_estimated_rows(estimated_rows as i64);
+        info.set_idx_num(1);
+        info.set_idx_str(&idx_str);
+        Ok(true)
+    }
+
+    fn open(&mut self) -> Result<ArrowTabCursor<'_>> {
+        Ok(ArrowTabCursor {
+            base: ffi::sqlite3_vtab_cursor::default(),
+            schema: self.schema.clone(),
+            batch: None,
+            filters: Vec::new(),
+            row: 0,
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// Pushed down comparison operator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Gt,
+    Le,
+    Lt,
+    Ge,
+}
+
+impl Op {
+    fn from(op: IndexConstraintOp) -> Option<Self> {
+        Some(match op {
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_EQ => Self::Eq,
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_GT => Self::Gt,
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_LE => Self::Le,
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_LT => Self::Lt,
+            IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_GE => Self::Ge,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Gt => ">",
+            Self::Le => "<=",
+            Self::Lt => "<",
+            Self::Ge => ">=",
+        }
+    }
+
+    /// Parse "<column><op>", as written by `best_index`
+    fn parse(s: &str) -> Option<(usize, Self)> {
+        let i = s.find(|c: char| !c.is_ascii_digit())?;
+        let (column, op) = s.split_at(i);
+        let op = [Self::Eq, Self::Gt, Self::Le, Self::Lt, Self::Ge]
+            .into_iter()
+            .find(|o| o.as_str() == op)?;
+        Some((column.parse().ok()?, op))
+    }
+
+    /// `false` only if `lhs op rhs` is known to be false. Values which SQLite
+    /// may convert before comparing them are not compared.
+    fn matches(self, lhs: ValueRef<'_>, rhs: ValueRef<'_>) -> bool {
+        // largest range of integers exactly represented by `f64`
+        const EXACT: i64 = 1 << f64::MANTISSA_DIGITS;
+        let ordering = match (lhs, rhs) {
+            (ValueRef::Null, _) | (_, ValueRef::Null) => return false,
+            (ValueRef::Integer(a), ValueRef::Integer(b)) => Some(a.cmp(&b)),
+            (ValueRef::Real(a), ValueRef::Real(b)) => a.partial_cmp(&b),
+            (ValueRef::Integer(a), ValueRef::Real(b)) if (-EXACT..=EXACT).contains(&a) => {
+                (a as f64).partial_cmp(&b)
+            }
+            (ValueRef::Real(a), ValueRef::Integer(b)) if (-EXACT..=EXACT).contains(&b) => {
+                a.partial_cmp(&(b as f64))
+            }
+            (ValueRef::Text(a), ValueRef::Text(b)) | (ValueRef::Blob(a), ValueRef::Blob(b)) => {
+                Some(a.cmp(b))
+            }
+            _ => None,
+        };
+        let Some(ordering) = ordering else {
+            return true;
+        };
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Declared type of columns of type `data_type`, `None` if not supported
+fn decl_type(data_type: &DataType) -> Option<&'static str> {
+    Some(match data_type {
+        DataType::Null => "",
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => "INTEGER",
+        DataType::Float32 | DataType::Float64 => "REAL",
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "TEXT",
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => "BLOB",
+        _ => return None,
+    })
+}
+
+/// Value at `row` of a column of a supported type
+fn value_ref(array: &dyn Array, row: usize) -> Result<ValueRef<'_>> {
+    if array.is_null(row) {
+        return Ok(ValueRef::Null);
+    }
+    Ok(match array.data_type() {
+        DataType::Boolean => ValueRef::Integer(array.as_boolean().value(row).into()),
+        DataType::Int8 => ValueRef::Integer(array.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => ValueRef::Integer(array.as_primitive::<Int16Type>().value(row).into()),
+        DataType::Int32 => ValueRef::Integer(array.as_primitive::<Int32Type>().value(row).into()),
+        DataType::Int64 => ValueRef::Integer(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => ValueRef::Integer(array.as_primitive::<UInt8Type>().value(row).into()),
+        DataType::UInt16 => ValueRef::Integer(array.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => ValueRef::Integer(array.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => {
+            let i = array.as_primitive::<UInt64Type>().value(row);
+            ValueRef::Integer(
+                i.try_into()
+                    .map_err(|err| Error::ToSqlConversionFailure(Box::new(err)))?,
+            )
+        }
+        DataType::Float32 => ValueRef::Real(array.as_primitive::<Float32Type>().value(row).into()),
+        DataType::Float64 => ValueRef::Real(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => ValueRef::Text(array.as_string::<i32>().value(row).as_bytes()),
+        DataType::LargeUtf8 => ValueRef::Text(array.as_string::<i64>().value(row).as_bytes()),
+        DataType::Utf8View => ValueRef::Text(array.as_string_view().value(row).as_bytes()),
+        DataType::Binary => ValueRef::Blob(array.as_binary::<i32>().value(row)),
+        DataType::LargeBinary => ValueRef::Blob(array.as_binary::<i64>().value(row)),
+        DataType::BinaryView => ValueRef::Blob(array.as_binary_view().value(row)),
+        DataType::FixedSizeBinary(_) => ValueRef::Blob(array.as_fixed_size_binary().value(row)),
+        _ => ValueRef::Null,
+    })
+}
+
+/// A cursor for the Arrow virtual table
+#[repr(C)]
+struct ArrowTabCursor<'vtab> {
+    /// Base class. Must be first
+    base: ffi::sqlite3_vtab_cursor,
+    schema: SchemaRef,
+    /// Record batch ("batch")
+    batch: Option<&'vtab RecordBatch>,
+    /// Pushed down constraints
+    filters: Vec<(usize, Op, Value)>,
+    /// Current row index
+    row: usize,
+    phantom: PhantomData<&'vtab ArrowTab>,
+}
+
+impl ArrowTabCursor<'_> {
+    fn len(&self) -> usize {
+        self.batch.map_or(0, RecordBatch::num_rows)
+    }
+
+    /// Move to the first row, from the current one, matching all the
+    /// pushed down constraints
+    fn skip_filtered(&mut self) -> Result<()> {
+        let Some(batch) = self.batch else {
+            return Ok(());
+        };
+        'rows: while self.row < batch.num_rows() {
+            for (column, op, rhs) in &self.filters {
+                let lhs = value_ref(batch.column(*column), self.row)?;
+                if !op.matches(lhs, rhs.into()) {
+                    self.row += 1;
+                    continue 'rows;
+                }
+            }
+            break;
+        }
+        Ok(())
+    }
+}
+
+unsafe impl VTabCursor for ArrowTabCursor<'_> {
+    fn filter(&mut self, idx_num: c_int, idx_str: Option<&str>, args: &Filters<'_>) -> Result<()> {
+        self.batch = None;
+        self.filters.clear();
+        self.row = 0;
+        if idx_num == 0 {
+            return Ok(());
+        }
+        let batch: Option<&RecordBatch> = unsafe { args.get_pointer(0, BATCH_TYPE) };
+        if let Some(batch) = batch
+            && batch.schema_ref().fields() != self.schema.fields()
+        {
+            return Err(Error::ModuleError(format!(
+                "batch schema {} does not match table schema {}",
+                batch.schema_ref(),
+                self.schema
+            )));
+        }
+        for (i, filter) in idx_str
+            .unwrap_or_default()
+            .split_terminator(',')
+            .enumerate()
+        {
+            let (column, op) = Op::parse(filter)
+                .ok_or_else(|| Error::ModuleError(format!("invalid index: {filter}")))?;
+            self.filters.push((column, op, args.get(i + 1)?));
+        }
+        self.batch = batch;
+        self.skip_filtered()
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.row += 1;
+        self.skip_filtered()
+    }
+
+    fn eof(&self) -> bool {
+        self.row >= self.len()
+    }
+
+    fn column(&self, ctx: &mut Context, i: c_int) -> Result<()> {
+        match self.batch {
+            Some(batch) if (i as usize) < batch.num_columns() => ctx.set_result(
+                ToSqlOutput::Borrowed(value_ref(batch.column(i as usize), self.row)?),
+            ),
+            // batch
+            _ => Ok(()),
+        }
+    }
+
+    fn rowid(&self) -> Result<i64> {
+        Ok(self.row as i64 + 1)
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use arrow_array::{
+        ArrayRef, Date32Array, Float64Array, Int64Array, RecordBatch, StringArray, UInt64Array,
+    };
+
+    use super::Op;
+    use crate::types::{Null, ValueRef};
+    use crate::vtab::arrowtab;
+    use crate::{Connection, Result};
+
+    fn orders() -> RecordBatch {
+        RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+            (
+                "customer",
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    None,
+                    Some("a"),
+                ])),
+            ),
+            (
+                "amount",
+                Arc::new(Float64Array::from(vec![
+                    Some(10.0),
+                    Some(250.5),
+                    Some(99.0),
+                    None,
+                ])),
+            ),
+            ("day", Arc::new(Date32Array::from(vec![1, 2, 3, 4]))),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_arrow_module() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let batch = Rc::new(orders());
+        arrowtab::load_module(&db, "orders", batch.schema())?;
+        {
+            let mut stmt = db.prepare("SELECT id, customer, amount FROM orders(?1)")?;
+            let rows = stmt
+                .query_map([&batch], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<f64>>(2)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>>>()?;
+            assert_eq!(2, Rc::strong_count(&batch));
+            assert_eq!(
+                vec![
+                    (1, Some("a".to_owned()), Some(10.0)),
+                    (2, Some("b".to_owned()), Some(250.5)),
+                    (3, None, Some(99.0)),
+                    (4, Some("a".to_owned()), None),
+                ],
+                rows
+            );
+        }
+        assert_eq!(1, Rc::strong_count(&batch));
+
+        let ids = |sql: &str| -> Result<Vec<i64>> {
+            let mut stmt = db.prepare(sql)?;
+            stmt.query_map([&batch], |row| row.get(0))?.collect()
+        };
+        assert_eq!(
+            vec![2, 3],
+            ids("SELECT id FROM orders(?1) WHERE amount > 50")?
+        );
+        assert_eq!(
+            vec![1, 4],
+            ids("SELECT id FROM orders(?1) WHERE customer = 'a'")?
+        );
+        assert_eq!(
+            vec![2],
+            ids("SELECT id FROM orders(?1) WHERE id >= 2 AND id < 4.5 AND amount >= 100")?
+        );
+        // converted by SQLite, not pushed down
+        assert_eq!(vec![3], ids("SELECT id FROM orders(?1) WHERE id = '3'")?);
+        assert_eq!(
+            vec![1, 4],
+            ids("SELECT id FROM orders(?1) WHERE customer = 'A' COLLATE NOCASE")?
+        );
+        assert_eq!(
+            Vec::<i64>::new(),
+            ids("SELECT id FROM orders(?1) WHERE customer = NULL")?
+        );
+        // no batch
+        let n: i64 = db.query_row("SELECT count(*) FROM orders", [], |r| r.get(0))?;
+        assert_eq!(0, n);
+
+        // join with a regular table
+        db.execute_batch(
+            "CREATE TABLE customers (id TEXT PRIMARY KEY, name TEXT);
+             INSERT INTO customers VALUES ('a', 'Alice'), ('b', 'Bob');",
+        )?;
+        let mut stmt = db.prepare(
+            "SELECT c.name, sum(o.amount) FROM customers c JOIN orders(?1) o \
+             ON o.customer = c.id GROUP BY c.name ORDER BY c.name",
+        )?;
+        let totals = stmt
+            .query_map([&batch], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, f64)>>>()?;
+        assert_eq!(
+            vec![("Alice".to_owned(), 10.0), ("Bob".to_owned(), 250.5)],
+            totals
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_best_index() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        arrowtab::load_module(&db, "orders", orders().schema())?;
+        let plan = |sql: &str| -> Result<String> {
+            db.query_row(&format!("EXPLAIN QUERY PLAN {sql}"), [Null], |row| {
+                row.get(3)
+            })
+        };
+        assert!(
+            plan("SELECT id FROM orders(?1) WHERE amount > 50 AND customer = 'a'")?
+                .ends_with("VIRTUAL TABLE INDEX 1:2>,1=")
+        );
+        assert!(plan("SELECT id FROM orders(?1)")?.ends_with("VIRTUAL TABLE INDEX 1:"));
+
+        // unsupported column type, only when used
+        let err = db.prepare("SELECT * FROM orders(?1)").unwrap_err();
+        assert_eq!(
+            "column day of type Date32 is not supported",
+            err.to_string()
+        );
+        db.prepare("SELECT count(*) FROM orders(?1) WHERE day IS NULL")
+            .unwrap_err();
+        db.prepare("SELECT id FROM orders(?1)")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        arrowtab::load_module(&db, "orders", orders().schema())?;
+        let other = RecordBatch::try_from_iter([(
+            "id",
+            Arc::new(UInt64Array::from(vec![1, u64::MAX])) as ArrayRef,
+        )])
+        .unwrap();
+        let r: Result<i64> =
+            db.query_row("SELECT id FROM orders(?1)", [Rc::new(other.clone())], |r| {
+                r.get(0)
+            });
+        assert!(r.unwrap_err().to_string().starts_with("batch schema"));
+
+        arrowtab::load_module(&db, "ids", other.schema())?;
+        let mut stmt = db.prepare("SELECT id FROM ids(?1)")?;
+        let mut rows = stmt.query([Rc::new(other)])?;
+        assert_eq!(1, rows.next()?.unwrap().get::<_, i64>(0)?);
+        assert!(rows.next().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches() {
+        use ValueRef::{Blob, Integer, Null, Real, Text};
+        assert!(Op::Eq.matches(Integer(1), Real(1.0)));
+        assert!(!Op::Eq.matches(Integer(1), Real(1.5)));
+        assert!(Op::Lt.matches(Integer(1), Integer(2)));
+        assert!(!Op::Gt.matches(Integer(1), Integer(2)));
+        assert!(Op::Ge.matches(Real(2.0), Integer(2)));
+        assert!(Op::Le.matches(Text(b"a"), Text(b"b")));
+        assert!(!Op::Eq.matches(Blob(b"a"), Blob(b"b")));
+        assert!(!Op::Eq.matches(Null, Integer(1)));
+        assert!(!Op::Eq.matches(Integer(1), Null));
+        // left to SQLite
+        assert!(Op::Eq.matches(Integer(1), Text(b"1")));
+        assert!(Op::Eq.matches(Integer(i64::MAX), Real(i64::MAX as f64)));
+        assert!(Op::Gt.matches(Text(b"a"), Integer(1)));
+    }
+}
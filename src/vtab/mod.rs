@@ -935,7 +935,9 @@ impl Values<'_> {
             FromSqlError::Other(err) => {
                 Error::FromSqlConversionFailure(idx, value.data_type(), err)
             }
-            FromSqlError::InvalidBlobSize { .. } => {
+            FromSqlError::InvalidBlobSize { .. }
+            | FromSqlError::PrecisionLoss(_)
+            | FromSqlError::Overflow(_) => {
                 Error::FromSqlConversionFailure(idx, value.data_type(), Box::new(err))
             }
             FromSqlError::OutOfRange(i) => Error::IntegralValueOutOfRange(idx, i),
@@ -1587,6 +1589,8 @@ unsafe fn result_error<T>(ctx: *mut sqlite3_context, result: Result<T>) -> c_int
 
 #[cfg(feature = "array")]
 pub mod array;
+#[cfg(feature = "arrowtab")]
+pub mod arrowtab;
 #[cfg(feature = "csvtab")]
 pub mod csvtab;
 #[cfg(feature = "series")]
@@ -0,0 +1,213 @@
+//! Ensure `FromRow`, `Params`, `ToSql` and `FromSql` can be derived outside
+//! `rusqlite` crate.
+#![cfg(feature = "rusqlite-macros")]
+#[cfg(all(target_family = "wasm", target_os = "unknown"))]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+use rusqlite::{Connection, Error, FromRow, Params, Result};
+
+#[derive(Debug, PartialEq, FromRow, Params)]
+struct Address {
+    city: String,
+    #[rusqlite(rename = "zip_code")]
+    zip: Option<String>,
+}
+
+#[derive(Debug, PartialEq, FromRow, Params)]
+struct Person {
+    id: i64,
+    r#type: String,
+    #[rusqlite(rename = "full_name")]
+    name: String,
+    #[rusqlite(skip)]
+    nickname: Option<String>,
+    #[rusqlite(flatten)]
+    address: Address,
+}
+
+#[derive(Debug, PartialEq, FromRow, Params)]
+struct Pair(i64, String);
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Wrapper<T> {
+    value: T,
+    #[rusqlite(default)]
+    missing: i64,
+}
+
+fn db() -> Result<Connection> {
+    let db = Connection::open_in_memory()?;
+    db.execute_batch(
+        "CREATE TABLE person (id INTEGER PRIMARY KEY, type TEXT, full_name TEXT, city TEXT, zip_code TEXT);",
+    )?;
+    Ok(db)
+}
+
+#[test]
+fn test_round_trip() -> Result<()> {
+    let db = db()?;
+    let person = Person {
+        id: 1,
+        r#type: "admin".to_owned(),
+        name: "Lisa Simpson".to_owned(),
+        nickname: Some("Lis".to_owned()),
+        address: Address {
+            city: "Springfield".to_owned(),
+            zip: None,
+        },
+    };
+    db.execute(
+        "INSERT INTO person VALUES (:id, :type, :full_name, :city, :zip_code)",
+        &person,
+    )?;
+    let mut stmt = db.prepare("SELECT * FROM person")?;
+    let people = stmt
+        .query_map([], Person::from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        vec![Person {
+            nickname: None,
+            ..person
+        }],
+        people
+    );
+    Ok(())
+}
+
+#[test]
+fn test_tuple_struct() -> Result<()> {
+    let db = db()?;
+    db.execute(
+        "INSERT INTO person (id, full_name) VALUES (?1, ?2)",
+        &Pair(2, "Bart".to_owned()),
+    )?;
+    let pair = db.query_row("SELECT id, full_name FROM person", [], Pair::from_row)?;
+    assert_eq!(Pair(2, "Bart".to_owned()), pair);
+    Ok(())
+}
+
+#[test]
+fn test_generic() -> Result<()> {
+    let db = Connection::open_in_memory()?;
+    let wrapper = db.query_row("SELECT 'x' AS value", [], Wrapper::<String>::from_row)?;
+    assert_eq!(
+        Wrapper {
+            value: "x".to_owned(),
+            missing: 0
+        },
+        wrapper
+    );
+    Ok(())
+}
+
+#[test]
+fn test_errors() -> Result<()> {
+    let db = db()?;
+    // missing column
+    let err = db
+        .query_row("SELECT 'Springfield' AS city", [], Address::from_row)
+        .unwrap_err();
+    assert_eq!(Error::InvalidColumnName("zip_code".to_owned()), err);
+    // unknown parameter
+    let address = Address {
+        city: "Shelbyville".to_owned(),
+        zip: None,
+    };
+    let err = db
+        .execute("INSERT INTO person (city) VALUES (:city)", &address)
+        .unwrap_err();
+    assert_eq!(Error::InvalidParameterName(":zip_code".to_owned()), err);
+    Ok(())
+}
+
+mod types {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use rusqlite::types::{FromSql, FromSqlError, ToSql, Type};
+    use rusqlite::{Connection, Error, Result};
+
+    #[derive(Debug, PartialEq, FromSql, ToSql)]
+    enum Status {
+        Active,
+        #[rusqlite(rename = "on_hold")]
+        OnHold,
+    }
+
+    #[derive(Debug, PartialEq, FromSql, ToSql)]
+    #[rusqlite(repr = "integer")]
+    enum Priority {
+        Low = 1,
+        High = 10,
+    }
+
+    #[derive(Debug, PartialEq, FromSql, ToSql)]
+    struct UserId(i64);
+
+    #[derive(Debug, PartialEq, FromSql, ToSql)]
+    struct Email {
+        address: String,
+    }
+
+    #[derive(Debug, PartialEq, FromSql, ToSql)]
+    struct Wrapper<T>(T);
+
+    #[test]
+    fn test_enum() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let s: String = db.query_row("SELECT ?1", [Status::OnHold], |r| r.get(0))?;
+        assert_eq!("on_hold", s);
+        let status: Status = db.query_row("SELECT 'Active'", [], |r| r.get(0))?;
+        assert_eq!(Status::Active, status);
+
+        let i: i64 = db.query_row("SELECT ?1", [Priority::High], |r| r.get(0))?;
+        assert_eq!(10, i);
+        let priority: Priority = db.query_row("SELECT 1", [], |r| r.get(0))?;
+        assert_eq!(Priority::Low, priority);
+
+        let err = db
+            .query_row("SELECT 'Inactive'", [], |r| r.get::<_, Status>(0))
+            .unwrap_err();
+        match err {
+            Error::FromSqlConversionFailure(0, Type::Text, err) => {
+                assert_eq!(
+                    "invalid value \"Inactive\" for enum Status",
+                    err.to_string()
+                );
+            }
+            err => panic!("unexpected error {err}"),
+        }
+        let err = db
+            .query_row("SELECT 2", [], |r| r.get::<_, Priority>(0))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FromSqlConversionFailure(0, Type::Integer, _)
+        ));
+        let err = db
+            .query_row("SELECT 1", [], |r| r.get::<_, Status>(0))
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidColumnType(0, _, Type::Integer)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_newtype() -> Result<()> {
+        let db = Connection::open_in_memory()?;
+        let id: UserId = db.query_row("SELECT ?1", [UserId(42)], |r| r.get(0))?;
+        assert_eq!(UserId(42), id);
+        let email = Email {
+            address: "lisa@example.com".to_owned(),
+        };
+        let s: String = db.query_row("SELECT ?1", [&email], |r| r.get(0))?;
+        assert_eq!(email.address, s);
+        let wrapper: Wrapper<f64> = db.query_row("SELECT 0.5", [], |r| r.get(0))?;
+        assert_eq!(Wrapper(0.5), wrapper);
+        assert_eq!(
+            Err(FromSqlError::InvalidType),
+            UserId::column_result("x".into())
+        );
+        assert!(Wrapper(1).to_sql().is_ok());
+        Ok(())
+    }
+}